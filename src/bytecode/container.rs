@@ -1,6 +1,9 @@
-use super::{MAGIC_BYTES, WASM_VERSION};
+use super::{
+    leb128::{signed_leb128, unsigned_leb128},
+    Opcode, SectionId, MAGIC_BYTES, WASM_VERSION,
+};
 
-#[derive(Default)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct BytecodeContainer {
     pub data: Vec<u8>,
 }
@@ -20,6 +23,49 @@ impl BytecodeContainer {
         self.data.extend(bytes);
     }
 
+    pub fn push_u32(&mut self, value: u32) {
+        self.push_bytes(&unsigned_leb128(value as u64));
+    }
+
+    pub fn push_u64(&mut self, value: u64) {
+        self.push_bytes(&unsigned_leb128(value));
+    }
+
+    pub fn push_i32(&mut self, value: i32) {
+        self.push_bytes(&signed_leb128(value as i64));
+    }
+
+    pub fn push_i64(&mut self, value: i64) {
+        self.push_bytes(&signed_leb128(value));
+    }
+
+    /// Strings in the binary format are a length followed by their UTF-8 bytes
+    pub fn push_name(&mut self, name: &str) {
+        self.push_u32(name.len() as u32);
+        self.push_bytes(name.as_bytes());
+    }
+
+    pub fn push_opcode(&mut self, opcode: Opcode) {
+        match opcode {
+            Opcode::Single(byte) => self.push_byte(byte),
+            Opcode::Prefixed(prefix, sub_opcode) => {
+                self.push_byte(prefix);
+                self.push_u32(sub_opcode);
+            }
+        }
+    }
+
+    /// Push the contents of another container prefixed by its size
+    pub fn push_sized(&mut self, contents: &BytecodeContainer) {
+        self.push_u32(contents.data.len() as u32);
+        self.push_bytes(&contents.data);
+    }
+
+    pub fn push_section(&mut self, id: SectionId, contents: &BytecodeContainer) {
+        self.push_byte(id as u8);
+        self.push_sized(contents);
+    }
+
     pub fn new_module() -> Self {
         let mut bytecode = Self::default();
 
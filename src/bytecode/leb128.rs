@@ -0,0 +1,67 @@
+/// Encode an unsigned integer in the variable length LEB128 format
+/// used for every integer in the binary format
+pub fn unsigned_leb128(mut value: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Encode a signed integer in the variable length LEB128 format
+pub fn signed_leb128(mut value: i64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        let sign_bit_clear = byte & 0x40 == 0;
+
+        if (value == 0 && sign_bit_clear) || (value == -1 && !sign_bit_clear) {
+            bytes.push(byte);
+            return bytes;
+        }
+
+        bytes.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{signed_leb128, unsigned_leb128};
+
+    #[test]
+    fn test_unsigned_single_byte() {
+        assert_eq!(unsigned_leb128(0), vec![0x00]);
+        assert_eq!(unsigned_leb128(127), vec![0x7F]);
+    }
+
+    #[test]
+    fn test_unsigned_multi_byte() {
+        assert_eq!(unsigned_leb128(128), vec![0x80, 0x01]);
+        assert_eq!(unsigned_leb128(624485), vec![0xE5, 0x8E, 0x26]);
+    }
+
+    #[test]
+    fn test_signed_negative() {
+        assert_eq!(signed_leb128(-1), vec![0x7F]);
+        assert_eq!(signed_leb128(-64), vec![0x40]);
+        assert_eq!(signed_leb128(-65), vec![0xBF, 0x7F]);
+        assert_eq!(signed_leb128(-123456), vec![0xC0, 0xBB, 0x78]);
+    }
+
+    #[test]
+    fn test_signed_positive_sign_bit() {
+        assert_eq!(signed_leb128(63), vec![0x3F]);
+        assert_eq!(signed_leb128(64), vec![0xC0, 0x00]);
+    }
+}
@@ -1,12 +1,54 @@
 pub mod container;
+pub mod leb128;
 
 pub enum ByteValue {
     I32 = 0x7F,
     I64 = 0x7E,
     F32 = 0x7D,
     F64 = 0x7C,
+    V128 = 0x7B,
+    FuncRef = 0x70,
+    ExternRef = 0x6F,
+    ExnRef = 0x69,
+    /// Packed storage types, which only struct fields and array elements can have
+    I8 = 0x78,
+    I16 = 0x77,
+    NullableRef = 0x63,
+    NonNullableRef = 0x64,
     MUTABLE = 0x01,
     IMMUTABLE = 0x00,
+    EmptyBlock = 0x40,
+    FunctionType = 0x60,
+    StructType = 0x5F,
+    ArrayType = 0x5E,
+    SubType = 0x50,
+    SubFinalType = 0x4F,
+    RecGroup = 0x4E,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionId {
+    Custom = 0,
+    Type = 1,
+    Import = 2,
+    Function = 3,
+    Table = 4,
+    Memory = 5,
+    Global = 6,
+    Export = 7,
+    Start = 8,
+    Element = 9,
+    Code = 10,
+    Data = 11,
+    DataCount = 12,
+    Tag = 13,
+}
+
+/// Opcode of an instruction, prefixed opcodes are followed by a LEB128 encoded sub opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Single(u8),
+    Prefixed(u8, u32),
 }
 
 pub static MAGIC_BYTES: [u8; 4] = [0x00, 0x61, 0x73, 0x6D];
@@ -0,0 +1,246 @@
+use crate::{
+    bytecode::container::BytecodeContainer,
+    diagnostic::{DiagnosticKind, Error},
+    features::errors::DisabledFeatureError,
+    parser::{
+        expression::ExternalKind,
+        instructions::{BuiltinType, HeapType, Instruction, InstructionKind, Mnemonic},
+    },
+    traits::page_position::PageCursor,
+};
+
+use super::{
+    errors::{NonConstantGlobalError, NonConstantInstructionError, TypeMismatchError},
+    types::{encode_heap_type, format_types},
+    BytecodeModule,
+};
+
+/// Validates and encodes an expression that is evaluated on instantiation,
+/// such as the offset of a segment, which has to produce exactly `expected`
+pub fn compile_constant_expression(
+    module: &BytecodeModule,
+    instructions: &[Instruction],
+    expected: BuiltinType,
+    cursor: PageCursor,
+) -> Result<BytecodeContainer, Error> {
+    let mut bytecode = BytecodeContainer::default();
+    let mut operands = Vec::new();
+
+    for instruction in instructions {
+        let mnemonic = instruction.kind.mnemonic();
+
+        if let Some(feature) = required_feature(&instruction.kind)
+            .filter(|feature| !module.options.features.is_enabled(feature))
+        {
+            return Err(Error::from(DisabledFeatureError {
+                construct: format!("`{}` in a constant expression", mnemonic),
+                feature,
+                kind: DiagnosticKind::Validate,
+                cursor: instruction.cursor,
+            }));
+        }
+
+        bytecode.push_opcode(mnemonic.opcode());
+
+        let operand = match &instruction.kind {
+            InstructionKind::I32Const(value) => {
+                bytecode.push_i32(*value);
+
+                BuiltinType::I32
+            }
+            InstructionKind::I64Const(value) => {
+                bytecode.push_i64(*value);
+
+                BuiltinType::I64
+            }
+            InstructionKind::F32Const(bits) => {
+                bytecode.push_bytes(&bits.to_le_bytes());
+
+                BuiltinType::F32
+            }
+            InstructionKind::F64Const(bits) => {
+                bytecode.push_bytes(&bits.to_le_bytes());
+
+                BuiltinType::F64
+            }
+            InstructionKind::V128Const(bytes) => {
+                bytecode.push_bytes(bytes);
+
+                BuiltinType::V128
+            }
+
+            InstructionKind::RefNull(heap_type) => {
+                let reference_type = BuiltinType::reference(true, *heap_type);
+
+                module.check_value_types(&[reference_type], instruction.cursor)?;
+                encode_heap_type(&mut bytecode, *heap_type);
+
+                reference_type
+            }
+            InstructionKind::RefFunc(function) => {
+                let function_index = module.functions.resolve(function, instruction.cursor)?;
+
+                bytecode.push_u32(function_index);
+
+                module.function_reference_type(function_index)
+            }
+
+            InstructionKind::GlobalGet(global) => {
+                let global_index = module.globals.resolve(global, instruction.cursor)?;
+                let global_type = module
+                    .globals
+                    .get(global_index)
+                    .expect("resolved globals should exist");
+
+                // Defined globals are only initialized once their own initializer has run,
+                // which gc allows referring to from the ones that follow
+                let initialized = if module.options.features.gc {
+                    module.imported(ExternalKind::Global) + module.global_initializers.len() as u32
+                } else {
+                    module.imported(ExternalKind::Global)
+                };
+
+                if global_index >= initialized || global_type.mutable {
+                    return Err(Error::from(NonConstantGlobalError {
+                        index: global.clone(),
+                        allows_defined: module.options.features.gc,
+                        cursor: instruction.cursor,
+                    }));
+                }
+
+                bytecode.push_u32(global_index);
+
+                global_type.value_type
+            }
+
+            // Lets offsets be computed from imported globals, such as a base address
+            InstructionKind::Plain(
+                arithmetic @ (Mnemonic::I32Add
+                | Mnemonic::I32Sub
+                | Mnemonic::I32Mul
+                | Mnemonic::I64Add
+                | Mnemonic::I64Sub
+                | Mnemonic::I64Mul),
+            ) => {
+                let (params, results) = arithmetic
+                    .signature()
+                    .expect("arithmetic instructions should have a fixed signature");
+
+                pop_operands(module, &mut operands, params, instruction.cursor)?;
+
+                results[0]
+            }
+
+            // Lets globals hold garbage collected values built up front
+            InstructionKind::Aggregate(
+                allocation @ (Mnemonic::StructNew
+                | Mnemonic::StructNewDefault
+                | Mnemonic::ArrayNew
+                | Mnemonic::ArrayNewDefault),
+                index,
+            ) => {
+                let type_index = module.types.resolve(index, instruction.cursor)?;
+                let params =
+                    module.allocation_params(*allocation, type_index, index, instruction.cursor)?;
+
+                pop_operands(module, &mut operands, &params, instruction.cursor)?;
+                bytecode.push_u32(type_index);
+
+                BuiltinType::reference(false, HeapType::Concrete(type_index))
+            }
+            InstructionKind::ArrayNewFixed(index, length) => {
+                let type_index = module.types.resolve(index, instruction.cursor)?;
+                let element = module.expect_array_type(type_index, index, instruction.cursor)?;
+                let params = vec![element.storage_type.unpacked(); *length as usize];
+
+                pop_operands(module, &mut operands, &params, instruction.cursor)?;
+                bytecode.push_u32(type_index);
+                bytecode.push_u32(*length);
+
+                BuiltinType::reference(false, HeapType::Concrete(type_index))
+            }
+            InstructionKind::Plain(
+                conversion @ (Mnemonic::RefI31
+                | Mnemonic::AnyConvertExtern
+                | Mnemonic::ExternConvertAny),
+            ) => {
+                let operand = operands.last().copied();
+                let (param, result) = module.conversion_signature(*conversion, operand);
+
+                pop_operands(module, &mut operands, &[param], instruction.cursor)?;
+
+                result
+            }
+
+            _ => {
+                return Err(Error::from(NonConstantInstructionError {
+                    instruction: mnemonic,
+                    cursor: instruction.cursor,
+                }))
+            }
+        };
+
+        operands.push(operand);
+    }
+
+    if !matches!(operands[..], [operand] if module.is_subtype(operand, expected)) {
+        return Err(Error::from(TypeMismatchError {
+            expected: format_types(&[expected]),
+            found: format_types(&operands),
+            cursor,
+        }));
+    }
+
+    bytecode.push_opcode(Mnemonic::End.opcode());
+
+    Ok(bytecode)
+}
+
+/// Pops `params` off the end of `operands`, which have to be subtypes of them
+fn pop_operands(
+    module: &BytecodeModule,
+    operands: &mut Vec<BuiltinType>,
+    params: &[BuiltinType],
+    cursor: PageCursor,
+) -> Result<(), TypeMismatchError> {
+    let split = operands.len().saturating_sub(params.len());
+
+    if !module.are_subtypes(&operands[split..], params) {
+        return Err(TypeMismatchError {
+            expected: format_types(params),
+            found: format_types(operands.iter()),
+            cursor,
+        });
+    }
+
+    operands.truncate(split);
+
+    Ok(())
+}
+
+/// Proposals that made an instruction constant, which it isn't allowed in
+/// constant expressions without
+fn required_feature(kind: &InstructionKind) -> Option<&'static str> {
+    match kind {
+        InstructionKind::Plain(
+            Mnemonic::I32Add
+            | Mnemonic::I32Sub
+            | Mnemonic::I32Mul
+            | Mnemonic::I64Add
+            | Mnemonic::I64Sub
+            | Mnemonic::I64Mul,
+        ) => Some("extended-const"),
+        InstructionKind::Aggregate(
+            Mnemonic::StructNew
+            | Mnemonic::StructNewDefault
+            | Mnemonic::ArrayNew
+            | Mnemonic::ArrayNewDefault,
+            _,
+        )
+        | InstructionKind::ArrayNewFixed(..)
+        | InstructionKind::Plain(
+            Mnemonic::RefI31 | Mnemonic::AnyConvertExtern | Mnemonic::ExternConvertAny,
+        ) => Some("gc"),
+        _ => None,
+    }
+}
@@ -1,4 +1,6 @@
 use crate::{
+    parser::instructions::Mnemonic,
+    shared::{Identifier, Index},
     source::Source,
     traits::{error_display::ErrorDisplay, page_position::PageCursor},
 };
@@ -17,3 +19,415 @@ impl ErrorDisplay for MisplacedError {
         format!("Misplaced identifier in:\n{}", position)
     }
 }
+
+#[derive(Debug)]
+pub struct DuplicateIdentifierError {
+    pub id: Identifier,
+    pub cursor: PageCursor,
+    /// Where the identifier was first defined
+    pub previous: PageCursor,
+}
+
+impl ErrorDisplay for DuplicateIdentifierError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        let previous = source
+            .display_position(self.previous)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Duplicate identifier {} in line:\n{}\nFirst defined in line:\n{}",
+            self.id, position, previous
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct DuplicateExportError {
+    pub name: String,
+    pub cursor: PageCursor,
+    /// Where the name was first exported
+    pub previous: PageCursor,
+}
+
+impl ErrorDisplay for DuplicateExportError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        let previous = source
+            .display_position(self.previous)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Duplicate export \"{}\" in line:\n{}\nFirst exported in line:\n{}",
+            self.name.escape_debug(),
+            position,
+            previous
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownIndexError {
+    pub kind: &'static str,
+    pub index: Index,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for UnknownIndexError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Unknown {} {} in line:\n{}",
+            self.kind, self.index, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct TypeMismatchError {
+    pub expected: String,
+    pub found: String,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for TypeMismatchError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Type mismatch, expected {} but found {} in line:\n{}",
+            self.expected, self.found, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct MisplacedInstructionError {
+    pub instruction: Mnemonic,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for MisplacedInstructionError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Misplaced instruction `{}` in line:\n{}",
+            self.instruction, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct MismatchedLabelError {
+    pub label: Identifier,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for MismatchedLabelError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Label {} does not match the enclosing block in line:\n{}",
+            self.label, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidLimitsError {
+    pub reason: String,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for InvalidLimitsError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!("Invalid limits, {} in line:\n{}", self.reason, position)
+    }
+}
+
+#[derive(Debug)]
+pub struct MultipleDefinitionsError {
+    pub kind: &'static str,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for MultipleDefinitionsError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "A module can define at most one {}, found another in line:\n{}",
+            self.kind, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidAlignmentError {
+    pub instruction: Mnemonic,
+    pub alignment: u64,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for InvalidAlignmentError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        if !self.alignment.is_power_of_two() {
+            return format!(
+                "Alignment {} is not a power of two in line:\n{}",
+                self.alignment, position
+            );
+        }
+
+        if self.instruction.is_atomic() {
+            return format!(
+                "Alignment {} differs from the natural alignment of `{}`, which atomic instructions require in line:\n{}",
+                self.alignment, self.instruction, position
+            );
+        }
+
+        format!(
+            "Alignment {} exceeds the natural alignment of `{}` in line:\n{}",
+            self.alignment, self.instruction, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidLaneError {
+    pub instruction: Mnemonic,
+    pub lane: u8,
+    pub lanes: u8,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for InvalidLaneError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Lane {} is out of range for `{}`, which has {} lanes in line:\n{}",
+            self.lane, self.instruction, self.lanes, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct NonConstantInstructionError {
+    pub instruction: Mnemonic,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for NonConstantInstructionError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Instruction `{}` is not allowed in a constant expression in line:\n{}",
+            self.instruction, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct NonConstantGlobalError {
+    pub index: Index,
+    /// Whether defined globals are readable too, as long as they come first
+    pub allows_defined: bool,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for NonConstantGlobalError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        let readable = if self.allows_defined {
+            "immutable globals imported or defined before it"
+        } else {
+            "imported immutable globals"
+        };
+
+        format!(
+            "Global {} can't be read in a constant expression, only {} can in line:\n{}",
+            self.index, readable, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct ImmutableGlobalError {
+    pub index: Index,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for ImmutableGlobalError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Global {} is immutable and can't be set in line:\n{}",
+            self.index, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct UndeclaredReferenceError {
+    pub index: Index,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for UndeclaredReferenceError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Function {} has to be declared in an element segment, global or export before it can be referenced in line:\n{}",
+            self.index, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct ImportAfterDefinitionError {
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for ImportAfterDefinitionError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Imports have to come before any function, table, memory or global definitions in line:\n{}",
+            position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct SignatureMismatchError {
+    pub index: Index,
+    /// Signature of the referenced type
+    pub expected: String,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for SignatureMismatchError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Inline signature doesn't match type {}, which is {} in line:\n{}",
+            self.index, self.expected, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidSubtypeError {
+    pub index: Index,
+    pub supertype: Index,
+    /// Why the type can't extend its supertype, already formatted for the message
+    pub reason: String,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for InvalidSubtypeError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Type {} can't be a subtype of {}, {} in line:\n{}",
+            self.index, self.supertype, self.reason, position
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct ImmutableFieldError {
+    /// Type of the struct or array
+    pub index: Index,
+    /// Field of a struct, arrays only have their elements
+    pub field: Option<Index>,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for ImmutableFieldError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        match &self.field {
+            Some(field) => format!(
+                "Field {} of type {} is immutable and can't be set in line:\n{}",
+                field, self.index, position
+            ),
+            None => format!(
+                "Elements of type {} are immutable and can't be set in line:\n{}",
+                self.index, position
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UninitializedLocalError {
+    pub index: Index,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for UninitializedLocalError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("compiler error should point to valid position");
+
+        format!(
+            "Local {} is read before it is set in line:\n{}",
+            self.index, position
+        )
+    }
+}
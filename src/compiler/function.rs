@@ -0,0 +1,1738 @@
+use crate::{
+    bytecode::{container::BytecodeContainer, ByteValue, Opcode},
+    diagnostic::{DiagnosticKind, Error},
+    features::errors::DisabledFeatureError,
+    parser::{
+        errors::ConstantOutOfRangeError,
+        expression::{FunctionDefinition, StorageType},
+        instructions::{
+            BlockType, BuiltinType, CatchClause, CatchKind, HeapType, Instruction, InstructionKind,
+            MemArg, Mnemonic, RefType,
+        },
+    },
+    shared::{Identifier, Index},
+    traits::page_position::PageCursor,
+};
+
+use super::{
+    errors::{
+        ImmutableFieldError, ImmutableGlobalError, InvalidAlignmentError, InvalidLaneError,
+        MismatchedLabelError, MisplacedInstructionError, TypeMismatchError,
+        UndeclaredReferenceError, UninitializedLocalError, UnknownIndexError,
+    },
+    index_space::IndexSpace,
+    types::{encode_heap_type, encode_value_type, format_types, FieldType, FunctionType},
+    BytecodeModule,
+};
+
+/// Operand types as tracked during validation, [None] being the unknown
+/// type produced by popping from an unreachable stack
+type Operand = Option<BuiltinType>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FrameKind {
+    Function,
+    Block,
+    Loop,
+    If,
+    Else,
+}
+
+#[derive(Debug)]
+struct ControlFrame {
+    kind: FrameKind,
+    label: Option<Identifier>,
+    start_types: Vec<BuiltinType>,
+    end_types: Vec<BuiltinType>,
+    height: usize,
+    /// Number of locals set inside of enclosing frames, which stay set after this one ends
+    initialized_height: usize,
+    unreachable: bool,
+}
+
+impl ControlFrame {
+    /// Branching to a loop jumps back to its start, every other block is exited
+    fn label_types(&self) -> &[BuiltinType] {
+        match self.kind {
+            FrameKind::Loop => &self.start_types,
+            _ => &self.end_types,
+        }
+    }
+}
+
+/// Validates a function body against the operand stack rules of the spec
+/// while emitting its code
+pub struct FunctionCompiler<'a> {
+    module: &'a BytecodeModule,
+    definition: &'a FunctionDefinition,
+    locals: IndexSpace<BuiltinType>,
+    /// Locals without a default value that have been set, in the order they were set
+    initialized: Vec<u32>,
+    operands: Vec<Operand>,
+    controls: Vec<ControlFrame>,
+    code: BytecodeContainer,
+}
+
+impl<'a> FunctionCompiler<'a> {
+    pub fn new(
+        module: &'a BytecodeModule,
+        definition: &'a FunctionDefinition,
+    ) -> Result<Self, Error> {
+        let type_index = module.lookup_type_use(&definition.type_use, definition.cursor)?;
+        let function_type = module.function_type_at(type_index);
+
+        let mut locals = IndexSpace::new("local");
+
+        for (position, param_type) in function_type.params.iter().enumerate() {
+            let id = definition
+                .type_use
+                .signature
+                .params
+                .get(position)
+                .and_then(|param| param.id.clone());
+
+            locals.push(id, *param_type, definition.cursor)?;
+        }
+
+        for local in &definition.locals {
+            module.check_value_types(&[local.local_type], definition.cursor)?;
+            locals.push(local.id.clone(), local.local_type, definition.cursor)?;
+        }
+
+        let mut compiler = Self {
+            module,
+            definition,
+            locals,
+            // Params are set by the caller
+            initialized: (0..function_type.params.len() as u32).collect(),
+            operands: Vec::new(),
+            controls: Vec::new(),
+            code: BytecodeContainer::default(),
+        };
+
+        compiler.push_control(
+            FrameKind::Function,
+            None,
+            Vec::new(),
+            function_type.results.clone(),
+        );
+
+        Ok(compiler)
+    }
+
+    pub fn compile(mut self) -> Result<BytecodeContainer, Error> {
+        for instruction in &self.definition.body {
+            self.compile_instruction(instruction)?;
+        }
+
+        let end_cursor = self
+            .definition
+            .body
+            .last()
+            .map_or(self.definition.cursor, |instruction| instruction.cursor);
+
+        self.pop_control(end_cursor)?;
+        self.code.push_opcode(Mnemonic::End.opcode());
+
+        let mut body = BytecodeContainer::default();
+
+        self.encode_locals(&mut body);
+        body.push_bytes(&self.code.data);
+
+        Ok(body)
+    }
+
+    /// Locals are encoded as runs of consecutive locals sharing a type
+    fn encode_locals(&self, body: &mut BytecodeContainer) {
+        let mut runs: Vec<(u32, BuiltinType)> = Vec::new();
+
+        for local in &self.definition.locals {
+            match runs.last_mut() {
+                Some((count, local_type)) if *local_type == local.local_type => *count += 1,
+                _ => runs.push((1, local.local_type)),
+            }
+        }
+
+        body.push_u32(runs.len() as u32);
+
+        for (count, local_type) in runs {
+            body.push_u32(count);
+            encode_value_type(body, local_type);
+        }
+    }
+
+    fn compile_instruction(&mut self, instruction: &Instruction) -> Result<(), Error> {
+        let cursor = instruction.cursor;
+        let mnemonic = instruction.kind.mnemonic();
+
+        let features = &self.module.options.features;
+
+        if let Some(feature) = required_features(&instruction.kind)
+            .into_iter()
+            .find(|feature| !features.is_enabled(feature))
+        {
+            return Err(Error::from(DisabledFeatureError {
+                construct: format!("`{}`", mnemonic),
+                feature,
+                kind: DiagnosticKind::Validate,
+                cursor,
+            }));
+        }
+
+        match &instruction.kind {
+            InstructionKind::Block(label, block_type)
+            | InstructionKind::Loop(label, block_type) => {
+                let kind = if mnemonic == Mnemonic::Block {
+                    FrameKind::Block
+                } else {
+                    FrameKind::Loop
+                };
+
+                self.code.push_opcode(mnemonic.opcode());
+
+                let block_signature = self.encode_block_type(block_type, cursor)?;
+
+                self.pop_operands(&block_signature.params, cursor)?;
+                self.push_control(
+                    kind,
+                    label.clone(),
+                    block_signature.params,
+                    block_signature.results,
+                );
+            }
+
+            InstructionKind::If(label, block_type) => {
+                self.pop_expected(BuiltinType::I32, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+
+                let block_signature = self.encode_block_type(block_type, cursor)?;
+
+                self.pop_operands(&block_signature.params, cursor)?;
+                self.push_control(
+                    FrameKind::If,
+                    label.clone(),
+                    block_signature.params,
+                    block_signature.results,
+                );
+            }
+
+            InstructionKind::TryTable(label, block_type, catches) => {
+                self.code.push_opcode(mnemonic.opcode());
+
+                let block_signature = self.encode_block_type(block_type, cursor)?;
+
+                self.code.push_u32(catches.len() as u32);
+
+                // Catch labels are relative to the blocks around the `try_table`, not inside it
+                for catch in catches {
+                    self.compile_catch(catch, cursor)?;
+                }
+
+                self.pop_operands(&block_signature.params, cursor)?;
+                self.push_control(
+                    FrameKind::Block,
+                    label.clone(),
+                    block_signature.params,
+                    block_signature.results,
+                );
+            }
+
+            InstructionKind::Else(label) => {
+                if self.controls.last().map(|frame| frame.kind) != Some(FrameKind::If) {
+                    return Err(Error::from(MisplacedInstructionError {
+                        instruction: mnemonic,
+                        cursor,
+                    }));
+                }
+
+                let frame = self.pop_control(cursor)?;
+                check_label(label, &frame, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.push_control(
+                    FrameKind::Else,
+                    frame.label,
+                    frame.start_types,
+                    frame.end_types,
+                );
+            }
+
+            InstructionKind::End(label) => {
+                if self.controls.len() <= 1 {
+                    return Err(Error::from(MisplacedInstructionError {
+                        instruction: mnemonic,
+                        cursor,
+                    }));
+                }
+
+                let frame = self.pop_control(cursor)?;
+                check_label(label, &frame, cursor)?;
+
+                // Without an else branch the inputs of the block are passed through unchanged
+                if frame.kind == FrameKind::If && frame.start_types != frame.end_types {
+                    return Err(Error::from(TypeMismatchError {
+                        expected: format_types(&frame.end_types),
+                        found: format_types(&frame.start_types),
+                        cursor,
+                    }));
+                }
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.push_operands(&frame.end_types);
+            }
+
+            InstructionKind::Br(label) => {
+                let depth = self.resolve_label(label, cursor)?;
+                let label_types = self.label_types(depth);
+
+                self.pop_operands(&label_types, cursor)?;
+                self.set_unreachable();
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(depth);
+            }
+
+            InstructionKind::BrIf(label) => {
+                let depth = self.resolve_label(label, cursor)?;
+                let label_types = self.label_types(depth);
+
+                self.pop_expected(BuiltinType::I32, cursor)?;
+                self.pop_operands(&label_types, cursor)?;
+                self.push_operands(&label_types);
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(depth);
+            }
+
+            InstructionKind::BrTable(labels, default) => {
+                self.pop_expected(BuiltinType::I32, cursor)?;
+
+                let default_depth = self.resolve_label(default, cursor)?;
+                let default_types = self.label_types(default_depth);
+
+                let mut depths = Vec::new();
+
+                for label in labels {
+                    let depth = self.resolve_label(label, cursor)?;
+                    let label_types = self.label_types(depth);
+
+                    if label_types.len() != default_types.len() {
+                        return Err(Error::from(TypeMismatchError {
+                            expected: format_types(&default_types),
+                            found: format_types(&label_types),
+                            cursor,
+                        }));
+                    }
+
+                    let popped = self.pop_operands(&label_types, cursor)?;
+                    self.operands.extend(popped);
+
+                    depths.push(depth);
+                }
+
+                self.pop_operands(&default_types, cursor)?;
+                self.set_unreachable();
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(depths.len() as u32);
+
+                for depth in depths {
+                    self.code.push_u32(depth);
+                }
+
+                self.code.push_u32(default_depth);
+            }
+
+            InstructionKind::Throw(tag) => {
+                let tag_index = self.module.tags.resolve(tag, cursor)?;
+                let tag_type = self.module.tag_type(tag_index);
+
+                self.pop_operands(&tag_type.params, cursor)?;
+                self.set_unreachable();
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(tag_index);
+            }
+
+            InstructionKind::Call(function) | InstructionKind::ReturnCall(function) => {
+                let function_index = self.module.functions.resolve(function, cursor)?;
+                let function_type = self.module.function_type(function_index);
+
+                self.pop_operands(&function_type.params, cursor)?;
+                self.push_call_results(mnemonic, &function_type.results, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(function_index);
+            }
+
+            InstructionKind::CallIndirect(table, type_use)
+            | InstructionKind::ReturnCallIndirect(table, type_use) => {
+                let table_index = self.module.tables.resolve(table, cursor)?;
+                let table_type = self
+                    .module
+                    .tables
+                    .get(table_index)
+                    .expect("resolved tables should exist");
+
+                if !self
+                    .module
+                    .is_subtype(table_type.element_type, BuiltinType::FuncRef)
+                {
+                    return Err(Error::from(TypeMismatchError {
+                        expected: format_types(&[BuiltinType::FuncRef]),
+                        found: format_types(&[table_type.element_type]),
+                        cursor,
+                    }));
+                }
+
+                let type_index = self.module.lookup_type_use(type_use, cursor)?;
+                let function_type = self.module.function_type_at(type_index);
+
+                self.pop_expected(BuiltinType::I32, cursor)?;
+                self.pop_operands(&function_type.params, cursor)?;
+                self.push_call_results(mnemonic, &function_type.results, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(type_index);
+                self.code.push_u32(table_index);
+            }
+
+            InstructionKind::CallRef(index) | InstructionKind::ReturnCallRef(index) => {
+                let type_index = self.module.types.resolve(index, cursor)?;
+                let function_type = self
+                    .module
+                    .expect_function_type(type_index, index, cursor)?;
+
+                self.pop_expected(
+                    BuiltinType::reference(true, HeapType::Concrete(type_index)),
+                    cursor,
+                )?;
+                self.pop_operands(&function_type.params, cursor)?;
+                self.push_call_results(mnemonic, &function_type.results, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(type_index);
+            }
+
+            // Only non-null references pass on, so they lose their null
+            InstructionKind::BrOnNull(label) => {
+                let depth = self.resolve_label(label, cursor)?;
+                let label_types = self.label_types(depth);
+                let reference = self.pop_reference(cursor)?;
+
+                self.pop_operands(&label_types, cursor)?;
+                self.push_operands(&label_types);
+                self.operands.push(
+                    reference.map(|reference| BuiltinType::reference(false, reference.heap_type)),
+                );
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(depth);
+            }
+
+            InstructionKind::BrOnNonNull(label) => {
+                let depth = self.resolve_label(label, cursor)?;
+                let label_types = self.label_types(depth);
+                let reference = self.pop_reference(cursor)?;
+
+                check_reference_label(&label_types, cursor)?;
+
+                // The branch takes the reference along, so it is checked against the label
+                self.operands.push(
+                    reference.map(|reference| BuiltinType::reference(false, reference.heap_type)),
+                );
+                self.pop_operands(&label_types, cursor)?;
+                self.push_operands(&label_types[..label_types.len() - 1]);
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(depth);
+            }
+
+            InstructionKind::BrOnCast(mnemonic, label, input, target) => {
+                self.module.check_value_types(&[*input, *target], cursor)?;
+
+                let (Some(input_reference), Some(target_reference)) =
+                    (input.as_reference(), target.as_reference())
+                else {
+                    return Err(Error::from(TypeMismatchError {
+                        expected: "reference types".to_owned(),
+                        found: format_types(&[*input, *target]),
+                        cursor,
+                    }));
+                };
+
+                if !self.module.is_subtype(*target, *input) {
+                    return Err(Error::from(TypeMismatchError {
+                        expected: format!("a subtype of {}", input),
+                        found: format_types(&[*target]),
+                        cursor,
+                    }));
+                }
+
+                let depth = self.resolve_label(label, cursor)?;
+                let label_types = self.label_types(depth);
+
+                check_reference_label(&label_types, cursor)?;
+
+                // References that fail the cast can't be null if the target accepts null
+                let difference = BuiltinType::reference(
+                    input_reference.nullable && !target_reference.nullable,
+                    input_reference.heap_type,
+                );
+                let (branch, fallthrough) = match mnemonic {
+                    Mnemonic::BrOnCast => (*target, difference),
+                    _ => (difference, *target),
+                };
+
+                self.pop_expected(*input, cursor)?;
+                self.operands.push(Some(branch));
+                self.pop_operands(&label_types, cursor)?;
+                self.push_operands(&label_types[..label_types.len() - 1]);
+                self.operands.push(Some(fallthrough));
+
+                // The first two bits tell whether the input and the target types are nullable
+                let flags = input_reference.nullable as u8 | (target_reference.nullable as u8) << 1;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_byte(flags);
+                self.code.push_u32(depth);
+                encode_heap_type(&mut self.code, input_reference.heap_type);
+                encode_heap_type(&mut self.code, target_reference.heap_type);
+            }
+
+            InstructionKind::RefCast(mnemonic, target) => {
+                self.module.check_value_types(&[*target], cursor)?;
+
+                let Some(target_reference) = target.as_reference() else {
+                    return Err(Error::from(TypeMismatchError {
+                        expected: "a reference type".to_owned(),
+                        found: format_types(&[*target]),
+                        cursor,
+                    }));
+                };
+
+                self.pop_reference(cursor)?;
+
+                if *mnemonic == Mnemonic::RefTest {
+                    self.operands.push(Some(BuiltinType::I32));
+                } else {
+                    self.operands.push(Some(*target));
+                }
+
+                let Opcode::Prefixed(prefix, opcode) = mnemonic.opcode() else {
+                    unreachable!("casts should be prefixed instructions");
+                };
+
+                self.code.push_opcode(Opcode::Prefixed(
+                    prefix,
+                    opcode + target_reference.nullable as u32,
+                ));
+                encode_heap_type(&mut self.code, target_reference.heap_type);
+            }
+
+            InstructionKind::Aggregate(mnemonic, index) => {
+                let type_index = self.module.types.resolve(index, cursor)?;
+                let reference = BuiltinType::reference(true, HeapType::Concrete(type_index));
+
+                match mnemonic {
+                    Mnemonic::StructNew
+                    | Mnemonic::StructNewDefault
+                    | Mnemonic::ArrayNew
+                    | Mnemonic::ArrayNewDefault => {
+                        let params = self
+                            .module
+                            .allocation_params(*mnemonic, type_index, index, cursor)?;
+
+                        self.pop_operands(&params, cursor)?;
+                        self.operands.push(Some(BuiltinType::reference(
+                            false,
+                            HeapType::Concrete(type_index),
+                        )));
+                    }
+                    Mnemonic::ArrayGet | Mnemonic::ArrayGetS | Mnemonic::ArrayGetU => {
+                        let element = self.module.expect_array_type(type_index, index, cursor)?;
+
+                        check_packed_access(*mnemonic, element, cursor)?;
+                        self.pop_operands(&[reference, BuiltinType::I32], cursor)?;
+                        self.operands.push(Some(element.storage_type.unpacked()));
+                    }
+                    _ => {
+                        let element = self.module.expect_array_type(type_index, index, cursor)?;
+                        let value_type = element.storage_type.unpacked();
+
+                        check_mutable(element, index, None, cursor)?;
+
+                        let params = match mnemonic {
+                            Mnemonic::ArraySet => vec![reference, BuiltinType::I32, value_type],
+                            _ => vec![reference, BuiltinType::I32, value_type, BuiltinType::I32],
+                        };
+
+                        self.pop_operands(&params, cursor)?;
+                    }
+                }
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(type_index);
+            }
+
+            InstructionKind::StructField(mnemonic, index, field) => {
+                let type_index = self.module.types.resolve(index, cursor)?;
+                let fields = self.module.expect_struct_type(type_index, index, cursor)?;
+                let field_index = self.resolve_field(type_index, fields, field, cursor)?;
+                let field_type = fields[field_index as usize];
+                let reference = BuiltinType::reference(true, HeapType::Concrete(type_index));
+
+                if *mnemonic == Mnemonic::StructSet {
+                    check_mutable(field_type, index, Some(field), cursor)?;
+                    self.pop_operands(&[reference, field_type.storage_type.unpacked()], cursor)?;
+                } else {
+                    check_packed_access(*mnemonic, field_type, cursor)?;
+                    self.pop_expected(reference, cursor)?;
+                    self.operands.push(Some(field_type.storage_type.unpacked()));
+                }
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(type_index);
+                self.code.push_u32(field_index);
+            }
+
+            InstructionKind::ArrayNewFixed(index, length) => {
+                let type_index = self.module.types.resolve(index, cursor)?;
+                let element = self.module.expect_array_type(type_index, index, cursor)?;
+
+                self.pop_operands(
+                    &vec![element.storage_type.unpacked(); *length as usize],
+                    cursor,
+                )?;
+                self.operands.push(Some(BuiltinType::reference(
+                    false,
+                    HeapType::Concrete(type_index),
+                )));
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(type_index);
+                self.code.push_u32(*length);
+            }
+
+            InstructionKind::ArraySegment(mnemonic, index, segment) => {
+                let type_index = self.module.types.resolve(index, cursor)?;
+                let element = self.module.expect_array_type(type_index, index, cursor)?;
+                let element_type = element.storage_type.unpacked();
+                let is_data = matches!(mnemonic, Mnemonic::ArrayNewData | Mnemonic::ArrayInitData);
+
+                // Data segments hold bytes, which can only be turned into numbers
+                let segment_index = if is_data {
+                    if element_type.is_reference() {
+                        return Err(Error::from(TypeMismatchError {
+                            expected: "an array of numbers".to_owned(),
+                            found: format_types(&[element_type]),
+                            cursor,
+                        }));
+                    }
+
+                    self.module.data.resolve(segment, cursor)?
+                } else {
+                    let segment_index = self.module.elements.resolve(segment, cursor)?;
+                    let segment_type = self
+                        .module
+                        .elements
+                        .get(segment_index)
+                        .expect("resolved element segments should exist")
+                        .element_type;
+
+                    if !self.module.is_subtype(segment_type, element_type) {
+                        return Err(Error::from(TypeMismatchError {
+                            expected: format_types(&[element_type]),
+                            found: format_types(&[segment_type]),
+                            cursor,
+                        }));
+                    }
+
+                    segment_index
+                };
+
+                if matches!(mnemonic, Mnemonic::ArrayNewData | Mnemonic::ArrayNewElem) {
+                    self.pop_operands(&[BuiltinType::I32, BuiltinType::I32], cursor)?;
+                    self.operands.push(Some(BuiltinType::reference(
+                        false,
+                        HeapType::Concrete(type_index),
+                    )));
+                } else {
+                    check_mutable(element, index, None, cursor)?;
+                    self.pop_operands(
+                        &[
+                            BuiltinType::reference(true, HeapType::Concrete(type_index)),
+                            BuiltinType::I32,
+                            BuiltinType::I32,
+                            BuiltinType::I32,
+                        ],
+                        cursor,
+                    )?;
+                }
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(type_index);
+                self.code.push_u32(segment_index);
+            }
+
+            InstructionKind::ArrayCopy(destination, source) => {
+                let destination_index = self.module.types.resolve(destination, cursor)?;
+                let source_index = self.module.types.resolve(source, cursor)?;
+                let destination_element =
+                    self.module
+                        .expect_array_type(destination_index, destination, cursor)?;
+                let source_element = self
+                    .module
+                    .expect_array_type(source_index, source, cursor)?;
+
+                check_mutable(destination_element, destination, None, cursor)?;
+
+                let is_compatible = match (
+                    destination_element.storage_type,
+                    source_element.storage_type,
+                ) {
+                    (StorageType::Value(destination_type), StorageType::Value(source_type)) => {
+                        self.module.is_subtype(source_type, destination_type)
+                    }
+                    (destination_type, source_type) => destination_type == source_type,
+                };
+
+                if !is_compatible {
+                    return Err(Error::from(TypeMismatchError {
+                        expected: format!("elements of {}", destination_element.storage_type),
+                        found: format!("elements of {}", source_element.storage_type),
+                        cursor,
+                    }));
+                }
+
+                self.pop_operands(
+                    &[
+                        BuiltinType::reference(true, HeapType::Concrete(destination_index)),
+                        BuiltinType::I32,
+                        BuiltinType::reference(true, HeapType::Concrete(source_index)),
+                        BuiltinType::I32,
+                        BuiltinType::I32,
+                    ],
+                    cursor,
+                )?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(destination_index);
+                self.code.push_u32(source_index);
+            }
+
+            InstructionKind::LocalGet(local) => {
+                let (local_index, local_type) = self.resolve_local(local, cursor)?;
+
+                if !local_type.is_defaultable() && !self.initialized.contains(&local_index) {
+                    return Err(Error::from(UninitializedLocalError {
+                        index: local.clone(),
+                        cursor,
+                    }));
+                }
+
+                self.operands.push(Some(local_type));
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(local_index);
+            }
+
+            InstructionKind::LocalSet(local) => {
+                let (local_index, local_type) = self.resolve_local(local, cursor)?;
+
+                self.pop_expected(local_type, cursor)?;
+                self.initialize_local(local_index, local_type);
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(local_index);
+            }
+
+            InstructionKind::LocalTee(local) => {
+                let (local_index, local_type) = self.resolve_local(local, cursor)?;
+
+                self.pop_expected(local_type, cursor)?;
+                self.initialize_local(local_index, local_type);
+                self.operands.push(Some(local_type));
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(local_index);
+            }
+
+            InstructionKind::GlobalGet(global) => {
+                let global_index = self.module.globals.resolve(global, cursor)?;
+                let global_type = self.module.global_type(global_index);
+
+                self.operands.push(Some(global_type.value_type));
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(global_index);
+            }
+
+            InstructionKind::GlobalSet(global) => {
+                let global_index = self.module.globals.resolve(global, cursor)?;
+                let global_type = self.module.global_type(global_index);
+
+                if !global_type.mutable {
+                    return Err(Error::from(ImmutableGlobalError {
+                        index: global.clone(),
+                        cursor,
+                    }));
+                }
+
+                self.pop_expected(global_type.value_type, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(global_index);
+            }
+
+            InstructionKind::Memory(mnemonic, memory, memarg) => {
+                let memory_index = self.module.memories.resolve(memory, cursor)?;
+                let (alignment, offset) =
+                    self.check_memarg(*mnemonic, memory_index, memarg, cursor)?;
+
+                self.apply_memory_signature(*mnemonic, memory_index, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.push_memarg(memory_index, alignment, offset);
+            }
+
+            InstructionKind::MemoryLane(mnemonic, memory, memarg, lane) => {
+                let memory_index = self.module.memories.resolve(memory, cursor)?;
+                let (alignment, offset) =
+                    self.check_memarg(*mnemonic, memory_index, memarg, cursor)?;
+
+                check_lane(*mnemonic, *lane, cursor)?;
+                self.apply_memory_signature(*mnemonic, memory_index, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.push_memarg(memory_index, alignment, offset);
+                self.code.push_byte(*lane);
+            }
+
+            InstructionKind::MemorySize(memory) | InstructionKind::MemoryGrow(memory) => {
+                let memory_index = self.module.memories.resolve(memory, cursor)?;
+
+                self.apply_memory_signature(mnemonic, memory_index, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(memory_index);
+            }
+
+            InstructionKind::MemoryInit(data, memory) => {
+                let memory_index = self.module.memories.resolve(memory, cursor)?;
+                let data_index = self.module.data.resolve(data, cursor)?;
+
+                self.apply_memory_signature(mnemonic, memory_index, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(data_index);
+                self.code.push_u32(memory_index);
+            }
+
+            InstructionKind::MemoryCopy(destination, source) => {
+                let destination_index = self.module.memories.resolve(destination, cursor)?;
+                let source_index = self.module.memories.resolve(source, cursor)?;
+                let destination_type = self.module.memory_index_type(destination_index);
+                let source_type = self.module.memory_index_type(source_index);
+
+                // The length has to fit into both memories
+                let length_type = if destination_type == source_type {
+                    source_type
+                } else {
+                    BuiltinType::I32
+                };
+
+                self.pop_operands(&[destination_type, source_type, length_type], cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(destination_index);
+                self.code.push_u32(source_index);
+            }
+
+            InstructionKind::MemoryFill(memory) => {
+                let memory_index = self.module.memories.resolve(memory, cursor)?;
+
+                self.apply_memory_signature(mnemonic, memory_index, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(memory_index);
+            }
+
+            InstructionKind::TableInit(element, table) => {
+                let table_index = self.module.tables.resolve(table, cursor)?;
+                let element_index = self.module.elements.resolve(element, cursor)?;
+                let element_type = self
+                    .module
+                    .elements
+                    .get(element_index)
+                    .expect("resolved element segments should exist")
+                    .element_type;
+
+                self.module
+                    .check_table_elements(table_index, element_type, cursor)?;
+                self.apply_signature(mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(element_index);
+                self.code.push_u32(table_index);
+            }
+
+            InstructionKind::ElemDrop(element) => {
+                let element_index = self.module.elements.resolve(element, cursor)?;
+
+                self.apply_signature(mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(element_index);
+            }
+
+            InstructionKind::TableCopy(destination, source) => {
+                let destination_index = self.module.tables.resolve(destination, cursor)?;
+                let source_index = self.module.tables.resolve(source, cursor)?;
+                let source_type = self
+                    .module
+                    .tables
+                    .get(source_index)
+                    .expect("resolved tables should exist")
+                    .element_type;
+
+                self.module
+                    .check_table_elements(destination_index, source_type, cursor)?;
+                self.apply_signature(mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(destination_index);
+                self.code.push_u32(source_index);
+            }
+
+            InstructionKind::DataDrop(data) => {
+                let data_index = self.module.data.resolve(data, cursor)?;
+
+                self.apply_signature(mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(data_index);
+            }
+
+            InstructionKind::Table(mnemonic, table) => {
+                let table_index = self.module.tables.resolve(table, cursor)?;
+                let element_type = self
+                    .module
+                    .tables
+                    .get(table_index)
+                    .expect("resolved tables should exist")
+                    .element_type;
+
+                let (params, results) = match mnemonic {
+                    Mnemonic::TableGet => (vec![BuiltinType::I32], vec![element_type]),
+                    Mnemonic::TableSet => (vec![BuiltinType::I32, element_type], vec![]),
+                    Mnemonic::TableGrow => {
+                        (vec![element_type, BuiltinType::I32], vec![BuiltinType::I32])
+                    }
+                    Mnemonic::TableSize => (vec![], vec![BuiltinType::I32]),
+                    Mnemonic::TableFill => (
+                        vec![BuiltinType::I32, element_type, BuiltinType::I32],
+                        vec![],
+                    ),
+                    _ => unreachable!("{} is not a table instruction", mnemonic),
+                };
+
+                self.pop_operands(&params, cursor)?;
+                self.push_operands(&results);
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(table_index);
+            }
+
+            InstructionKind::TypedSelect(result_types) => {
+                let [value_type] = result_types.as_slice() else {
+                    return Err(Error::from(TypeMismatchError {
+                        expected: "a single result type".to_owned(),
+                        found: format_types(result_types),
+                        cursor,
+                    }));
+                };
+
+                self.module.check_value_types(&[*value_type], cursor)?;
+                self.pop_expected(BuiltinType::I32, cursor)?;
+                self.pop_expected(*value_type, cursor)?;
+                self.pop_expected(*value_type, cursor)?;
+                self.operands.push(Some(*value_type));
+
+                self.code.push_opcode(Mnemonic::TYPED_SELECT);
+                self.code.push_u32(1);
+                encode_value_type(&mut self.code, *value_type);
+            }
+
+            InstructionKind::RefNull(heap_type) => {
+                let reference_type = BuiltinType::reference(true, *heap_type);
+
+                self.module.check_value_types(&[reference_type], cursor)?;
+                self.operands.push(Some(reference_type));
+
+                self.code.push_opcode(mnemonic.opcode());
+                encode_heap_type(&mut self.code, *heap_type);
+            }
+
+            InstructionKind::RefFunc(function) => {
+                let function_index = self.module.functions.resolve(function, cursor)?;
+
+                if !self.module.references.contains(&function_index) {
+                    return Err(Error::from(UndeclaredReferenceError {
+                        index: function.clone(),
+                        cursor,
+                    }));
+                }
+
+                self.operands
+                    .push(Some(self.module.function_reference_type(function_index)));
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_u32(function_index);
+            }
+
+            InstructionKind::I32Const(value) => {
+                self.apply_signature(mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_i32(*value);
+            }
+
+            InstructionKind::I64Const(value) => {
+                self.apply_signature(mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_i64(*value);
+            }
+
+            InstructionKind::F32Const(bits) => {
+                self.apply_signature(mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_bytes(&bits.to_le_bytes());
+            }
+
+            InstructionKind::F64Const(bits) => {
+                self.apply_signature(mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_bytes(&bits.to_le_bytes());
+            }
+
+            InstructionKind::V128Const(bytes) => {
+                self.apply_signature(mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_bytes(bytes);
+            }
+
+            InstructionKind::Shuffle(lanes) => {
+                // Lanes index into the concatenation of both operands
+                for lane in lanes {
+                    check_lane(mnemonic, *lane, cursor)?;
+                }
+
+                self.apply_signature(mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_bytes(lanes);
+            }
+
+            InstructionKind::Lane(mnemonic, lane) => {
+                check_lane(*mnemonic, *lane, cursor)?;
+                self.apply_signature(*mnemonic, cursor)?;
+
+                self.code.push_opcode(mnemonic.opcode());
+                self.code.push_byte(*lane);
+            }
+
+            InstructionKind::Plain(mnemonic) => self.compile_plain(*mnemonic, cursor)?,
+        }
+
+        Ok(())
+    }
+
+    /// Encodes a catch clause, whose label receives the payload of the tag,
+    /// followed by the caught exception for the `_ref` variants
+    fn compile_catch(&mut self, catch: &CatchClause, cursor: PageCursor) -> Result<(), Error> {
+        let depth = self.resolve_label(&catch.label, cursor)?;
+
+        self.code.push_byte(catch.kind as u8);
+
+        let mut payload = match &catch.tag {
+            Some(tag) => {
+                let tag_index = self.module.tags.resolve(tag, cursor)?;
+
+                self.code.push_u32(tag_index);
+
+                self.module.tag_type(tag_index).params.clone()
+            }
+            None => Vec::new(),
+        };
+
+        if matches!(catch.kind, CatchKind::CatchRef | CatchKind::CatchAllRef) {
+            payload.push(BuiltinType::ExnRef);
+        }
+
+        let label_types = self.label_types(depth);
+
+        if !self.module.are_subtypes(&payload, &label_types) {
+            return Err(Error::from(TypeMismatchError {
+                expected: format_types(&label_types),
+                found: format_types(&payload),
+                cursor,
+            }));
+        }
+
+        self.code.push_u32(depth);
+
+        Ok(())
+    }
+
+    /// Validates the immediates of a memory instruction, returning the alignment
+    /// exponent and the offset as they are encoded
+    fn check_memarg(
+        &self,
+        mnemonic: Mnemonic,
+        memory_index: u32,
+        memarg: &MemArg,
+        cursor: PageCursor,
+    ) -> Result<(u32, u64), Error> {
+        let natural_alignment = mnemonic
+            .natural_alignment()
+            .expect("memory instructions should have a natural alignment");
+        let alignment = memarg.align.unwrap_or(natural_alignment);
+
+        let is_misaligned_atomic = mnemonic.is_atomic() && alignment != natural_alignment;
+
+        if !alignment.is_power_of_two() || alignment > natural_alignment || is_misaligned_atomic {
+            return Err(Error::from(InvalidAlignmentError {
+                instruction: mnemonic,
+                alignment,
+                cursor,
+            }));
+        }
+
+        // Only 64-bit memories can be accessed past 4 GiB
+        if self.module.memory_index_type(memory_index) == BuiltinType::I32
+            && u32::try_from(memarg.offset).is_err()
+        {
+            return Err(Error::from(ConstantOutOfRangeError { cursor }));
+        }
+
+        Ok((alignment.trailing_zeros(), memarg.offset))
+    }
+
+    /// Memories other than the first are named after the alignment,
+    /// which has its sixth bit set to announce them
+    fn push_memarg(&mut self, memory_index: u32, alignment: u32, offset: u64) {
+        if memory_index == 0 {
+            self.code.push_u32(alignment);
+        } else {
+            self.code.push_u32(alignment | 0x40);
+            self.code.push_u32(memory_index);
+        }
+
+        self.code.push_u64(offset);
+    }
+
+    fn compile_plain(&mut self, mnemonic: Mnemonic, cursor: PageCursor) -> Result<(), Error> {
+        match mnemonic {
+            Mnemonic::Unreachable => self.set_unreachable(),
+
+            Mnemonic::Return => {
+                let results = self.controls[0].end_types.clone();
+
+                self.pop_operands(&results, cursor)?;
+                self.set_unreachable();
+            }
+
+            Mnemonic::Drop => {
+                self.pop_operand(cursor)?;
+            }
+
+            Mnemonic::ThrowRef => {
+                self.pop_expected(BuiltinType::ExnRef, cursor)?;
+                self.set_unreachable();
+            }
+
+            Mnemonic::RefIsNull => {
+                self.pop_reference(cursor)?;
+                self.operands.push(Some(BuiltinType::I32));
+            }
+
+            Mnemonic::RefAsNonNull => {
+                let reference = self.pop_reference(cursor)?;
+
+                self.operands.push(
+                    reference.map(|reference| BuiltinType::reference(false, reference.heap_type)),
+                );
+            }
+
+            Mnemonic::RefEq => {
+                let eq_reference = BuiltinType::reference(true, HeapType::Eq);
+
+                self.pop_operands(&[eq_reference, eq_reference], cursor)?;
+                self.operands.push(Some(BuiltinType::I32));
+            }
+
+            Mnemonic::RefI31 | Mnemonic::AnyConvertExtern | Mnemonic::ExternConvertAny => {
+                let operand = self.pop_operand(cursor)?;
+                let (param, result) = self.module.conversion_signature(mnemonic, operand);
+
+                self.operands.push(operand);
+                self.pop_expected(param, cursor)?;
+                self.operands.push(Some(result));
+            }
+
+            Mnemonic::I31GetS | Mnemonic::I31GetU => {
+                self.pop_expected(BuiltinType::reference(true, HeapType::I31), cursor)?;
+                self.operands.push(Some(BuiltinType::I32));
+            }
+
+            Mnemonic::ArrayLen => {
+                self.pop_expected(BuiltinType::reference(true, HeapType::Array), cursor)?;
+                self.operands.push(Some(BuiltinType::I32));
+            }
+
+            Mnemonic::Select => {
+                self.pop_expected(BuiltinType::I32, cursor)?;
+
+                let first = self.pop_operand(cursor)?;
+                let second = self.pop_operand(cursor)?;
+
+                // References can only be selected by the typed form
+                if let Some(reference_type) = [first, second]
+                    .into_iter()
+                    .flatten()
+                    .find(BuiltinType::is_reference)
+                {
+                    return Err(Error::from(TypeMismatchError {
+                        expected: "a numeric type without `(result ...)`".to_owned(),
+                        found: format_types(&[reference_type]),
+                        cursor,
+                    }));
+                }
+
+                match (first, second) {
+                    (Some(first), Some(second)) if first != second => {
+                        return Err(Error::from(TypeMismatchError {
+                            expected: format_types(&[first]),
+                            found: format_types(&[second]),
+                            cursor,
+                        }))
+                    }
+                    _ => self.operands.push(first.or(second)),
+                }
+            }
+
+            _ => self.apply_signature(mnemonic, cursor)?,
+        }
+
+        self.code.push_opcode(mnemonic.opcode());
+
+        // Reserved for fences with other orderings
+        if mnemonic == Mnemonic::AtomicFence {
+            self.code.push_byte(0x00);
+        }
+
+        Ok(())
+    }
+
+    /// A tail call hands its results straight to the caller of the current function,
+    /// so they have to be usable as what the current function returns
+    fn push_call_results(
+        &mut self,
+        mnemonic: Mnemonic,
+        results: &[BuiltinType],
+        cursor: PageCursor,
+    ) -> Result<(), TypeMismatchError> {
+        if !matches!(
+            mnemonic,
+            Mnemonic::ReturnCall | Mnemonic::ReturnCallIndirect | Mnemonic::ReturnCallRef
+        ) {
+            self.push_operands(results);
+
+            return Ok(());
+        }
+
+        let caller_results = &self.controls[0].end_types;
+
+        if !self.module.are_subtypes(results, caller_results) {
+            return Err(TypeMismatchError {
+                expected: format_types(caller_results),
+                found: format_types(results),
+                cursor,
+            });
+        }
+
+        self.set_unreachable();
+
+        Ok(())
+    }
+
+    /// Applies the signature of a memory instruction, with the addresses and
+    /// sizes it takes or returns changed to the index type of its memory
+    fn apply_memory_signature(
+        &mut self,
+        mnemonic: Mnemonic,
+        memory_index: u32,
+        cursor: PageCursor,
+    ) -> Result<(), Error> {
+        let index_type = self.module.memory_index_type(memory_index);
+        let (params, results) = mnemonic
+            .signature()
+            .unwrap_or_else(|| panic!("{} should have a fixed signature", mnemonic));
+
+        let mut params = params.to_vec();
+        let mut results = results.to_vec();
+
+        match mnemonic {
+            Mnemonic::MemorySize => results[0] = index_type,
+            Mnemonic::MemoryGrow => {
+                params[0] = index_type;
+                results[0] = index_type;
+            }
+            Mnemonic::MemoryFill => {
+                params[0] = index_type;
+                params[2] = index_type;
+            }
+            _ => params[0] = index_type,
+        }
+
+        self.pop_operands(&params, cursor)?;
+        self.push_operands(&results);
+
+        Ok(())
+    }
+
+    /// Pops the operands and pushes the results of an instruction with a fixed signature
+    fn apply_signature(&mut self, mnemonic: Mnemonic, cursor: PageCursor) -> Result<(), Error> {
+        let (params, results) = mnemonic
+            .signature()
+            .unwrap_or_else(|| panic!("{} should have a fixed signature", mnemonic));
+
+        self.pop_operands(params, cursor)?;
+        self.push_operands(results);
+
+        Ok(())
+    }
+
+    /// Encodes a block type, returning the signature it stands for
+    fn encode_block_type(
+        &mut self,
+        block_type: &BlockType,
+        cursor: PageCursor,
+    ) -> Result<FunctionType, Error> {
+        match block_type {
+            BlockType::Empty => {
+                self.code.push_byte(ByteValue::EmptyBlock as u8);
+
+                Ok(FunctionType::default())
+            }
+            BlockType::Value(value_type) => {
+                self.module.check_value_types(&[*value_type], cursor)?;
+                encode_value_type(&mut self.code, *value_type);
+
+                Ok(FunctionType {
+                    params: Vec::new(),
+                    results: vec![*value_type],
+                })
+            }
+            BlockType::TypeUse(type_use) => {
+                if !self.module.options.features.multi_value {
+                    return Err(Error::from(DisabledFeatureError {
+                        construct: "A block type other than a single result".to_owned(),
+                        feature: "multi-value",
+                        kind: DiagnosticKind::Validate,
+                        cursor,
+                    }));
+                }
+
+                let type_index = self.module.lookup_type_use(type_use, cursor)?;
+
+                // Type indices are encoded as signed integers, so they can't be mistaken for value types
+                self.code.push_i64(type_index as i64);
+
+                Ok(self.module.function_type_at(type_index).clone())
+            }
+        }
+    }
+
+    fn resolve_local(
+        &self,
+        local: &Index,
+        cursor: PageCursor,
+    ) -> Result<(u32, BuiltinType), UnknownIndexError> {
+        let local_index = self.locals.resolve(local, cursor)?;
+
+        Ok((
+            local_index,
+            *self
+                .locals
+                .get(local_index)
+                .expect("resolved locals should exist"),
+        ))
+    }
+
+    /// Converts a label into the relative depth `br` instructions are encoded with
+    fn resolve_label(&self, label: &Index, cursor: PageCursor) -> Result<u32, UnknownIndexError> {
+        let depth = match label {
+            Index::Numeric(depth) => {
+                Some(*depth).filter(|depth| *depth < self.controls.len() as u32)
+            }
+            Index::Identifier(id) => self
+                .controls
+                .iter()
+                .rev()
+                .position(|frame| frame.label.as_ref() == Some(id))
+                .map(|depth| depth as u32),
+        };
+
+        depth.ok_or_else(|| UnknownIndexError {
+            kind: "label",
+            index: label.clone(),
+            cursor,
+        })
+    }
+
+    /// Struct fields can be named in the type section, fields of types
+    /// declared elsewhere only by their position
+    fn resolve_field(
+        &self,
+        type_index: u32,
+        fields: &[FieldType],
+        field: &Index,
+        cursor: PageCursor,
+    ) -> Result<u32, UnknownIndexError> {
+        match self.module.fields.get(&type_index) {
+            Some(field_space) => field_space.resolve(field, cursor),
+            None => match field {
+                Index::Numeric(index) if (*index as usize) < fields.len() => Ok(*index),
+                _ => Err(UnknownIndexError {
+                    kind: "field",
+                    index: field.clone(),
+                    cursor,
+                }),
+            },
+        }
+    }
+
+    /// Locals without a default value can only be read once they are set,
+    /// which lasts until the end of the enclosing block
+    fn initialize_local(&mut self, local_index: u32, local_type: BuiltinType) {
+        if !local_type.is_defaultable() && !self.initialized.contains(&local_index) {
+            self.initialized.push(local_index);
+        }
+    }
+
+    fn label_types(&self, depth: u32) -> Vec<BuiltinType> {
+        self.controls[self.controls.len() - 1 - depth as usize]
+            .label_types()
+            .to_vec()
+    }
+
+    fn push_operands(&mut self, types: &[BuiltinType]) {
+        self.operands
+            .extend(types.iter().map(|value_type| Some(*value_type)));
+    }
+
+    fn pop_operand(&mut self, cursor: PageCursor) -> Result<Operand, TypeMismatchError> {
+        let frame = self
+            .controls
+            .last()
+            .expect("operands should only be popped inside a control frame");
+
+        if self.operands.len() == frame.height {
+            if frame.unreachable {
+                return Ok(None);
+            }
+
+            return Err(TypeMismatchError {
+                expected: "an operand".to_owned(),
+                found: "an empty stack".to_owned(),
+                cursor,
+            });
+        }
+
+        Ok(self
+            .operands
+            .pop()
+            .expect("stack should be above frame height"))
+    }
+
+    /// Pops a reference, which is unknown in unreachable code
+    fn pop_reference(&mut self, cursor: PageCursor) -> Result<Option<RefType>, TypeMismatchError> {
+        match self.pop_operand(cursor)? {
+            Some(operand) => operand
+                .as_reference()
+                .map(Some)
+                .ok_or_else(|| TypeMismatchError {
+                    expected: "a reference".to_owned(),
+                    found: format_types(&[operand]),
+                    cursor,
+                }),
+            None => Ok(None),
+        }
+    }
+
+    fn pop_expected(
+        &mut self,
+        expected: BuiltinType,
+        cursor: PageCursor,
+    ) -> Result<Operand, TypeMismatchError> {
+        match self.pop_operand(cursor) {
+            Ok(Some(actual)) if !self.module.is_subtype(actual, expected) => {
+                Err(TypeMismatchError {
+                    expected: format_types(&[expected]),
+                    found: format_types(&[actual]),
+                    cursor,
+                })
+            }
+            Ok(_) => Ok(Some(expected)),
+            Err(_) => Err(TypeMismatchError {
+                expected: format_types(&[expected]),
+                found: "an empty stack".to_owned(),
+                cursor,
+            }),
+        }
+    }
+
+    /// Pops operands matching `types`, returning them in stack order
+    fn pop_operands(
+        &mut self,
+        types: &[BuiltinType],
+        cursor: PageCursor,
+    ) -> Result<Vec<Operand>, TypeMismatchError> {
+        let mut popped = Vec::new();
+
+        for expected in types.iter().rev() {
+            popped.push(self.pop_expected(*expected, cursor)?);
+        }
+
+        popped.reverse();
+
+        Ok(popped)
+    }
+
+    fn push_control(
+        &mut self,
+        kind: FrameKind,
+        label: Option<Identifier>,
+        start_types: Vec<BuiltinType>,
+        end_types: Vec<BuiltinType>,
+    ) {
+        // Params of the block belong to its frame, so they are pushed after recording the height
+        let height = self.operands.len();
+
+        self.push_operands(&start_types);
+
+        self.controls.push(ControlFrame {
+            kind,
+            label,
+            start_types,
+            end_types,
+            height,
+            initialized_height: self.initialized.len(),
+            unreachable: false,
+        });
+    }
+
+    fn pop_control(&mut self, cursor: PageCursor) -> Result<ControlFrame, TypeMismatchError> {
+        let end_types = self
+            .controls
+            .last()
+            .expect("control stack should not be empty")
+            .end_types
+            .clone();
+
+        self.pop_operands(&end_types, cursor)?;
+
+        let frame = self
+            .controls
+            .pop()
+            .expect("control stack should not be empty");
+
+        self.initialized.truncate(frame.initialized_height);
+
+        if self.operands.len() != frame.height {
+            let leftover = self.operands[frame.height..]
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>();
+
+            return Err(TypeMismatchError {
+                expected: format_types(&end_types),
+                found: format_types(end_types.iter().chain(&leftover)),
+                cursor,
+            });
+        }
+
+        Ok(frame)
+    }
+
+    fn set_unreachable(&mut self) {
+        let frame = self
+            .controls
+            .last_mut()
+            .expect("control stack should not be empty");
+
+        self.operands.truncate(frame.height);
+        frame.unreachable = true;
+    }
+}
+
+/// The optional label after `else` and `end` has to repeat the label of the block
+fn check_label(
+    label: &Option<Identifier>,
+    frame: &ControlFrame,
+    cursor: PageCursor,
+) -> Result<(), MismatchedLabelError> {
+    match label {
+        Some(label) if frame.label.as_ref() != Some(label) => Err(MismatchedLabelError {
+            label: label.clone(),
+            cursor,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Branches that test references hand them to their label as its last value
+fn check_reference_label(
+    label_types: &[BuiltinType],
+    cursor: PageCursor,
+) -> Result<(), TypeMismatchError> {
+    if label_types.last().is_some_and(BuiltinType::is_reference) {
+        return Ok(());
+    }
+
+    Err(TypeMismatchError {
+        expected: "a label taking a reference".to_owned(),
+        found: format_types(label_types),
+        cursor,
+    })
+}
+
+/// Packed fields are read with an explicit sign extension, other fields without
+fn check_packed_access(
+    mnemonic: Mnemonic,
+    field: FieldType,
+    cursor: PageCursor,
+) -> Result<(), TypeMismatchError> {
+    let is_extending = !matches!(mnemonic, Mnemonic::StructGet | Mnemonic::ArrayGet);
+
+    if field.storage_type.is_packed() == is_extending {
+        return Ok(());
+    }
+
+    Err(TypeMismatchError {
+        expected: if is_extending {
+            "a packed field".to_owned()
+        } else {
+            "an unpacked field".to_owned()
+        },
+        found: field.storage_type.to_string(),
+        cursor,
+    })
+}
+
+/// Elements of arrays are checked as their only field, which has no index
+fn check_mutable(
+    field_type: FieldType,
+    index: &Index,
+    field: Option<&Index>,
+    cursor: PageCursor,
+) -> Result<(), ImmutableFieldError> {
+    if field_type.mutable {
+        return Ok(());
+    }
+
+    Err(ImmutableFieldError {
+        index: index.clone(),
+        field: field.cloned(),
+        cursor,
+    })
+}
+
+fn check_lane(instruction: Mnemonic, lane: u8, cursor: PageCursor) -> Result<(), InvalidLaneError> {
+    let lanes = match instruction {
+        Mnemonic::I8x16Shuffle => 32,
+        _ => instruction
+            .lanes()
+            .expect("lane instructions should have a lane count"),
+    };
+
+    if lane < lanes {
+        Ok(())
+    } else {
+        Err(InvalidLaneError {
+            instruction,
+            lane,
+            lanes,
+            cursor,
+        })
+    }
+}
+
+/// Proposals an instruction came with, in the order they are reported when disabled
+fn required_features(kind: &InstructionKind) -> Vec<&'static str> {
+    let mnemonic = kind.mnemonic();
+    let mut features = Vec::new();
+
+    match mnemonic.opcode() {
+        Opcode::Single(0xC0..=0xC4) => features.push("sign-extension"),
+        Opcode::Prefixed(0xFC, 0..=7) => features.push("saturating-float-to-int"),
+        Opcode::Prefixed(0xFC, 8..=14) => features.push("bulk-memory"),
+        Opcode::Prefixed(0xFD, _) => features.push("simd"),
+        Opcode::Prefixed(0xFE, _) => features.push("threads"),
+        _ => {}
+    }
+
+    if matches!(
+        kind,
+        InstructionKind::TypedSelect(_)
+            | InstructionKind::Table(..)
+            | InstructionKind::RefNull(_)
+            | InstructionKind::RefFunc(_)
+            | InstructionKind::Plain(Mnemonic::RefIsNull)
+    ) {
+        features.push("reference-types");
+    }
+
+    if matches!(
+        kind,
+        InstructionKind::ReturnCall(_)
+            | InstructionKind::ReturnCallIndirect(..)
+            | InstructionKind::ReturnCallRef(_)
+    ) {
+        features.push("tail-call");
+    }
+
+    if matches!(
+        kind,
+        InstructionKind::TryTable(..)
+            | InstructionKind::Throw(_)
+            | InstructionKind::Plain(Mnemonic::ThrowRef)
+    ) {
+        features.push("exception-handling");
+    }
+
+    if matches!(
+        kind,
+        InstructionKind::CallRef(_)
+            | InstructionKind::ReturnCallRef(_)
+            | InstructionKind::BrOnNull(_)
+            | InstructionKind::BrOnNonNull(_)
+            | InstructionKind::Plain(Mnemonic::RefAsNonNull)
+    ) {
+        features.push("function-references");
+    }
+
+    if matches!(mnemonic.opcode(), Opcode::Prefixed(0xFB, _)) || mnemonic == Mnemonic::RefEq {
+        features.push("gc");
+    }
+
+    features
+}
@@ -0,0 +1,52 @@
+use crate::{
+    bytecode::container::BytecodeContainer,
+    parser::expression::{ExternalKind, GlobalType, Limits, TableType},
+};
+
+use super::types::{encode_global_type, encode_limits, encode_table_type, encode_tag_type};
+
+#[derive(Debug)]
+pub enum ImportDescriptor {
+    /// Type index of the imported function
+    Func(u32),
+    Table(TableType),
+    Memory(Limits),
+    Global(GlobalType),
+    /// Type index of the imported tag
+    Tag(u32),
+}
+
+impl ImportDescriptor {
+    pub fn kind(&self) -> ExternalKind {
+        match self {
+            ImportDescriptor::Func(_) => ExternalKind::Func,
+            ImportDescriptor::Table(_) => ExternalKind::Table,
+            ImportDescriptor::Memory(_) => ExternalKind::Memory,
+            ImportDescriptor::Global(_) => ExternalKind::Global,
+            ImportDescriptor::Tag(_) => ExternalKind::Tag,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ImportEntry {
+    pub module: String,
+    pub name: String,
+    pub descriptor: ImportDescriptor,
+}
+
+impl ImportEntry {
+    pub fn encode(&self, section: &mut BytecodeContainer) {
+        section.push_name(&self.module);
+        section.push_name(&self.name);
+        section.push_byte(self.descriptor.kind() as u8);
+
+        match &self.descriptor {
+            ImportDescriptor::Func(type_index) => section.push_u32(*type_index),
+            ImportDescriptor::Table(table_type) => encode_table_type(section, table_type),
+            ImportDescriptor::Memory(limits) => encode_limits(section, limits),
+            ImportDescriptor::Global(global_type) => encode_global_type(section, global_type),
+            ImportDescriptor::Tag(type_index) => encode_tag_type(section, *type_index),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use crate::{
+    shared::{Identifier, Index},
+    traits::page_position::PageCursor,
+};
+
+use super::errors::{DuplicateIdentifierError, UnknownIndexError};
+
+/// Items of one kind (functions, types, locals, ...) in definition order,
+/// along with the identifiers they can be referenced by
+#[derive(Debug)]
+pub struct IndexSpace<T> {
+    kind: &'static str,
+    items: Vec<T>,
+    /// Index of every identifier, along with where it was defined
+    ids: HashMap<Identifier, (u32, PageCursor)>,
+}
+
+impl<T> IndexSpace<T> {
+    pub fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            items: Vec::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        id: Option<Identifier>,
+        item: T,
+        cursor: PageCursor,
+    ) -> Result<u32, DuplicateIdentifierError> {
+        let index = self.items.len() as u32;
+
+        if let Some(id) = id {
+            if let Some(&(_, previous)) = self.ids.get(&id) {
+                return Err(DuplicateIdentifierError {
+                    id,
+                    cursor,
+                    previous,
+                });
+            }
+
+            self.ids.insert(id, (index, cursor));
+        }
+
+        self.items.push(item);
+
+        Ok(index)
+    }
+
+    pub fn resolve(&self, index: &Index, cursor: PageCursor) -> Result<u32, UnknownIndexError> {
+        let resolved = match index {
+            Index::Numeric(numeric) => Some(*numeric).filter(|numeric| *numeric < self.len()),
+            Index::Identifier(id) => self.ids.get(id).map(|(index, _)| *index),
+        };
+
+        resolved.ok_or_else(|| UnknownIndexError {
+            kind: self.kind,
+            index: index.clone(),
+            cursor,
+        })
+    }
+
+    pub fn get(&self, index: u32) -> Option<&T> {
+        self.items.get(index as usize)
+    }
+
+    pub fn len(&self) -> u32 {
+        self.items.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Identifiers of the items that have one, ordered by index
+    pub fn names(&self) -> Vec<(u32, &Identifier)> {
+        let mut names = self
+            .ids
+            .iter()
+            .map(|(id, (index, _))| (*index, id))
+            .collect::<Vec<_>>();
+
+        names.sort_by_key(|(index, _)| *index);
+
+        names
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+}
@@ -1,59 +1,1619 @@
+mod constant;
 pub mod errors;
+mod function;
+mod imports;
+mod index_space;
+mod names;
+mod producers;
+mod segments;
+#[cfg(test)]
+mod tests;
+mod types;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use errors::MisplacedError;
+use constant::compile_constant_expression;
+use errors::{
+    DuplicateExportError, ImportAfterDefinitionError, InvalidLimitsError, InvalidSubtypeError,
+    MisplacedError, MultipleDefinitionsError, SignatureMismatchError, TypeMismatchError,
+    UnknownIndexError,
+};
+use function::FunctionCompiler;
+use imports::{ImportDescriptor, ImportEntry};
+use index_space::IndexSpace;
+use names::encode_name_section;
+use producers::encode_producers_section;
+use segments::{
+    zero_offset, DataSegment, DataSegmentMode, ElementSegment, ElementSegmentItems,
+    ElementSegmentMode,
+};
+use types::{
+    encode_global_type, encode_limits, encode_table_type, encode_tag_type, format_types,
+    validate_limits, CompositeType, DefinedType, FieldType, FunctionType, MAX_MEMORY64_PAGES,
+    MAX_MEMORY_PAGES, MAX_TABLE_ELEMENTS,
+};
 
 use crate::{
-    bytecode::container::BytecodeContainer,
-    parser::expression::{Expr, FunctionDefinition, Module},
-    shared::Identifier,
-    traits::{error_display::ErrorDisplay, page_position::PageCursor},
+    bytecode::{container::BytecodeContainer, ByteValue, SectionId},
+    diagnostic::{DiagnosticKind, Error},
+    features::{errors::DisabledFeatureError, WasmFeatures},
+    parser::{
+        expression::{
+            CompositeDefinition, CustomDefinition, CustomPlacement, DataDefinition, DataMode,
+            ElementDefinition, ElementItems, ElementMode, Expr, ExternalKind, FunctionDefinition,
+            GlobalDefinition, GlobalType, ImportDescription, Limits, Module, StorageType,
+            TableDefinition, TableType, TypeDefinition, TypeUse,
+        },
+        instructions::{
+            BlockType, BuiltinType, HeapType, Instruction, InstructionKind, Mnemonic, RefType,
+        },
+    },
+    shared::{Identifier, Index},
+    traits::page_position::PageCursor,
 };
 
+/// Order of the standard sections in the binary, which differs from their ids
+const SECTION_ORDER: [SectionId; 13] = [
+    SectionId::Type,
+    SectionId::Import,
+    SectionId::Function,
+    SectionId::Table,
+    SectionId::Memory,
+    SectionId::Tag,
+    SectionId::Global,
+    SectionId::Export,
+    SectionId::Start,
+    SectionId::Element,
+    SectionId::DataCount,
+    SectionId::Code,
+    SectionId::Data,
+];
+
 struct BytecodeModule {
-    pub functions: HashMap<Identifier, FunctionDefinition>,
-    pub exports: Vec<Identifier>,
+    pub options: CompileOptions,
+    pub types: IndexSpace<DefinedType>,
+    /// First type and number of types of every recursion group, in the order of the type section
+    pub recursion_groups: Vec<(u32, u32)>,
+    /// Index of the first type every type is equivalent to, which types of
+    /// identical recursion groups are
+    pub canonical_types: Vec<u32>,
+    /// First type of every distinct recursion group, with the types inside the group
+    /// referred to relative to it and the others by their canonical index
+    canonical_groups: HashMap<Vec<DefinedType>, u32>,
+    /// Fields of the struct types defined in the type section, which may be named
+    pub fields: HashMap<u32, IndexSpace<FieldType>>,
+    pub imports: Vec<ImportEntry>,
+    /// Type index of every function
+    pub functions: IndexSpace<u32>,
+    pub tables: IndexSpace<TableType>,
+    pub memories: IndexSpace<Limits>,
+    pub globals: IndexSpace<GlobalType>,
+    /// Type index of every tag
+    pub tags: IndexSpace<u32>,
+    /// Encoded initializers of the tables defined by the module, which are null when missing
+    pub table_initializers: Vec<Option<BytecodeContainer>>,
+    /// Encoded initializers of the globals defined by the module, following the imported ones
+    pub global_initializers: Vec<BytecodeContainer>,
+    pub exports: Vec<(String, ExternalKind, u32)>,
+    /// Function called once the module is instantiated
+    pub start: Option<u32>,
+    pub elements: IndexSpace<ElementSegment>,
+    /// Functions referenced outside of code, which `ref.func` is limited to
+    pub references: HashSet<u32>,
+    pub data: IndexSpace<DataSegment>,
+    /// Whether code refers to data segments, which requires their count up front
+    pub uses_data_count: bool,
+    pub custom_sections: Vec<CustomDefinition>,
 }
 
 impl BytecodeModule {
-    fn new() -> Self {
+    fn new(options: CompileOptions) -> Self {
         Self {
-            functions: Default::default(),
+            options,
+            types: IndexSpace::new("type"),
+            recursion_groups: Default::default(),
+            canonical_types: Default::default(),
+            canonical_groups: Default::default(),
+            fields: Default::default(),
+            imports: Default::default(),
+            functions: IndexSpace::new("function"),
+            tables: IndexSpace::new("table"),
+            memories: IndexSpace::new("memory"),
+            globals: IndexSpace::new("global"),
+            tags: IndexSpace::new("tag"),
+            table_initializers: Default::default(),
+            global_initializers: Default::default(),
             exports: Default::default(),
+            start: None,
+            elements: IndexSpace::new("element segment"),
+            references: Default::default(),
+            data: IndexSpace::new("data segment"),
+            uses_data_count: false,
+            custom_sections: Default::default(),
+        }
+    }
+
+    /// `v128` requires the simd feature, reference types outside of `funcref` tables the
+    /// reference types one, `exnref` the exception handling one, non-nullable and concrete
+    /// references the function references one and the heap types of structs and arrays the gc one
+    fn check_value_types(&self, types: &[BuiltinType], cursor: PageCursor) -> Result<(), Error> {
+        for value_type in types {
+            let reference = value_type.as_reference();
+
+            if let Some(HeapType::Concrete(index)) = reference.map(|reference| reference.heap_type)
+            {
+                if index >= self.types.len() {
+                    return Err(Error::from(UnknownIndexError {
+                        kind: "type",
+                        index: Index::Numeric(index),
+                        cursor,
+                    }));
+                }
+            }
+
+            let feature = match reference {
+                None if *value_type == BuiltinType::V128 && !self.options.features.simd => "simd",
+                Some(RefType {
+                    heap_type: HeapType::Exn | HeapType::NoExn,
+                    ..
+                }) if !self.options.features.exceptions => "exception-handling",
+                Some(reference)
+                    if self.is_gc_heap_type(reference.heap_type) && !self.options.features.gc =>
+                {
+                    "gc"
+                }
+                Some(RefType {
+                    nullable: false, ..
+                })
+                | Some(RefType {
+                    heap_type: HeapType::Concrete(_),
+                    ..
+                }) if !self.accepts_typed_references() => "function-references",
+                Some(_) if !self.options.features.reference_types => "reference-types",
+                _ => continue,
+            };
+
+            return Err(Error::from(DisabledFeatureError {
+                construct: format!("`{}`", value_type),
+                feature,
+                kind: DiagnosticKind::Validate,
+                cursor,
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Functions returning more than one value came with the multi-value feature
+    fn check_results(
+        &self,
+        function_type: &FunctionType,
+        cursor: PageCursor,
+    ) -> Result<(), DisabledFeatureError> {
+        if function_type.results.len() > 1 && !self.options.features.multi_value {
+            return Err(DisabledFeatureError {
+                construct: "A function type with more than one result".to_owned(),
+                feature: "multi-value",
+                kind: DiagnosticKind::Validate,
+                cursor,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Globals shared with the host or other modules could only be immutable before
+    /// the mutable globals feature
+    fn check_shared_global(
+        &self,
+        global_type: &GlobalType,
+        action: &str,
+        cursor: PageCursor,
+    ) -> Result<(), DisabledFeatureError> {
+        if global_type.mutable && !self.options.features.mutable_globals {
+            return Err(DisabledFeatureError {
+                construct: format!("{} a mutable global", action),
+                feature: "mutable-globals",
+                kind: DiagnosticKind::Validate,
+                cursor,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Garbage collected values and the bottom types of the hierarchies outside of it
+    /// came with the gc feature
+    fn is_gc_heap_type(&self, heap_type: HeapType) -> bool {
+        match heap_type {
+            HeapType::Func | HeapType::Extern | HeapType::Exn | HeapType::NoExn => false,
+            HeapType::Concrete(index) => self.defined_type(index).as_function().is_none(),
+            _ => true,
+        }
+    }
+
+    /// The gc feature builds on function references, so it enables them as well
+    fn accepts_typed_references(&self) -> bool {
+        self.options.features.function_references || self.options.features.gc
+    }
+
+    /// Declares the types of a recursion group, which may refer to each other regardless
+    /// of their order and only to the types of earlier groups otherwise
+    fn declare_recursion_group(&mut self, definitions: &[TypeDefinition]) -> Result<(), Error> {
+        let start = self.types.len();
+
+        for definition in definitions {
+            let index = self.types.len();
+            let construct = match definition.composite {
+                CompositeDefinition::Struct(_) => Some("A `struct` type"),
+                CompositeDefinition::Array(_) => Some("An `array` type"),
+                _ if !definition.is_final || !definition.supertypes.is_empty() => Some("A subtype"),
+                _ => None,
+            };
+
+            if let Some(construct) = construct.filter(|_| !self.options.features.gc) {
+                return Err(Error::from(DisabledFeatureError {
+                    construct: construct.to_owned(),
+                    feature: "gc",
+                    kind: DiagnosticKind::Validate,
+                    cursor: definition.cursor,
+                }));
+            }
+
+            let supertype = match definition.supertypes.as_slice() {
+                [] => None,
+                [supertype] => Some(self.types.resolve(supertype, definition.cursor)?),
+                [_, supertype, ..] => {
+                    return Err(Error::from(InvalidSubtypeError {
+                        index: definition
+                            .id
+                            .clone()
+                            .map_or(Index::Numeric(index), Index::Identifier),
+                        supertype: supertype.clone(),
+                        reason: "types can have at most one supertype".to_owned(),
+                        cursor: definition.cursor,
+                    }))
+                }
+            };
+
+            if let CompositeDefinition::Struct(fields) = &definition.composite {
+                let mut field_space = IndexSpace::new("field");
+
+                for field in fields {
+                    field_space.push(field.id.clone(), field.into(), definition.cursor)?;
+                }
+
+                self.fields.insert(index, field_space);
+            }
+
+            let defined_type = DefinedType {
+                composite_type: CompositeType::from(&definition.composite),
+                is_final: definition.is_final,
+                supertype,
+            };
+
+            self.types
+                .push(definition.id.clone(), defined_type, definition.cursor)?;
+        }
+
+        // Checked once the whole group is declared, since its types may refer to later ones
+        for (index, definition) in (start..).zip(definitions) {
+            let value_types = self.defined_type(index).value_types();
+
+            self.check_value_types(&value_types, definition.cursor)?;
+
+            if let Some(function_type) = self.defined_type(index).as_function() {
+                self.check_results(function_type, definition.cursor)?;
+            }
+        }
+
+        self.register_recursion_group(start);
+
+        for (index, definition) in (start..).zip(definitions) {
+            self.check_subtype(index, definition)?;
+        }
+
+        Ok(())
+    }
+
+    /// Closes the recursion group starting at `start` with the last declared type, and maps
+    /// its types to those of the first identical group
+    fn register_recursion_group(&mut self, start: u32) {
+        let end = self.types.len();
+        let key = (start..end)
+            .map(|index| {
+                self.defined_type(index).map_indices(|referenced| {
+                    if referenced < start {
+                        self.canonical_types[referenced as usize]
+                    } else {
+                        u32::MAX - (referenced - start)
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let canonical_start = *self.canonical_groups.entry(key).or_insert(start);
+
+        self.canonical_types
+            .extend((0..end - start).map(|offset| canonical_start + offset));
+        self.recursion_groups.push((start, end - start));
+    }
+
+    /// Subtypes have to be usable wherever their supertype is expected
+    fn check_subtype(
+        &self,
+        index: u32,
+        definition: &TypeDefinition,
+    ) -> Result<(), InvalidSubtypeError> {
+        let defined_type = self.defined_type(index);
+        let Some(supertype_index) = defined_type.supertype else {
+            return Ok(());
+        };
+        let supertype = self.defined_type(supertype_index);
+
+        let reason = if supertype.is_final {
+            "the supertype is final"
+        } else if !self
+            .is_composite_subtype(&defined_type.composite_type, &supertype.composite_type)
+        {
+            "their definitions don't match"
+        } else {
+            return Ok(());
+        };
+
+        Err(InvalidSubtypeError {
+            index: definition
+                .id
+                .clone()
+                .map_or(Index::Numeric(index), Index::Identifier),
+            supertype: definition.supertypes[0].clone(),
+            reason: reason.to_owned(),
+            cursor: definition.cursor,
+        })
+    }
+
+    /// Functions may take more general params and return more specific results,
+    /// structs may add fields and immutable fields may become more specific
+    fn is_composite_subtype(&self, actual: &CompositeType, expected: &CompositeType) -> bool {
+        match (actual, expected) {
+            (CompositeType::Func(actual), CompositeType::Func(expected)) => {
+                self.are_subtypes(&expected.params, &actual.params)
+                    && self.are_subtypes(&actual.results, &expected.results)
+            }
+            (CompositeType::Struct(actual), CompositeType::Struct(expected)) => {
+                actual.len() >= expected.len()
+                    && actual
+                        .iter()
+                        .zip(expected)
+                        .all(|(actual, expected)| self.is_field_subtype(*actual, *expected))
+            }
+            (CompositeType::Array(actual), CompositeType::Array(expected)) => {
+                self.is_field_subtype(*actual, *expected)
+            }
+            _ => false,
+        }
+    }
+
+    /// Mutable fields can be written through the supertype, so they have to match exactly
+    fn is_field_subtype(&self, actual: FieldType, expected: FieldType) -> bool {
+        if actual.mutable != expected.mutable {
+            return false;
+        }
+
+        match (actual.storage_type, expected.storage_type) {
+            (StorageType::Value(actual_type), StorageType::Value(expected_type)) => {
+                self.is_subtype(actual_type, expected_type)
+                    && (!actual.mutable || self.is_subtype(expected_type, actual_type))
+            }
+            (actual, expected) => actual == expected,
+        }
+    }
+
+    fn are_subtypes(&self, actual: &[BuiltinType], expected: &[BuiltinType]) -> bool {
+        actual.len() == expected.len()
+            && actual
+                .iter()
+                .zip(expected)
+                .all(|(actual, expected)| self.is_subtype(*actual, *expected))
+    }
+
+    /// Whether values of type `actual` can be used where `expected` is required,
+    /// which only differ for references
+    fn is_subtype(&self, actual: BuiltinType, expected: BuiltinType) -> bool {
+        match (actual.as_reference(), expected.as_reference()) {
+            (Some(actual), Some(expected)) => {
+                (!actual.nullable || expected.nullable)
+                    && self.is_heap_subtype(actual.heap_type, expected.heap_type)
+            }
+            _ => actual == expected,
+        }
+    }
+
+    /// Concrete types are subtypes of the types they are equivalent to, their declared
+    /// supertypes and the abstract type of their kind
+    fn is_heap_subtype(&self, actual: HeapType, expected: HeapType) -> bool {
+        match (actual, expected) {
+            (HeapType::Concrete(actual), HeapType::Concrete(expected)) => {
+                let expected = self.canonical_types[expected as usize];
+                let mut current = Some(actual);
+
+                while let Some(index) = current {
+                    if self.canonical_types[index as usize] == expected {
+                        return true;
+                    }
+
+                    current = self.defined_type(index).supertype;
+                }
+
+                false
+            }
+            (HeapType::Concrete(actual), expected) => {
+                self.is_heap_subtype(self.abstract_type(actual), expected)
+            }
+            (actual, HeapType::Concrete(expected)) => {
+                actual.is_bottom()
+                    && actual
+                        .abstract_supertypes()
+                        .contains(&self.abstract_type(expected))
+            }
+            (actual, expected) => actual.abstract_supertypes().contains(&expected),
+        }
+    }
+
+    /// Operands that `struct.new` and `array.new` and their default forms take to allocate `type_index`
+    fn allocation_params(
+        &self,
+        mnemonic: Mnemonic,
+        type_index: u32,
+        index: &Index,
+        cursor: PageCursor,
+    ) -> Result<Vec<BuiltinType>, TypeMismatchError> {
+        let fields = match mnemonic {
+            Mnemonic::StructNew | Mnemonic::StructNewDefault => {
+                self.expect_struct_type(type_index, index, cursor)?.to_vec()
+            }
+            _ => vec![self.expect_array_type(type_index, index, cursor)?],
+        };
+        let is_default = matches!(
+            mnemonic,
+            Mnemonic::StructNewDefault | Mnemonic::ArrayNewDefault
+        );
+
+        if let Some(field) = fields
+            .iter()
+            .find(|field| is_default && !field.storage_type.unpacked().is_defaultable())
+        {
+            return Err(TypeMismatchError {
+                expected: "fields with a default value".to_owned(),
+                found: format_types(&[field.storage_type.unpacked()]),
+                cursor,
+            });
+        }
+
+        let mut params = match mnemonic {
+            Mnemonic::StructNew | Mnemonic::ArrayNew => fields
+                .iter()
+                .map(|field| field.storage_type.unpacked())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // Arrays also take their length
+        if matches!(mnemonic, Mnemonic::ArrayNew | Mnemonic::ArrayNewDefault) {
+            params.push(BuiltinType::I32);
+        }
+
+        Ok(params)
+    }
+
+    /// Param and result of `ref.i31` and the conversions between the internal and external
+    /// hierarchies, which keep whether `operand` is nullable
+    fn conversion_signature(
+        &self,
+        mnemonic: Mnemonic,
+        operand: Option<BuiltinType>,
+    ) -> (BuiltinType, BuiltinType) {
+        let nullable = operand
+            .and_then(|operand| operand.as_reference())
+            .is_none_or(|reference| reference.nullable);
+
+        match mnemonic {
+            Mnemonic::RefI31 => (
+                BuiltinType::I32,
+                BuiltinType::reference(false, HeapType::I31),
+            ),
+            Mnemonic::AnyConvertExtern => (
+                BuiltinType::reference(nullable, HeapType::Extern),
+                BuiltinType::reference(nullable, HeapType::Any),
+            ),
+            _ => (
+                BuiltinType::reference(nullable, HeapType::Any),
+                BuiltinType::reference(nullable, HeapType::Extern),
+            ),
+        }
+    }
+
+    /// `ref.func` produces a reference to the exact function type once typed references exist,
+    /// and a plain `funcref` before
+    fn function_reference_type(&self, function_index: u32) -> BuiltinType {
+        if !self.accepts_typed_references() {
+            return BuiltinType::FuncRef;
+        }
+
+        let type_index = self
+            .functions
+            .get(function_index)
+            .expect("function indices should be resolved before looking up their type");
+
+        BuiltinType::reference(false, HeapType::Concrete(*type_index))
+    }
+
+    fn abstract_type(&self, type_index: u32) -> HeapType {
+        self.defined_type(type_index).composite_type.abstract_type()
+    }
+
+    fn defined_type(&self, type_index: u32) -> &DefinedType {
+        self.types
+            .get(type_index)
+            .expect("type indices should be resolved before looking up their definition")
+    }
+
+    /// Tags carry their params as the payload of an exception, so they can't have results
+    fn declare_tag(
+        &mut self,
+        id: Option<Identifier>,
+        type_use: &TypeUse,
+        cursor: PageCursor,
+    ) -> Result<u32, Error> {
+        if !self.options.features.exceptions {
+            return Err(Error::from(DisabledFeatureError {
+                construct: "`tag`".to_owned(),
+                feature: "exception-handling",
+                kind: DiagnosticKind::Validate,
+                cursor,
+            }));
+        }
+
+        let type_index = self.declare_type_use(type_use, cursor)?;
+        let function_type = self.function_type_at(type_index);
+
+        if !function_type.results.is_empty() {
+            return Err(Error::from(TypeMismatchError {
+                expected: "a type without results".to_owned(),
+                found: function_type.to_string(),
+                cursor,
+            }));
+        }
+
+        self.tags.push(id, type_index, cursor)?;
+
+        Ok(type_index)
+    }
+
+    fn declare_table(
+        &mut self,
+        id: Option<Identifier>,
+        table_type: &TableType,
+        cursor: PageCursor,
+    ) -> Result<(), Error> {
+        if !self.options.features.reference_types && !self.tables.is_empty() {
+            return Err(Error::from(DisabledFeatureError {
+                construct: "Defining more than one table".to_owned(),
+                feature: "reference-types",
+                kind: DiagnosticKind::Validate,
+                cursor,
+            }));
+        }
+
+        if table_type.element_type != BuiltinType::FuncRef {
+            self.check_value_types(&[table_type.element_type], cursor)?;
+        }
+
+        validate_limits(&table_type.limits, MAX_TABLE_ELEMENTS, "elements", cursor)?;
+
+        self.tables.push(id, table_type.clone(), cursor)?;
+
+        Ok(())
+    }
+
+    /// Shared memories have to state how far they may grow, since they can't be moved
+    fn declare_memory(
+        &mut self,
+        id: Option<Identifier>,
+        limits: &Limits,
+        cursor: PageCursor,
+    ) -> Result<(), Error> {
+        if !self.options.features.multi_memory && !self.memories.is_empty() {
+            return Err(Error::from(DisabledFeatureError {
+                construct: "Defining more than one memory".to_owned(),
+                feature: "multi-memory",
+                kind: DiagnosticKind::Validate,
+                cursor,
+            }));
+        }
+
+        let max_pages = if limits.index_type == BuiltinType::I64 {
+            if !self.options.features.memory64 {
+                return Err(Error::from(DisabledFeatureError {
+                    construct: "A 64-bit memory".to_owned(),
+                    feature: "memory64",
+                    kind: DiagnosticKind::Validate,
+                    cursor,
+                }));
+            }
+
+            MAX_MEMORY64_PAGES
+        } else {
+            MAX_MEMORY_PAGES
+        };
+
+        if limits.shared {
+            if !self.options.features.threads {
+                return Err(Error::from(DisabledFeatureError {
+                    construct: "A shared memory".to_owned(),
+                    feature: "threads",
+                    kind: DiagnosticKind::Validate,
+                    cursor,
+                }));
+            }
+
+            if limits.max.is_none() {
+                return Err(Error::from(InvalidLimitsError {
+                    reason: "shared memories need a maximum".to_owned(),
+                    cursor,
+                }));
+            }
+        }
+
+        validate_limits(limits, max_pages, "pages", cursor)?;
+
+        self.memories.push(id, limits.clone(), cursor)?;
+
+        Ok(())
+    }
+
+    /// Finds the type a type use refers to, adding its signature to
+    /// the end of the type section if no such type exists yet
+    fn declare_type_use(&mut self, type_use: &TypeUse, cursor: PageCursor) -> Result<u32, Error> {
+        if type_use.index.is_some() {
+            return self.lookup_type_use(type_use, cursor);
+        }
+
+        let function_type = FunctionType::from(&type_use.signature);
+
+        self.check_value_types(&function_type.params, cursor)?;
+        self.check_value_types(&function_type.results, cursor)?;
+        self.check_results(&function_type, cursor)?;
+
+        if let Some(index) = self.find_function_type(&function_type) {
+            return Ok(index);
+        }
+
+        let index = self
+            .types
+            .push(None, DefinedType::function(function_type), cursor)?;
+
+        self.register_recursion_group(index);
+
+        Ok(index)
+    }
+
+    /// Finds a type equivalent to a function type written outside of the type section,
+    /// which would form a recursion group of its own
+    fn find_function_type(&self, function_type: &FunctionType) -> Option<u32> {
+        let key = vec![DefinedType::function(function_type.clone())
+            .map_indices(|referenced| self.canonical_types[referenced as usize])];
+
+        self.canonical_groups.get(&key).copied()
+    }
+
+    fn lookup_type_use(&self, type_use: &TypeUse, cursor: PageCursor) -> Result<u32, Error> {
+        if let Some(index) = &type_use.index {
+            let type_index = self.types.resolve(index, cursor)?;
+            let function_type = self.expect_function_type(type_index, index, cursor)?;
+
+            // Inline params and results may be repeated next to the index, as long as they match
+            let signature = &type_use.signature;
+            let has_inline_signature =
+                !signature.params.is_empty() || !signature.results.is_empty();
+
+            if has_inline_signature && FunctionType::from(signature) != *function_type {
+                return Err(Error::from(SignatureMismatchError {
+                    index: index.clone(),
+                    expected: function_type.to_string(),
+                    cursor,
+                }));
+            }
+
+            return Ok(type_index);
+        }
+
+        let function_type = FunctionType::from(&type_use.signature);
+
+        Ok(self
+            .find_function_type(&function_type)
+            .expect("inline type uses should be declared before they are looked up"))
+    }
+
+    fn expect_function_type(
+        &self,
+        type_index: u32,
+        index: &Index,
+        cursor: PageCursor,
+    ) -> Result<&FunctionType, TypeMismatchError> {
+        self.defined_type(type_index)
+            .as_function()
+            .ok_or_else(|| expected_kind("a function type", index, cursor))
+    }
+
+    fn expect_struct_type(
+        &self,
+        type_index: u32,
+        index: &Index,
+        cursor: PageCursor,
+    ) -> Result<&[FieldType], TypeMismatchError> {
+        self.defined_type(type_index)
+            .as_struct()
+            .ok_or_else(|| expected_kind("a struct type", index, cursor))
+    }
+
+    fn expect_array_type(
+        &self,
+        type_index: u32,
+        index: &Index,
+        cursor: PageCursor,
+    ) -> Result<FieldType, TypeMismatchError> {
+        self.defined_type(type_index)
+            .as_array()
+            .ok_or_else(|| expected_kind("an array type", index, cursor))
+    }
+
+    fn data_segment(&self, definition: &DataDefinition) -> Result<DataSegment, Error> {
+        let mode = match &definition.mode {
+            DataMode::Passive => {
+                self.check_segment_mode(
+                    "A passive data segment",
+                    "bulk-memory",
+                    definition.cursor,
+                )?;
+
+                DataSegmentMode::Passive
+            }
+            DataMode::Active { memory, offset } => {
+                let memory = self.memories.resolve(memory, definition.cursor)?;
+
+                DataSegmentMode::Active {
+                    memory,
+                    offset: compile_constant_expression(
+                        self,
+                        offset,
+                        self.memory_index_type(memory),
+                        definition.cursor,
+                    )?,
+                }
+            }
+        };
+
+        Ok(DataSegment {
+            mode,
+            bytes: definition.bytes.clone(),
+        })
+    }
+
+    /// Segments that aren't copied in on instantiation came with the proposals
+    /// that added the instructions using them
+    fn check_segment_mode(
+        &self,
+        construct: &str,
+        feature: &'static str,
+        cursor: PageCursor,
+    ) -> Result<(), DisabledFeatureError> {
+        if !self.options.features.is_enabled(feature) {
+            return Err(DisabledFeatureError {
+                construct: construct.to_owned(),
+                feature,
+                kind: DiagnosticKind::Validate,
+                cursor,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn element_segment(&self, definition: &ElementDefinition) -> Result<ElementSegment, Error> {
+        let (element_type, items) = self.element_items(&definition.items, definition.cursor)?;
+
+        let mode = match &definition.mode {
+            ElementMode::Passive => {
+                self.check_segment_mode(
+                    "A passive element segment",
+                    "bulk-memory",
+                    definition.cursor,
+                )?;
+
+                ElementSegmentMode::Passive
+            }
+            ElementMode::Declarative => {
+                self.check_segment_mode(
+                    "A declarative element segment",
+                    "reference-types",
+                    definition.cursor,
+                )?;
+
+                ElementSegmentMode::Declarative
+            }
+            ElementMode::Active { table, offset } => {
+                let table = self.tables.resolve(table, definition.cursor)?;
+
+                self.check_table_elements(table, element_type, definition.cursor)?;
+
+                ElementSegmentMode::Active {
+                    table,
+                    offset: compile_constant_expression(
+                        self,
+                        offset,
+                        BuiltinType::I32,
+                        definition.cursor,
+                    )?,
+                }
+            }
+        };
+
+        Ok(ElementSegment {
+            mode,
+            element_type,
+            items,
+        })
+    }
+
+    fn element_items(
+        &self,
+        items: &ElementItems,
+        cursor: PageCursor,
+    ) -> Result<(BuiltinType, ElementSegmentItems), Error> {
+        match items {
+            ElementItems::Functions(functions) => {
+                let functions = functions
+                    .iter()
+                    .map(|function| self.functions.resolve(function, cursor))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok((
+                    BuiltinType::FuncRef,
+                    ElementSegmentItems::Functions(functions),
+                ))
+            }
+            ElementItems::Expressions(element_type, expressions) => {
+                let expressions = expressions
+                    .iter()
+                    .map(|expression| {
+                        compile_constant_expression(self, expression, *element_type, cursor)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok((*element_type, ElementSegmentItems::Expressions(expressions)))
+            }
+        }
+    }
+
+    fn check_table_elements(
+        &self,
+        table: u32,
+        element_type: BuiltinType,
+        cursor: PageCursor,
+    ) -> Result<(), TypeMismatchError> {
+        let table_type = self
+            .tables
+            .get(table)
+            .expect("resolved tables should exist");
+
+        if !self.is_subtype(element_type, table_type.element_type) {
+            return Err(TypeMismatchError {
+                expected: format_types(&[table_type.element_type]),
+                found: format_types(&[element_type]),
+                cursor,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Marks the functions of `ref.func` instructions outside of code as referenced
+    fn declare_references(&mut self, instructions: &[Instruction]) {
+        for instruction in instructions {
+            if let InstructionKind::RefFunc(function) = &instruction.kind {
+                if let Ok(function_index) = self.functions.resolve(function, instruction.cursor) {
+                    self.references.insert(function_index);
+                }
+            }
+        }
+    }
+
+    fn declare_element_references(&mut self, items: &ElementItems, segment: &ElementSegment) {
+        match (items, &segment.items) {
+            (_, ElementSegmentItems::Functions(functions)) => {
+                self.references.extend(functions);
+            }
+            (ElementItems::Expressions(_, expressions), _) => {
+                for expression in expressions {
+                    self.declare_references(expression);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Number of imports of `kind`, which come first in its index space
+    fn imported(&self, kind: ExternalKind) -> u32 {
+        self.imports
+            .iter()
+            .filter(|import| import.descriptor.kind() == kind)
+            .count() as u32
+    }
+
+    fn global_type(&self, global_index: u32) -> &GlobalType {
+        self.globals
+            .get(global_index)
+            .expect("global indices should be resolved before looking up their type")
+    }
+
+    /// Addresses into 64-bit memories are `i64` values, all others are `i32`
+    fn memory_index_type(&self, memory_index: u32) -> BuiltinType {
+        self.memories
+            .get(memory_index)
+            .expect("memory indices should be resolved before looking up their type")
+            .index_type
+    }
+
+    fn tag_type(&self, tag_index: u32) -> &FunctionType {
+        let type_index = self
+            .tags
+            .get(tag_index)
+            .expect("tag indices should be resolved before looking up their type");
+
+        self.function_type_at(*type_index)
+    }
+
+    fn function_type(&self, function_index: u32) -> &FunctionType {
+        let type_index = self
+            .functions
+            .get(function_index)
+            .expect("function indices should be resolved before looking up their type");
+
+        self.function_type_at(*type_index)
+    }
+
+    /// Type uses are checked to refer to function types when they are declared
+    fn function_type_at(&self, type_index: u32) -> &FunctionType {
+        self.defined_type(type_index)
+            .as_function()
+            .expect("type uses should refer to function types")
+    }
+
+    fn encode(&self, code: Vec<BytecodeContainer>) -> BytecodeContainer {
+        let mut sections = Vec::new();
+
+        if !self.recursion_groups.is_empty() {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.recursion_groups.len() as u32);
+
+            // Types outside of a `rec` clause are groups of one, which need no marker
+            for (start, len) in &self.recursion_groups {
+                if *len != 1 {
+                    section.push_byte(ByteValue::RecGroup as u8);
+                    section.push_u32(*len);
+                }
+
+                for index in *start..start + len {
+                    self.defined_type(index).encode(&mut section);
+                }
+            }
+
+            sections.push((SectionId::Type, section));
+        }
+
+        if !self.imports.is_empty() {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.imports.len() as u32);
+
+            for import in &self.imports {
+                import.encode(&mut section);
+            }
+
+            sections.push((SectionId::Import, section));
+        }
+
+        let imported_functions = self.imported(ExternalKind::Func);
+
+        if self.functions.len() > imported_functions {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.functions.len() - imported_functions);
+
+            for type_index in self.functions.iter().skip(imported_functions as usize) {
+                section.push_u32(*type_index);
+            }
+
+            sections.push((SectionId::Function, section));
+        }
+
+        let imported_tables = self.imported(ExternalKind::Table);
+
+        if self.tables.len() > imported_tables {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.tables.len() - imported_tables);
+
+            let defined_tables = self.tables.iter().skip(imported_tables as usize);
+
+            for (table_type, initializer) in defined_tables.zip(&self.table_initializers) {
+                match initializer {
+                    Some(initializer) => {
+                        // Tables with an initializer are prefixed to tell them apart
+                        section.push_bytes(&[0x40, 0x00]);
+                        encode_table_type(&mut section, table_type);
+                        section.push_bytes(&initializer.data);
+                    }
+                    None => encode_table_type(&mut section, table_type),
+                }
+            }
+
+            sections.push((SectionId::Table, section));
         }
+
+        let imported_memories = self.imported(ExternalKind::Memory);
+
+        if self.memories.len() > imported_memories {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.memories.len() - imported_memories);
+
+            for limits in self.memories.iter().skip(imported_memories as usize) {
+                encode_limits(&mut section, limits);
+            }
+
+            sections.push((SectionId::Memory, section));
+        }
+
+        let imported_tags = self.imported(ExternalKind::Tag);
+
+        if self.tags.len() > imported_tags {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.tags.len() - imported_tags);
+
+            for type_index in self.tags.iter().skip(imported_tags as usize) {
+                encode_tag_type(&mut section, *type_index);
+            }
+
+            sections.push((SectionId::Tag, section));
+        }
+
+        if !self.global_initializers.is_empty() {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.global_initializers.len() as u32);
+
+            let defined_globals = self
+                .globals
+                .iter()
+                .skip(self.imported(ExternalKind::Global) as usize);
+
+            for (global_type, initializer) in defined_globals.zip(&self.global_initializers) {
+                encode_global_type(&mut section, global_type);
+                section.push_bytes(&initializer.data);
+            }
+
+            sections.push((SectionId::Global, section));
+        }
+
+        if !self.exports.is_empty() {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.exports.len() as u32);
+
+            for (name, kind, index) in &self.exports {
+                section.push_name(name);
+                section.push_byte(*kind as u8);
+                section.push_u32(*index);
+            }
+
+            sections.push((SectionId::Export, section));
+        }
+
+        if let Some(function_index) = self.start {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(function_index);
+
+            sections.push((SectionId::Start, section));
+        }
+
+        if !self.elements.is_empty() {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.elements.len());
+
+            for segment in self.elements.iter() {
+                segment.encode(&mut section);
+            }
+
+            sections.push((SectionId::Element, section));
+        }
+
+        if self.uses_data_count {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.data.len());
+
+            sections.push((SectionId::DataCount, section));
+        }
+
+        if !code.is_empty() {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(code.len() as u32);
+
+            for body in &code {
+                section.push_sized(body);
+            }
+
+            sections.push((SectionId::Code, section));
+        }
+
+        if !self.data.is_empty() {
+            let mut section = BytecodeContainer::default();
+
+            section.push_u32(self.data.len());
+
+            for segment in self.data.iter() {
+                segment.encode(&mut section);
+            }
+
+            sections.push((SectionId::Data, section));
+        }
+
+        let mut bytecode = BytecodeContainer::new_module();
+
+        self.push_custom_sections(&mut bytecode, CustomPlacement::First);
+
+        for id in SECTION_ORDER {
+            self.push_custom_sections(&mut bytecode, CustomPlacement::Before(id));
+
+            if let Some((_, section)) = sections.iter().find(|(section_id, _)| *section_id == id) {
+                bytecode.push_section(id, section);
+            }
+
+            self.push_custom_sections(&mut bytecode, CustomPlacement::After(id));
+        }
+
+        bytecode
     }
+
+    fn push_custom_sections(&self, bytecode: &mut BytecodeContainer, placement: CustomPlacement) {
+        for custom in &self.custom_sections {
+            if custom.placement == placement {
+                let mut section = BytecodeContainer::default();
+
+                section.push_name(&custom.name);
+                section.push_bytes(&custom.bytes);
+
+                bytecode.push_section(SectionId::Custom, &section);
+            }
+        }
+    }
+}
+
+/// Settings for the optional parts of the output and the proposals the compiler accepts
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// Emit the `name` custom section, so engines can show source identifiers in stack traces
+    pub debug_names: bool,
+    /// Emit the `producers` custom section, recording the compiler that built the module
+    pub producers: bool,
+    /// Name and version of every SDK listed in the `producers` section
+    pub sdks: Vec<(String, String)>,
+    /// Proposals accepted on top of the MVP
+    pub features: WasmFeatures,
 }
 
-pub fn compile(tree: Expr) -> Result<BytecodeContainer, Box<dyn ErrorDisplay>> {
-    let Expr::Module(Module { exprs }) = tree else {
-        todo!("Currently does not support compiling non modules");
-    };
+pub fn compile(module: Module, options: &CompileOptions) -> Result<BytecodeContainer, Error> {
+    let Module { id, exprs, .. } = module;
 
-    let mut bc = BytecodeModule::new();
+    let mut bc = BytecodeModule::new(options.clone());
 
-    for expr in exprs {
+    // Explicit types come first, the implicit ones from type uses are appended after them
+    for expr in &exprs {
         match expr {
+            Expr::Type(definition) => {
+                bc.declare_recursion_group(std::slice::from_ref(definition))?;
+            }
+
+            Expr::Rec(group) if !options.features.gc => {
+                return Err(Error::from(DisabledFeatureError {
+                    construct: "`rec`".to_owned(),
+                    feature: "gc",
+                    kind: DiagnosticKind::Validate,
+                    cursor: group.cursor,
+                }))
+            }
+
+            Expr::Rec(group) => bc.declare_recursion_group(&group.types)?,
+
+            _ => {}
+        }
+    }
+
+    // Imports precede the definitions in every index space
+    let mut has_definitions = false;
+
+    for expr in &exprs {
+        match expr {
+            Expr::Func(_) | Expr::Table(_) | Expr::Memory(_) | Expr::Global(_) | Expr::Tag(_) => {
+                has_definitions = true;
+            }
+
+            Expr::Import(import) if has_definitions => {
+                return Err(Error::from(ImportAfterDefinitionError {
+                    cursor: import.cursor,
+                }))
+            }
+
+            _ => {}
+        }
+
+        if let Expr::Import(import) = expr {
+            let descriptor = match &import.description {
+                ImportDescription::Func(type_use) => {
+                    let type_index = bc.declare_type_use(type_use, import.cursor)?;
+
+                    bc.functions
+                        .push(import.id.clone(), type_index, import.cursor)?;
+
+                    ImportDescriptor::Func(type_index)
+                }
+                ImportDescription::Table(table_type) => {
+                    bc.declare_table(import.id.clone(), table_type, import.cursor)?;
+
+                    ImportDescriptor::Table(table_type.clone())
+                }
+                ImportDescription::Memory(limits) => {
+                    bc.declare_memory(import.id.clone(), limits, import.cursor)?;
+
+                    ImportDescriptor::Memory(limits.clone())
+                }
+                ImportDescription::Global(global_type) => {
+                    bc.check_value_types(&[global_type.value_type], import.cursor)?;
+                    bc.check_shared_global(global_type, "Importing", import.cursor)?;
+                    bc.globals
+                        .push(import.id.clone(), *global_type, import.cursor)?;
+
+                    ImportDescriptor::Global(*global_type)
+                }
+                ImportDescription::Tag(type_use) => {
+                    let type_index = bc.declare_tag(import.id.clone(), type_use, import.cursor)?;
+
+                    ImportDescriptor::Tag(type_index)
+                }
+            };
+
+            bc.imports.push(ImportEntry {
+                module: import.module.clone(),
+                name: import.name.clone(),
+                descriptor,
+            });
+        }
+    }
+
+    let mut definitions: Vec<&FunctionDefinition> = Vec::new();
+    let mut table_definitions: Vec<&TableDefinition> = Vec::new();
+    let mut global_definitions: Vec<&GlobalDefinition> = Vec::new();
+
+    for expr in &exprs {
+        match expr {
+            Expr::Type(_)
+            | Expr::Rec(_)
+            | Expr::Elem(_)
+            | Expr::Data(_)
+            | Expr::Import(_)
+            | Expr::Export(_)
+            | Expr::Start(_) => {}
+
+            Expr::Custom(definition) => bc.custom_sections.push(definition.clone()),
+
             Expr::Func(definition) => {
-                bc.functions.insert(
-                    definition
-                        .id
-                        .clone()
-                        // TODO: Gracefully handle this error
-                        .expect("Top level function definitions should have identifiers"),
-                    definition,
-                );
+                let type_index = bc.declare_type_use(&definition.type_use, definition.cursor)?;
+
+                for instruction in &definition.body {
+                    match &instruction.kind {
+                        InstructionKind::CallIndirect(_, type_use)
+                        | InstructionKind::ReturnCallIndirect(_, type_use)
+                        | InstructionKind::Block(_, BlockType::TypeUse(type_use))
+                        | InstructionKind::Loop(_, BlockType::TypeUse(type_use))
+                        | InstructionKind::If(_, BlockType::TypeUse(type_use))
+                        | InstructionKind::TryTable(_, BlockType::TypeUse(type_use), _) => {
+                            bc.declare_type_use(type_use, instruction.cursor)?;
+                        }
+                        _ => {}
+                    }
+                }
+
+                bc.functions
+                    .push(definition.id.clone(), type_index, definition.cursor)?;
+
+                definitions.push(definition);
+            }
+
+            Expr::Table(definition) => {
+                bc.declare_table(
+                    definition.id.clone(),
+                    &definition.table_type,
+                    definition.cursor,
+                )?;
+
+                if definition.init.is_some()
+                    && !bc.options.features.is_enabled("function-references")
+                {
+                    return Err(Error::from(DisabledFeatureError {
+                        construct: "Initializing a table with an expression".to_owned(),
+                        feature: "function-references",
+                        kind: DiagnosticKind::Validate,
+                        cursor: definition.cursor,
+                    }));
+                }
+
+                // Defined tables without an initializer start out filled with null,
+                // unlike imported ones
+                if definition.init.is_none() && !definition.table_type.element_type.is_defaultable()
+                {
+                    return Err(Error::from(TypeMismatchError {
+                        expected: "a nullable reference type".to_owned(),
+                        found: format_types(&[definition.table_type.element_type]),
+                        cursor: definition.cursor,
+                    }));
+                }
+
+                table_definitions.push(definition);
+            }
+
+            Expr::Memory(definition) => {
+                bc.declare_memory(definition.id.clone(), &definition.limits, definition.cursor)?;
             }
 
-            Expr::Export(_) => todo!(),
+            Expr::Global(definition) => {
+                bc.check_value_types(&[definition.global_type.value_type], definition.cursor)?;
+                bc.globals.push(
+                    definition.id.clone(),
+                    definition.global_type,
+                    definition.cursor,
+                )?;
 
-            _ => {
-                return Err(Box::new(MisplacedError {
-                    cursor: PageCursor::start(),
+                global_definitions.push(definition);
+            }
+
+            Expr::Tag(definition) => {
+                bc.declare_tag(
+                    definition.id.clone(),
+                    &definition.type_use,
+                    definition.cursor,
+                )?;
+            }
+
+            Expr::Module(nested) => {
+                return Err(Error::from(MisplacedError {
+                    cursor: nested.cursor,
                 }))
             }
         }
     }
 
-    Ok(Default::default())
+    // Initializers can refer to functions defined anywhere in the module
+    for definition in table_definitions {
+        let initializer = match &definition.init {
+            Some(init) => {
+                let initializer = compile_constant_expression(
+                    &bc,
+                    init,
+                    definition.table_type.element_type,
+                    definition.cursor,
+                )?;
+
+                bc.declare_references(init);
+
+                Some(initializer)
+            }
+            None => None,
+        };
+
+        bc.table_initializers.push(initializer);
+    }
+
+    for definition in global_definitions {
+        let initializer = compile_constant_expression(
+            &bc,
+            &definition.init,
+            definition.global_type.value_type,
+            definition.cursor,
+        )?;
+
+        bc.global_initializers.push(initializer);
+        bc.declare_references(&definition.init);
+    }
+
+    // Segments are declared once all tables and memories are known, in the order they were written
+    let mut table_index = bc.imported(ExternalKind::Table);
+    let mut memory_index = bc.imported(ExternalKind::Memory);
+
+    for expr in &exprs {
+        match expr {
+            Expr::Table(definition) => {
+                if let Some(items) = &definition.inline_elements {
+                    let (element_type, segment_items) =
+                        bc.element_items(items, definition.cursor)?;
+
+                    bc.check_table_elements(table_index, element_type, definition.cursor)?;
+
+                    let segment = ElementSegment {
+                        mode: ElementSegmentMode::Active {
+                            table: table_index,
+                            offset: zero_offset(BuiltinType::I32),
+                        },
+                        element_type,
+                        items: segment_items,
+                    };
+
+                    bc.declare_element_references(items, &segment);
+                    bc.elements.push(None, segment, definition.cursor)?;
+                }
+
+                table_index += 1;
+            }
+
+            Expr::Elem(definition) => {
+                let segment = bc.element_segment(definition)?;
+
+                bc.declare_element_references(&definition.items, &segment);
+                bc.elements
+                    .push(definition.id.clone(), segment, definition.cursor)?;
+            }
+
+            Expr::Memory(definition) => {
+                if let Some(bytes) = &definition.inline_data {
+                    let segment = DataSegment {
+                        mode: DataSegmentMode::Active {
+                            memory: memory_index,
+                            offset: zero_offset(bc.memory_index_type(memory_index)),
+                        },
+                        bytes: bytes.clone(),
+                    };
+
+                    bc.data.push(None, segment, definition.cursor)?;
+                }
+
+                memory_index += 1;
+            }
+
+            Expr::Data(definition) => {
+                let segment = bc.data_segment(definition)?;
+
+                bc.data
+                    .push(definition.id.clone(), segment, definition.cursor)?;
+            }
+
+            _ => {}
+        }
+    }
+
+    bc.uses_data_count = definitions
+        .iter()
+        .flat_map(|definition| &definition.body)
+        .any(|instruction| {
+            matches!(
+                instruction.kind,
+                InstructionKind::MemoryInit(..)
+                    | InstructionKind::DataDrop(_)
+                    | InstructionKind::ArraySegment(
+                        Mnemonic::ArrayNewData | Mnemonic::ArrayInitData,
+                        ..
+                    )
+            )
+        });
+
+    let mut export_names = HashMap::new();
+
+    for expr in &exprs {
+        if let Expr::Export(export) = expr {
+            if let Some(previous) = export_names.insert(export.name.clone(), export.cursor) {
+                return Err(Error::from(DuplicateExportError {
+                    name: export.name.clone(),
+                    cursor: export.cursor,
+                    previous,
+                }));
+            }
+
+            let index = match export.kind {
+                ExternalKind::Func => {
+                    let function_index = bc.functions.resolve(&export.index, export.cursor)?;
+
+                    bc.references.insert(function_index);
+
+                    function_index
+                }
+                ExternalKind::Table => bc.tables.resolve(&export.index, export.cursor)?,
+                ExternalKind::Memory => bc.memories.resolve(&export.index, export.cursor)?,
+                ExternalKind::Global => {
+                    let global_index = bc.globals.resolve(&export.index, export.cursor)?;
+                    let global_type = bc
+                        .globals
+                        .get(global_index)
+                        .expect("resolved globals should exist");
+
+                    bc.check_shared_global(global_type, "Exporting", export.cursor)?;
+
+                    global_index
+                }
+                ExternalKind::Tag => bc.tags.resolve(&export.index, export.cursor)?,
+            };
+
+            bc.exports.push((export.name.clone(), export.kind, index));
+        }
+    }
+
+    for expr in &exprs {
+        if let Expr::Start(start) = expr {
+            if bc.start.is_some() {
+                return Err(Error::from(MultipleDefinitionsError {
+                    kind: "start function",
+                    cursor: start.cursor,
+                }));
+            }
+
+            let function_index = bc.functions.resolve(&start.function, start.cursor)?;
+            let function_type = bc.function_type(function_index);
+
+            if *function_type != FunctionType::default() {
+                return Err(Error::from(TypeMismatchError {
+                    expected: FunctionType::default().to_string(),
+                    found: function_type.to_string(),
+                    cursor: start.cursor,
+                }));
+            }
+
+            bc.start = Some(function_index);
+        }
+    }
+
+    let code = definitions
+        .iter()
+        .map(|definition| FunctionCompiler::new(&bc, definition)?.compile())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut bytecode = bc.encode(code);
+
+    if options.debug_names {
+        if let Some(names) = encode_name_section(&bc, id.as_ref(), &definitions) {
+            bytecode.push_section(SectionId::Custom, &names);
+        }
+    }
+
+    if options.producers {
+        let producers = encode_producers_section(&options.sdks);
+
+        bytecode.push_section(SectionId::Custom, &producers);
+    }
+
+    bc.push_custom_sections(&mut bytecode, CustomPlacement::Last);
+
+    Ok(bytecode)
+}
+
+/// Instructions on composite types name the type they work on, which has to be of the right kind
+fn expected_kind(kind: &str, index: &Index, cursor: PageCursor) -> TypeMismatchError {
+    TypeMismatchError {
+        expected: kind.to_owned(),
+        found: format!("type {}", index),
+        cursor,
+    }
 }
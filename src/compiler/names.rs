@@ -0,0 +1,193 @@
+use crate::{
+    bytecode::container::BytecodeContainer,
+    parser::{
+        expression::{ExternalKind, FunctionDefinition},
+        instructions::InstructionKind,
+    },
+    shared::Identifier,
+};
+
+use super::BytecodeModule;
+
+/// Subsections of the `name` custom section, including the ones of the extended name proposal
+enum NameSubsection {
+    Module = 0,
+    Function = 1,
+    Local = 2,
+    Label = 3,
+    Type = 4,
+    Table = 5,
+    Memory = 6,
+    Global = 7,
+    Element = 8,
+    Data = 9,
+    Field = 10,
+    Tag = 11,
+}
+
+type NameMap<'a> = Vec<(u32, &'a Identifier)>;
+
+/// Builds the contents of the `name` custom section from the identifiers used in the source,
+/// or nothing when none of them have a name
+pub fn encode_name_section(
+    module: &BytecodeModule,
+    module_name: Option<&Identifier>,
+    definitions: &[&FunctionDefinition],
+) -> Option<BytecodeContainer> {
+    let mut section = BytecodeContainer::default();
+
+    section.push_name("name");
+
+    let header_length = section.data.len();
+
+    if let Some(module_name) = module_name {
+        let mut subsection = BytecodeContainer::default();
+
+        subsection.push_name(&module_name.0);
+        push_subsection(&mut section, NameSubsection::Module, &subsection);
+    }
+
+    push_name_map(
+        &mut section,
+        NameSubsection::Function,
+        module.functions.names(),
+    );
+
+    // Definitions follow the imported functions, which have neither locals nor labels
+    let function_indices = (module.imported(ExternalKind::Func)..).zip(definitions);
+
+    let local_names = function_indices
+        .clone()
+        .map(|(function_index, definition)| {
+            (
+                function_index,
+                local_names(module, function_index, definition),
+            )
+        })
+        .collect();
+
+    let label_names = function_indices
+        .map(|(function_index, definition)| (function_index, label_names(definition)))
+        .collect();
+
+    push_indirect_name_map(&mut section, NameSubsection::Local, local_names);
+    push_indirect_name_map(&mut section, NameSubsection::Label, label_names);
+
+    push_name_map(&mut section, NameSubsection::Type, module.types.names());
+    push_name_map(&mut section, NameSubsection::Table, module.tables.names());
+    push_name_map(
+        &mut section,
+        NameSubsection::Memory,
+        module.memories.names(),
+    );
+    push_name_map(&mut section, NameSubsection::Global, module.globals.names());
+    push_name_map(
+        &mut section,
+        NameSubsection::Element,
+        module.elements.names(),
+    );
+    push_name_map(&mut section, NameSubsection::Data, module.data.names());
+
+    let mut field_names = module
+        .fields
+        .iter()
+        .map(|(type_index, fields)| (*type_index, fields.names()))
+        .collect::<Vec<_>>();
+
+    field_names.sort_by_key(|(type_index, _)| *type_index);
+
+    push_indirect_name_map(&mut section, NameSubsection::Field, field_names);
+    push_name_map(&mut section, NameSubsection::Tag, module.tags.names());
+
+    (section.data.len() > header_length).then_some(section)
+}
+
+/// Parameters are numbered first, followed by the declared locals
+fn local_names<'a>(
+    module: &BytecodeModule,
+    function_index: u32,
+    definition: &'a FunctionDefinition,
+) -> NameMap<'a> {
+    let param_count = module.function_type(function_index).params.len() as u32;
+
+    let params = (0..).zip(&definition.type_use.signature.params);
+    let locals = (param_count..).zip(&definition.locals);
+
+    params
+        .filter_map(|(index, param)| Some((index, param.id.as_ref()?)))
+        .chain(locals.filter_map(|(index, local)| Some((index, local.id.as_ref()?))))
+        .collect()
+}
+
+/// Labels are numbered in the order their blocks appear in the body
+fn label_names(definition: &FunctionDefinition) -> NameMap<'_> {
+    definition
+        .body
+        .iter()
+        .filter_map(|instruction| match &instruction.kind {
+            InstructionKind::Block(label, _)
+            | InstructionKind::Loop(label, _)
+            | InstructionKind::If(label, _)
+            | InstructionKind::TryTable(label, ..) => Some(label.as_ref()),
+            _ => None,
+        })
+        .zip(0..)
+        .filter_map(|(label, index)| Some((index, label?)))
+        .collect()
+}
+
+fn encode_name_map(bytecode: &mut BytecodeContainer, names: &NameMap) {
+    bytecode.push_u32(names.len() as u32);
+
+    for (index, id) in names {
+        bytecode.push_u32(*index);
+        bytecode.push_name(&id.0);
+    }
+}
+
+fn push_subsection(
+    section: &mut BytecodeContainer,
+    id: NameSubsection,
+    contents: &BytecodeContainer,
+) {
+    section.push_byte(id as u8);
+    section.push_sized(contents);
+}
+
+/// Empty maps are left out entirely
+fn push_name_map(section: &mut BytecodeContainer, id: NameSubsection, names: NameMap) {
+    if names.is_empty() {
+        return;
+    }
+
+    let mut subsection = BytecodeContainer::default();
+
+    encode_name_map(&mut subsection, &names);
+    push_subsection(section, id, &subsection);
+}
+
+fn push_indirect_name_map(
+    section: &mut BytecodeContainer,
+    id: NameSubsection,
+    names: Vec<(u32, NameMap)>,
+) {
+    let names = names
+        .into_iter()
+        .filter(|(_, names)| !names.is_empty())
+        .collect::<Vec<_>>();
+
+    if names.is_empty() {
+        return;
+    }
+
+    let mut subsection = BytecodeContainer::default();
+
+    subsection.push_u32(names.len() as u32);
+
+    for (index, names) in &names {
+        subsection.push_u32(*index);
+        encode_name_map(&mut subsection, names);
+    }
+
+    push_subsection(section, id, &subsection);
+}
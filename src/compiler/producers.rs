@@ -0,0 +1,42 @@
+use crate::bytecode::container::BytecodeContainer;
+
+/// Builds the contents of the `producers` custom section from the tool conventions,
+/// naming the source language, this compiler and the SDKs given as `(name, version)` pairs
+pub fn encode_producers_section(sdks: &[(String, String)]) -> BytecodeContainer {
+    let mut section = BytecodeContainer::default();
+
+    section.push_name("producers");
+
+    let language = [("wat", "")];
+    let processed_by = [("wat-to-wasm", env!("CARGO_PKG_VERSION"))];
+    let sdks = sdks
+        .iter()
+        .map(|(name, version)| (name.as_str(), version.as_str()))
+        .collect::<Vec<_>>();
+
+    let fields = [
+        ("language", language.as_slice()),
+        ("processed-by", processed_by.as_slice()),
+        ("sdk", sdks.as_slice()),
+    ];
+
+    // Fields without any values are left out
+    let fields = fields
+        .iter()
+        .filter(|(_, values)| !values.is_empty())
+        .collect::<Vec<_>>();
+
+    section.push_u32(fields.len() as u32);
+
+    for (field_name, values) in fields {
+        section.push_name(field_name);
+        section.push_u32(values.len() as u32);
+
+        for (name, version) in *values {
+            section.push_name(name);
+            section.push_name(version);
+        }
+    }
+
+    section
+}
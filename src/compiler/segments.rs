@@ -0,0 +1,151 @@
+use crate::{
+    bytecode::container::BytecodeContainer,
+    parser::instructions::{BuiltinType, Mnemonic},
+};
+
+use super::types::encode_value_type;
+
+/// Offset expression of segments written to the start of a memory or table,
+/// which is addressed with `index_type`
+pub fn zero_offset(index_type: BuiltinType) -> BytecodeContainer {
+    let mut offset = BytecodeContainer::default();
+
+    if index_type == BuiltinType::I64 {
+        offset.push_opcode(Mnemonic::I64Const.opcode());
+        offset.push_i64(0);
+    } else {
+        offset.push_opcode(Mnemonic::I32Const.opcode());
+        offset.push_i32(0);
+    }
+
+    offset.push_opcode(Mnemonic::End.opcode());
+
+    offset
+}
+
+#[derive(Debug)]
+pub enum DataSegmentMode {
+    Passive,
+    Active {
+        memory: u32,
+        /// Encoded constant expression computing the address the data is written to
+        offset: BytecodeContainer,
+    },
+}
+
+/// Bytes that are either copied into a memory when the module is instantiated,
+/// or kept around for `memory.init`
+#[derive(Debug)]
+pub struct DataSegment {
+    pub mode: DataSegmentMode,
+    pub bytes: Vec<u8>,
+}
+
+impl DataSegment {
+    pub fn encode(&self, section: &mut BytecodeContainer) {
+        match &self.mode {
+            DataSegmentMode::Passive => section.push_u32(0x01),
+            // Segments for the first memory have a shorter encoding without the memory index
+            DataSegmentMode::Active { memory: 0, offset } => {
+                section.push_u32(0x00);
+                section.push_bytes(&offset.data);
+            }
+            DataSegmentMode::Active { memory, offset } => {
+                section.push_u32(0x02);
+                section.push_u32(*memory);
+                section.push_bytes(&offset.data);
+            }
+        }
+
+        section.push_u32(self.bytes.len() as u32);
+        section.push_bytes(&self.bytes);
+    }
+}
+
+#[derive(Debug)]
+pub enum ElementSegmentMode {
+    Passive,
+    Declarative,
+    Active {
+        table: u32,
+        /// Encoded constant expression computing the first table slot that is written
+        offset: BytecodeContainer,
+    },
+}
+
+#[derive(Debug)]
+pub enum ElementSegmentItems {
+    Functions(Vec<u32>),
+    /// Encoded constant expressions
+    Expressions(Vec<BytecodeContainer>),
+}
+
+/// References that are either written to a table when the module is instantiated,
+/// or kept around for `table.init`
+#[derive(Debug)]
+pub struct ElementSegment {
+    pub mode: ElementSegmentMode,
+    pub element_type: BuiltinType,
+    pub items: ElementSegmentItems,
+}
+
+impl ElementSegment {
+    pub fn encode(&self, section: &mut BytecodeContainer) {
+        // Bit 0 marks segments that aren't active, bit 1 an explicit table index
+        // or a declarative segment and bit 2 elements given as expressions
+        let mut kind = BytecodeContainer::default();
+        let flags = match &self.items {
+            ElementSegmentItems::Functions(_) => {
+                kind.push_byte(0x00);
+
+                0b000
+            }
+            ElementSegmentItems::Expressions(_) => {
+                encode_value_type(&mut kind, self.element_type);
+
+                0b100
+            }
+        };
+
+        match &self.mode {
+            // Active segments of the first table have a shorter encoding, which implies `funcref`
+            ElementSegmentMode::Active { table: 0, offset }
+                if self.element_type == BuiltinType::FuncRef =>
+            {
+                section.push_u32(flags);
+                section.push_bytes(&offset.data);
+            }
+            ElementSegmentMode::Active { table, offset } => {
+                section.push_u32(flags | 0b010);
+                section.push_u32(*table);
+                section.push_bytes(&offset.data);
+                section.push_bytes(&kind.data);
+            }
+            ElementSegmentMode::Passive => {
+                section.push_u32(flags | 0b001);
+                section.push_bytes(&kind.data);
+            }
+            ElementSegmentMode::Declarative => {
+                section.push_u32(flags | 0b011);
+                section.push_bytes(&kind.data);
+            }
+        }
+
+        match &self.items {
+            ElementSegmentItems::Functions(functions) => {
+                section.push_u32(functions.len() as u32);
+
+                for function in functions {
+                    section.push_u32(*function);
+                }
+            }
+            ElementSegmentItems::Expressions(expressions) => {
+                section.push_u32(expressions.len() as u32);
+
+                for expression in expressions {
+                    section.push_bytes(&expression.data);
+                }
+            }
+        }
+    }
+}
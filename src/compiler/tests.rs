@@ -0,0 +1,1572 @@
+use crate::{
+    compile, compile_with_options,
+    diagnostic::{DiagnosticKind, DisabledFeatureError, Label, Severity, Span},
+    CompileOptions, Error, WasmFeatures,
+};
+
+/// Compiles `source` and strips the preamble so tests only have to spell out the sections
+fn compile_sections(source: &str) -> Vec<u8> {
+    let bytes = compile(source.to_owned()).unwrap_or_else(|errors| panic!("{}", errors[0]));
+
+    bytes[8..].to_vec()
+}
+
+#[test]
+fn test_call_resolves_function_index() {
+    let sections = compile_sections(
+        "(module
+            (func $one (result i32) (i32.const 1))
+            (func $two (result i32) (call $one)))",
+    );
+
+    assert_eq!(
+        sections,
+        vec![
+            // Both functions share the type [] -> [i32]
+            0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7F, 0x03, 0x03, 0x02, 0x00, 0x00, 0x0A, 0x0B,
+            0x02, 0x04, 0x00, 0x41, 0x01, 0x0B, 0x04, 0x00, 0x10, 0x00, 0x0B,
+        ]
+    );
+}
+
+#[test]
+fn test_call_checks_signature() {
+    let result = compile(
+        "(module
+            (func $f (param $x i32))
+            (func $g (call $f (f32.const 1))))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [i32] but found [f32]")));
+}
+
+#[test]
+fn test_call_indirect_uses_type_index() {
+    let sections = compile_sections(
+        "(module
+            (type $t (func (param i32) (result i32)))
+            (table 1 funcref)
+            (func $f (result i32)
+                (call_indirect (type $t) (i32.const 5) (i32.const 0))))",
+    );
+
+    assert!(sections.ends_with(&[0x09, 0x00, 0x41, 0x05, 0x41, 0x00, 0x11, 0x00, 0x00, 0x0B]));
+}
+
+#[test]
+fn test_branches_check_label_types() {
+    let sections = compile_sections(
+        "(module
+            (func (result i32)
+                (block (result i32) (i32.const 1) (br 0))))",
+    );
+
+    assert!(sections.ends_with(&[0x09, 0x00, 0x02, 0x7F, 0x41, 0x01, 0x0C, 0x00, 0x0B, 0x0B]));
+
+    let result = compile(
+        "(module
+            (func (result i32)
+                (block (result i32) (f32.const 1) (br 0))))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [i32] but found [f32]")));
+
+    let result = compile("(module (func (result i32) (block (result i32) (br 0))))".to_owned());
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [i32] but found an empty stack")));
+}
+
+#[test]
+fn test_br_table_checks_every_label() {
+    let sections = compile_sections(
+        "(module
+            (func (param i32) (result i32)
+                (block (result i32)
+                    (block (result i32)
+                        (br_table 0 1 (i32.const 1) (local.get 0))))))",
+    );
+
+    assert!(sections.ends_with(&[
+        0x10, 0x00, 0x02, 0x7F, 0x02, 0x7F, 0x41, 0x01, 0x20, 0x00, 0x0E, 0x01, 0x00, 0x01, 0x0B,
+        0x0B, 0x0B,
+    ]));
+
+    // The default label expects an i32 while the inner block expects an f32
+    let result = compile(
+        "(module
+            (func (param i32) (result i32)
+                (block (result i32)
+                    (block (result f32)
+                        (br_table 0 1 (f32.const 1) (local.get 0)))
+                    (drop)
+                    (i32.const 0))))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [i32] but found [f32]")));
+}
+
+#[test]
+fn test_if_without_else() {
+    let sections = compile_sections("(module (func (param i32) (if (local.get 0) (then (nop)))))");
+
+    assert!(sections.ends_with(&[0x08, 0x00, 0x20, 0x00, 0x04, 0x40, 0x01, 0x0B, 0x0B]));
+
+    // The missing else branch produces nothing, so the if cannot have results
+    let result = compile(
+        "(module
+            (func (param i32) (result i32)
+                (if (result i32) (local.get 0) (then (i32.const 1)))))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [i32] but found []")));
+}
+
+#[test]
+fn test_unreachable_stack_is_polymorphic() {
+    let sections = compile_sections("(module (func (result i32) (unreachable) (i32.add)))");
+
+    assert!(sections.ends_with(&[0x04, 0x00, 0x00, 0x6A, 0x0B]));
+
+    // Operands pushed after unreachable are still checked
+    let result =
+        compile("(module (func (result i32) (unreachable) (f32.const 0) (i32.add)))".to_owned());
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [i32] but found [f32]")));
+
+    let result = compile("(module (func (unreachable) (i32.const 1)))".to_owned());
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [] but found [i32]")));
+}
+
+#[test]
+fn test_memory_instructions_encode_memarg() {
+    let sections = compile_sections(
+        "(module
+            (memory 1 2)
+            (func $f (param $a i32) (result i32)
+                (i32.load16_u offset=8 (local.get $a))))",
+    );
+
+    // Memory section with both limits
+    assert!(sections
+        .windows(5)
+        .any(|window| window == [0x05, 0x04, 0x01, 0x01, 0x01]));
+    // Alignment is encoded as its base 2 logarithm
+    assert!(sections.ends_with(&[0x20, 0x00, 0x2F, 0x01, 0x08, 0x0B]));
+}
+
+#[test]
+fn test_inline_data_becomes_active_segment() {
+    let sections = compile_sections("(module (memory (data \"hi\")))");
+
+    assert_eq!(
+        sections,
+        vec![
+            0x05, 0x04, 0x01, 0x01, 0x01, 0x01, 0x0B, 0x08, 0x01, 0x00, 0x41, 0x00, 0x0B, 0x02,
+            b'h', b'i',
+        ]
+    );
+}
+
+#[test]
+fn test_alignment_cannot_exceed_natural_alignment() {
+    let result = compile(
+        "(module
+            (memory 1)
+            (func $f (drop (i32.load align=8 (i32.const 0)))))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Alignment 8 exceeds the natural alignment of `i32.load`")));
+}
+
+#[test]
+fn test_data_segments() {
+    let sections = compile_sections(
+        "(module
+            (memory 1)
+            (data (i32.const 16) \"a\" \"b\")
+            (data $passive \"c\"))",
+    );
+
+    assert_eq!(
+        sections,
+        vec![
+            0x05, 0x03, 0x01, 0x00, 0x01, 0x0B, 0x0B, 0x02, 0x00, 0x41, 0x10, 0x0B, 0x02, b'a',
+            b'b', 0x01, 0x01, b'c',
+        ]
+    );
+}
+
+#[test]
+fn test_data_count_precedes_code() {
+    let sections = compile_sections(
+        "(module
+            (memory 1)
+            (data $d \"c\")
+            (func $f (data.drop $d)))",
+    );
+
+    // Data count section followed by the code section
+    assert!(sections
+        .windows(5)
+        .any(|window| window == [0x0C, 0x01, 0x01, 0x0A, 0x07]));
+    assert!(sections.ends_with(&[0xFC, 0x09, 0x00, 0x0B, 0x0B, 0x04, 0x01, 0x01, 0x01, b'c']));
+}
+
+#[test]
+fn test_data_offset_must_be_constant() {
+    let result = compile(
+        "(module
+            (memory 1)
+            (data (offset (i32.const 1) (i32.const 2) (i32.div_s)) \"\"))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Instruction `i32.div_s` is not allowed in a constant expression")));
+}
+
+#[test]
+fn test_globals_follow_imports() {
+    let sections = compile_sections(
+        "(module
+            (import \"env\" \"base\" (global $base i32))
+            (global $g (mut i32) (global.get $base))
+            (export \"g\" (global $g)))",
+    );
+
+    assert_eq!(
+        sections,
+        [
+            // Import section
+            vec![
+                0x02, 0x0D, 0x01, 0x03, b'e', b'n', b'v', 0x04, b'b', b'a', b's', b'e', 0x03, 0x7F,
+                0x00,
+            ],
+            // Global section, initialized from the imported global
+            vec![0x06, 0x06, 0x01, 0x7F, 0x01, 0x23, 0x00, 0x0B],
+            // Export section
+            vec![0x07, 0x05, 0x01, 0x01, b'g', 0x03, 0x01],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_global_set_requires_mutable_global() {
+    let result = compile(
+        "(module
+            (global $g i32 (i32.const 0))
+            (func $f (global.set $g (i32.const 1))))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0].message.starts_with("Global $g is immutable")));
+}
+
+#[test]
+fn test_call_indirect_requires_table() {
+    let result = compile(
+        "(module
+            (func $f (call_indirect (i32.const 0))))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0].message.starts_with("Unknown table 0")));
+}
+
+#[test]
+fn test_inline_table_elements() {
+    let sections = compile_sections(
+        "(module
+            (table funcref (elem $f $f))
+            (func $f))",
+    );
+
+    assert_eq!(
+        sections,
+        [
+            vec![0x01, 0x04, 0x01, 0x60, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00],
+            // Table sized to fit its elements
+            vec![0x04, 0x05, 0x01, 0x70, 0x01, 0x02, 0x02],
+            // Active segment of the first table
+            vec![0x09, 0x08, 0x01, 0x00, 0x41, 0x00, 0x0B, 0x02, 0x00, 0x00],
+            vec![0x0A, 0x04, 0x01, 0x02, 0x00, 0x0B],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_element_segment_encodings() {
+    let sections = compile_sections(
+        "(module
+            (table 1 funcref)
+            (func $f)
+            (elem func $f)
+            (elem declare funcref (ref.func $f))
+            (elem (table 0) (i32.const 0) funcref (ref.null func)))",
+    );
+
+    let element_section = [
+        vec![0x09, 0x13, 0x03],
+        // Passive function indices
+        vec![0x01, 0x00, 0x01, 0x00],
+        // Declarative expressions
+        vec![0x07, 0x70, 0x01, 0xD2, 0x00, 0x0B],
+        // Active expressions of the first table
+        vec![0x04, 0x41, 0x00, 0x0B, 0x01, 0xD0, 0x70, 0x0B],
+    ]
+    .concat();
+
+    assert!(sections
+        .windows(element_section.len())
+        .any(|window| window == element_section));
+}
+
+#[test]
+fn test_imported_functions_precede_definitions() {
+    let sections = compile_sections(
+        "(module
+            (func $log (import \"env\" \"log\") (param i32))
+            (func $f (call $log (i32.const 1))))",
+    );
+
+    assert_eq!(
+        sections,
+        [
+            vec![0x01, 0x08, 0x02, 0x60, 0x01, 0x7F, 0x00, 0x60, 0x00, 0x00],
+            // Import section
+            vec![0x02, 0x0B, 0x01, 0x03, b'e', b'n', b'v', 0x03, b'l', b'o', b'g', 0x00, 0x00],
+            // Only the defined function is listed in the function section
+            vec![0x03, 0x02, 0x01, 0x01],
+            vec![0x0A, 0x08, 0x01, 0x06, 0x00, 0x41, 0x01, 0x10, 0x00, 0x0B],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_imports_come_before_definitions() {
+    let result = compile(
+        "(module
+            (memory 1)
+            (import \"env\" \"table\" (table 1 funcref)))"
+            .to_owned(),
+    );
+
+    assert!(
+        result.is_err_and(|errors| errors[0].message.starts_with("Imports have to come before"))
+    );
+}
+
+#[test]
+fn test_inline_exports_are_desugared() {
+    let sections = compile_sections("(module (memory (export \"a\") (export \"b\") 1))");
+
+    assert_eq!(
+        sections,
+        [
+            vec![0x05, 0x03, 0x01, 0x00, 0x01],
+            // Both exports point at the anonymous memory
+            vec![0x07, 0x09, 0x02, 0x01, b'a', 0x02, 0x00, 0x01, b'b', 0x02, 0x00],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_type_use_must_match_inline_signature() {
+    let result = compile(
+        "(module
+            (type $t (func (param i32) (result i32)))
+            (func $f (type $t) (param f32) (result i32) (i32.const 0)))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Inline signature doesn't match type $t, which is [i32] -> [i32]")));
+}
+
+#[test]
+fn test_start_section() {
+    let sections = compile_sections(
+        "(module
+            (func $unused)
+            (func $init)
+            (start $init))",
+    );
+
+    assert_eq!(
+        sections,
+        [
+            vec![0x01, 0x04, 0x01, 0x60, 0x00, 0x00, 0x03, 0x03, 0x02, 0x00, 0x00],
+            // Start section pointing at the second function
+            vec![0x08, 0x01, 0x01],
+            vec![0x0A, 0x07, 0x02, 0x02, 0x00, 0x0B, 0x02, 0x00, 0x0B],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_start_function_takes_no_arguments() {
+    let result = compile(
+        "(module
+            (func $init (param i32))
+            (start $init))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [] -> [] but found [i32] -> []")));
+}
+
+#[test]
+fn test_anonymous_functions_use_numeric_indices() {
+    let sections = compile_sections(
+        "(module $named
+            (func (result i32) (i32.const 1))
+            (func (result i32) (call 0)))",
+    );
+
+    assert!(sections.ends_with(&[0x04, 0x00, 0x10, 0x00, 0x0B]));
+}
+
+#[test]
+fn test_name_section() {
+    let options = CompileOptions {
+        debug_names: true,
+        ..Default::default()
+    };
+    let bytes = compile_with_options(
+        "(module $m
+            (func $f (param $a i32) (local i32) (local $b i32)
+                (block $exit)))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    let name_section = [
+        vec![0x00, 0x25, 0x04, b'n', b'a', b'm', b'e'],
+        // Module name
+        vec![0x00, 0x02, 0x01, b'm'],
+        // Function names
+        vec![0x01, 0x04, 0x01, 0x00, 0x01, b'f'],
+        // Local names skip the anonymous local
+        vec![
+            0x02, 0x09, 0x01, 0x00, 0x02, 0x00, 0x01, b'a', 0x02, 0x01, b'b',
+        ],
+        // Label names
+        vec![
+            0x03, 0x09, 0x01, 0x00, 0x01, 0x00, 0x04, b'e', b'x', b'i', b't',
+        ],
+    ]
+    .concat();
+
+    assert!(bytes.ends_with(&name_section));
+
+    // Without any identifiers there is nothing to name
+    let bytes = compile_with_options("(module (func))".to_owned(), &options).unwrap();
+
+    assert_eq!(bytes, compile("(module (func))".to_owned()).unwrap());
+}
+
+#[test]
+fn test_custom_section_placement() {
+    let sections = compile_sections(
+        "(module
+            (@custom \"late\" \"z\")
+            (@unknown (nested) annotation)
+            (func $f (@hint) (nop))
+            (@custom \"early\" (before func) \"a\"))",
+    );
+
+    assert_eq!(
+        sections,
+        [
+            vec![0x01, 0x04, 0x01, 0x60, 0x00, 0x00],
+            // Custom section placed before the function section
+            vec![0x00, 0x07, 0x05, b'e', b'a', b'r', b'l', b'y', b'a'],
+            vec![0x03, 0x02, 0x01, 0x00],
+            vec![0x0A, 0x05, 0x01, 0x03, 0x00, 0x01, 0x0B],
+            // Custom sections go last by default
+            vec![0x00, 0x06, 0x04, b'l', b'a', b't', b'e', b'z'],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_custom_sections_first_and_last() {
+    let options = CompileOptions {
+        debug_names: true,
+        ..Default::default()
+    };
+    let bytes = compile_with_options(
+        "(module
+            (@custom \"l\" (after last) \"\")
+            (@custom \"d\" (after data) \"\")
+            (@custom \"f\" (before first) \"\")
+            (type $t (func)))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(
+        bytes[8..],
+        [
+            vec![0x00, 0x02, 0x01, b'f'],
+            vec![0x01, 0x04, 0x01, 0x60, 0x00, 0x00],
+            vec![0x00, 0x02, 0x01, b'd'],
+            vec![0x00, 0x0B, 0x04, b'n', b'a', b'm', b'e', 0x04, 0x04, 0x01, 0x00, 0x01, b't'],
+            // Even the sections added by the compiler come before the last one
+            vec![0x00, 0x02, 0x01, b'l'],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_producers_section() {
+    let options = CompileOptions {
+        producers: true,
+        sdks: vec![("sdk".to_owned(), "1.0".to_owned())],
+        ..Default::default()
+    };
+    let bytes = compile_with_options("(module)".to_owned(), &options).unwrap();
+
+    let version = env!("CARGO_PKG_VERSION").as_bytes();
+    let producers = [
+        b"\x09producers\x03".to_vec(),
+        b"\x08language\x01\x03wat\x00".to_vec(),
+        b"\x0Cprocessed-by\x01\x0Bwat-to-wasm".to_vec(),
+        [&[version.len() as u8], version].concat(),
+        b"\x03sdk\x01\x03sdk\x031.0".to_vec(),
+    ]
+    .concat();
+
+    assert!(bytes.ends_with(&producers));
+}
+
+#[test]
+fn test_multi_value_block_uses_type_index() {
+    let sections = compile_sections(
+        "(module
+            (func $f (param i32 i64) (result i64 i32)
+                (local.get 0) (local.get 1)
+                (block (param i32 i64) (result i64 i32)
+                    (local.set 1) (local.set 0)
+                    (local.get 1) (local.get 0))))",
+    );
+
+    assert_eq!(
+        sections,
+        [
+            // The block signature is the same as the one of the function
+            vec![0x01, 0x08, 0x01, 0x60, 0x02, 0x7F, 0x7E, 0x02, 0x7E, 0x7F],
+            vec![0x03, 0x02, 0x01, 0x00],
+            vec![0x0A, 0x13, 0x01, 0x11, 0x00, 0x20, 0x00, 0x20, 0x01],
+            // Block with type index 0
+            vec![0x02, 0x00, 0x21, 0x01, 0x21, 0x00, 0x20, 0x01, 0x20, 0x00, 0x0B, 0x0B],
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_block_params_are_taken_from_the_stack() {
+    let result = compile(
+        "(module
+            (func $f (result i32)
+                (block (param i32) (result i32))))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [i32] but found an empty stack")));
+}
+
+#[test]
+fn test_table_instructions() {
+    let sections = compile_sections(
+        "(module
+            (table 1 funcref)
+            (table $refs 1 externref)
+            (func $f (param externref) (result i32)
+                (table.set $refs (i32.const 0) (select (result externref)
+                    (local.get 0) (table.get $refs (i32.const 0)) (i32.const 1)))
+                (ref.is_null (table.get 0 (table.size $refs)))))",
+    );
+
+    assert!(sections.ends_with(&[
+        // table.set $refs with the typed select as its value
+        0x41, 0x00, 0x20, 0x00, 0x41, 0x00, 0x25, 0x01, 0x41, 0x01, 0x1C, 0x01, 0x6F, 0x26, 0x01,
+        // table.size, table.get and ref.is_null
+        0xFC, 0x10, 0x01, 0x25, 0x00, 0xD1, 0x0B,
+    ]));
+}
+
+#[test]
+fn test_untyped_select_rejects_references() {
+    let result = compile(
+        "(module
+            (func $f (param externref) (result externref)
+                (select (local.get 0) (local.get 0) (i32.const 1))))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected a numeric type without `(result ...)`")));
+}
+
+#[test]
+fn test_reference_types_can_be_disabled() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            reference_types: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let compile_without = |source: &str| compile_with_options(source.to_owned(), &options);
+
+    assert!(
+        compile_without("(module (table 1 funcref) (func $f (call_indirect (i32.const 0))))")
+            .is_ok()
+    );
+    assert!(
+        compile_without("(module (table 1 funcref) (table 1 funcref))")
+            .is_err_and(|errors| errors[0]
+                .message
+                .starts_with("Defining more than one table requires the reference-types feature"))
+    );
+    assert!(
+        compile_without("(module (func $f (local externref)))").is_err_and(|errors| errors[0]
+            .message
+            .starts_with("`externref` requires the reference-types feature"))
+    );
+    assert!(
+        compile_without("(module (table 1 funcref) (func $f (drop (table.size 0))))").is_err_and(
+            |errors| errors[0]
+                .message
+                .starts_with("`table.size` requires the reference-types feature")
+        )
+    );
+}
+
+#[test]
+fn test_bulk_memory_instructions() {
+    let sections = compile_sections(
+        "(module
+            (memory 1)
+            (table 1 funcref)
+            (elem $e func)
+            (func $f
+                (memory.copy (i32.const 0) (i32.const 1) (i32.const 2))
+                (memory.fill (i32.const 0) (i32.const 1) (i32.const 2))
+                (table.init $e (i32.const 0) (i32.const 0) (i32.const 0))
+                (elem.drop $e)
+                (table.copy (i32.const 0) (i32.const 0) (i32.const 0))))",
+    );
+
+    let code = [
+        vec![0x41, 0x00, 0x41, 0x01, 0x41, 0x02, 0xFC, 0x0A, 0x00, 0x00],
+        vec![0x41, 0x00, 0x41, 0x01, 0x41, 0x02, 0xFC, 0x0B, 0x00],
+        vec![0x41, 0x00, 0x41, 0x00, 0x41, 0x00, 0xFC, 0x0C, 0x00, 0x00],
+        vec![0xFC, 0x0D, 0x00],
+        vec![
+            0x41, 0x00, 0x41, 0x00, 0x41, 0x00, 0xFC, 0x0E, 0x00, 0x00, 0x0B,
+        ],
+    ]
+    .concat();
+
+    assert!(sections.ends_with(&code));
+}
+
+#[test]
+fn test_table_init_checks_element_type() {
+    let result = compile(
+        "(module
+            (table 1 funcref)
+            (elem $e externref)
+            (func $f (table.init $e (i32.const 0) (i32.const 0) (i32.const 0))))"
+            .to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [funcref] but found [externref]")));
+}
+
+#[test]
+fn test_vector_instructions() {
+    let sections = compile_sections(
+        "(module
+            (memory 1)
+            (func $f (result i32)
+                i32.const 0
+                (v128.const i16x8 -1 0 1 2 3 4 5 65535)
+                v128.load32_lane offset=4 3
+                (i32x4.splat (i32.const 1))
+                i8x16.shuffle 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 31
+                (i32x4.splat (i32.const 1))
+                i32x4.sub
+                i32x4.extract_lane 2))",
+    );
+
+    let code = [
+        // v128.const i16x8
+        vec![
+            0x41, 0x00, 0xFD, 0x0C, 0xFF, 0xFF, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00,
+        ],
+        vec![0x03, 0x00, 0x04, 0x00, 0x05, 0x00, 0xFF, 0xFF],
+        // v128.load32_lane with its memarg and lane
+        vec![0xFD, 0x56, 0x02, 0x04, 0x03],
+        // i8x16.shuffle
+        vec![
+            0x41, 0x01, 0xFD, 0x11, 0xFD, 0x0D, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+        ],
+        vec![0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x1F],
+        // Sub-opcodes past 0x7F take two bytes
+        vec![
+            0x41, 0x01, 0xFD, 0x11, 0xFD, 0xB1, 0x01, 0xFD, 0x1B, 0x02, 0x0B,
+        ],
+    ]
+    .concat();
+
+    assert!(sections.ends_with(&code));
+}
+
+#[test]
+fn test_vector_lane_out_of_range() {
+    let result = compile(
+        "(module (func $f (result i64) (i64x2.extract_lane 2 (v128.const i64x2 0 0))))".to_owned(),
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Lane 2 is out of range for `i64x2.extract_lane`, which has 2 lanes")));
+}
+
+#[test]
+fn test_sign_extension_and_saturating_truncation() {
+    let sections = compile_sections(
+        "(module
+            (func $f (result i64)
+                (i64.extend32_s (i64.trunc_sat_f64_u (f64.const -1)))
+                (i64.extend_i32_s (i32.extend8_s (i32.trunc_sat_f32_s (f32.const 300))))
+                i64.add))",
+    );
+
+    let code = [
+        vec![0x44, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF0, 0xBF],
+        vec![0xFC, 0x07, 0xC4],
+        vec![0x43, 0x00, 0x00, 0x96, 0x43],
+        vec![0xFC, 0x00, 0xC0, 0xAC, 0x7C, 0x0B],
+    ]
+    .concat();
+
+    assert!(sections.ends_with(&code));
+}
+
+#[test]
+fn test_tail_calls() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            tail_call: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (table 1 funcref)
+            (func $f (param i32) (result i32)
+                (return_call $f (local.get 0))
+                (return_call_indirect (param i32) (result i32) (i32.const 1) (i32.const 0))))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    let code = [
+        vec![0x20, 0x00, 0x12, 0x00],
+        vec![0x41, 0x01, 0x41, 0x00, 0x13, 0x00, 0x00, 0x0B],
+    ]
+    .concat();
+
+    assert!(result.ends_with(&code));
+}
+
+#[test]
+fn test_tail_call_checks_results() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            tail_call: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (func $f (result i64) (i64.const 0))
+            (func $g (result i32) (return_call $f)))"
+            .to_owned(),
+        &options,
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [i32] but found [i64]")));
+    assert!(compile("(module (func $f (return_call $f)))".to_owned())
+        .is_err_and(|errors| errors[0]
+            .message
+            .starts_with("`return_call` requires the tail-call feature")));
+}
+
+#[test]
+fn test_exception_handling() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            exceptions: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (tag $e (param i32))
+            (func $f
+                (block $all
+                    (block $h (result i32)
+                        (try_table (catch $e $h) (catch_all $all)
+                            (throw $e (i32.const 1)))
+                        unreachable)
+                    drop)))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    let tag_section = vec![0x0D, 0x03, 0x01, 0x00, 0x00];
+
+    let code = [
+        vec![0x02, 0x40, 0x02, 0x7F],
+        // try_table with an empty block type and two catch clauses
+        vec![0x1F, 0x40, 0x02, 0x00, 0x00, 0x00, 0x02, 0x01],
+        vec![0x41, 0x01, 0x08, 0x00, 0x0B, 0x00, 0x0B, 0x1A, 0x0B, 0x0B],
+    ]
+    .concat();
+
+    assert!(result
+        .windows(tag_section.len())
+        .any(|window| window == tag_section));
+    assert!(result.ends_with(&code));
+}
+
+#[test]
+fn test_catch_checks_label_types() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            exceptions: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (tag $e (param i32))
+            (func $f
+                (block $h (try_table (catch_ref $e $h)))))"
+            .to_owned(),
+        &options,
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [] but found [i32 exnref]")));
+    assert!(
+        compile("(module (tag $e))".to_owned()).is_err_and(|errors| errors[0]
+            .message
+            .starts_with("`tag` requires the exception-handling feature"))
+    );
+}
+
+#[test]
+fn test_atomic_instructions() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            threads: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (memory 1 2 shared)
+            (func $f (result i64)
+                atomic.fence
+                (i64.atomic.rmw16.cmpxchg_u offset=2 (i32.const 0) (i64.const 1) (i64.const 2))))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    let memory_section = vec![0x05, 0x04, 0x01, 0x03, 0x01, 0x02];
+
+    let code = [
+        vec![0xFE, 0x03, 0x00],
+        vec![
+            0x41, 0x00, 0x42, 0x01, 0x42, 0x02, 0xFE, 0x4D, 0x01, 0x02, 0x0B,
+        ],
+    ]
+    .concat();
+
+    assert!(result
+        .windows(memory_section.len())
+        .any(|window| window == memory_section));
+    assert!(result.ends_with(&code));
+}
+
+#[test]
+fn test_atomic_alignment() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            threads: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module (memory 1 1 shared) (func $f (drop (i64.atomic.load align=4 (i32.const 0)))))"
+            .to_owned(),
+        &options,
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Alignment 4 differs from the natural alignment of `i64.atomic.load`")));
+    assert!(
+        compile("(module (memory 1 1 shared))".to_owned()).is_err_and(|errors| errors[0]
+            .message
+            .starts_with("A shared memory requires the threads feature"))
+    );
+}
+
+#[test]
+fn test_multiple_memories() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            multi_memory: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (memory $a 1)
+            (memory $b 1)
+            (func $f (result i32)
+                (i32.store $b offset=4 (i32.const 0) (i32.const 7))
+                (i32.load8_u 0 (i32.const 8))))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    let code = [
+        // The store names its memory after the flagged alignment
+        vec![0x41, 0x00, 0x41, 0x07, 0x36, 0x42, 0x01, 0x04],
+        vec![0x41, 0x08, 0x2D, 0x00, 0x00, 0x0B],
+    ]
+    .concat();
+
+    assert!(result.ends_with(&code));
+    assert!(
+        compile("(module (memory 1) (memory 1))".to_owned()).is_err_and(|errors| errors[0]
+            .message
+            .starts_with("Defining more than one memory requires the multi-memory feature"))
+    );
+}
+
+#[test]
+fn test_64_bit_memories() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            memory64: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (memory i64 1 2)
+            (func $f (result i64)
+                (i64.load offset=4294967296 (i64.const 0))))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    let memory_section = vec![0x05, 0x04, 0x01, 0x05, 0x01, 0x02];
+
+    let code = [
+        vec![0x42, 0x00, 0x29, 0x03],
+        // Offsets past 4 GiB
+        vec![0x80, 0x80, 0x80, 0x80, 0x10, 0x0B],
+    ]
+    .concat();
+
+    assert!(result
+        .windows(memory_section.len())
+        .any(|window| window == memory_section));
+    assert!(result.ends_with(&code));
+
+    let result = compile_with_options(
+        "(module (memory i64 1) (func $f (drop (i32.load (i32.const 0)))))".to_owned(),
+        &options,
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [i64] but found [i32]")));
+    assert!(
+        compile("(module (memory i64 1))".to_owned()).is_err_and(|errors| errors[0]
+            .message
+            .starts_with("A 64-bit memory requires the memory64 feature"))
+    );
+}
+
+#[test]
+fn test_inline_data_on_64_bit_memories() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            memory64: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result =
+        compile_with_options("(module (memory i64 (data \"abc\")))".to_owned(), &options).unwrap();
+
+    // The segment starts at an `i64.const 0` offset, since the memory is addressed with i64
+    let data_section = vec![
+        0x0B, 0x09, 0x01, 0x00, 0x42, 0x00, 0x0B, 0x03, 0x61, 0x62, 0x63,
+    ];
+
+    assert!(result.ends_with(&data_section));
+}
+
+#[test]
+fn test_extended_constant_expressions() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            extended_const: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (import \"env\" \"__memory_base\" (global $base i32))
+            (memory 1)
+            (data (i32.add (global.get $base) (i32.const 4)) \"\"))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    let data_section = vec![
+        0x0B, 0x09, 0x01, 0x00, 0x23, 0x00, 0x41, 0x04, 0x6A, 0x0B, 0x00,
+    ];
+
+    assert!(result.ends_with(&data_section));
+
+    let result = compile_with_options(
+        "(module (global i64 (i64.mul (i64.const 2) (i32.const 3))))".to_owned(),
+        &options,
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [i64 i64] but found [i64 i32]")));
+}
+
+#[test]
+fn test_typed_function_references() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            function_references: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (type $unary (func (param i32) (result i32)))
+            (func $f (type $unary) (local.get 0))
+            (elem declare func $f)
+            (func (param i32) (result i32)
+                (local $g (ref $unary))
+                (local.set $g (ref.func $f))
+                (call_ref $unary (local.get 0) (local.get $g))))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    let code = vec![
+        0x01, 0x01, 0x64, 0x00, 0xD2, 0x00, 0x21, 0x01, 0x20, 0x00, 0x20, 0x01, 0x14, 0x00, 0x0B,
+    ];
+
+    assert!(result.ends_with(&code));
+
+    let result = compile_with_options(
+        "(module (func (local (ref func)) (block (local.set 0 (ref.null func))) (drop (local.get 0))))"
+            .to_owned(),
+        &options,
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected [(ref func)] but found [funcref]")));
+    assert!(compile("(module (func (param (ref func))))".to_owned())
+        .is_err_and(|errors| errors[0]
+            .message
+            .starts_with("`(ref func)` requires the function-references feature")));
+}
+
+#[test]
+fn test_passive_segments_with_typed_references() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            function_references: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (type $t (func))
+            (func $f (type $t))
+            (elem $e (ref null $t) (ref.func $f)))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    // Passive segment of expressions typed `(ref null 0)`
+    let elements = vec![0x09, 0x08, 0x01, 0x05, 0x63, 0x00, 0x01, 0xD2, 0x00, 0x0B];
+
+    assert!(result
+        .windows(elements.len())
+        .any(|window| window == elements));
+}
+
+#[test]
+fn test_inline_elements_of_typed_tables() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            function_references: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module (type $t (func)) (func $f (type $t)) (table $x (ref null $t) (elem $f)))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    // Active segment of `ref.func` expressions typed like the table
+    let elements = vec![
+        0x09, 0x0C, 0x01, 0x06, 0x00, 0x41, 0x00, 0x0B, 0x63, 0x00, 0x01, 0xD2, 0x00, 0x0B,
+    ];
+
+    assert!(result
+        .windows(elements.len())
+        .any(|window| window == elements));
+}
+
+#[test]
+fn test_non_nullable_tables() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            function_references: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    // The host fills imported tables, so their elements don't need a default
+    let result = compile_with_options(
+        "(module (type $t (func)) (import \"a\" \"b\" (table 1 (ref $t))))".to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    assert!(
+        result.ends_with(&[0x02, 0x0A, 0x01, 0x01, b'a', 0x01, b'b', 0x01, 0x64, 0x00, 0x00, 0x01])
+    );
+
+    let result = compile_with_options(
+        "(module (type $t (func)) (table 1 (ref $t)))".to_owned(),
+        &options,
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type mismatch, expected a nullable reference type but found [(ref 0)]")));
+
+    // Unless an initializer gives the elements a value
+    let result = compile_with_options(
+        "(module (type $t (func)) (func $f (type $t)) (table 2 (ref $t) (ref.func $f)))".to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    let tables = vec![
+        0x04, 0x0A, 0x01, 0x40, 0x00, 0x64, 0x00, 0x00, 0x02, 0xD2, 0x00, 0x0B,
+    ];
+
+    assert!(result.windows(tables.len()).any(|window| window == tables));
+    assert!(
+        compile("(module (table 1 funcref (ref.null func)))".to_owned()).is_err_and(|errors| {
+            errors[0].message.starts_with(
+                "Initializing a table with an expression requires the function-references feature",
+            )
+        })
+    );
+}
+
+#[test]
+fn test_garbage_collected_types() {
+    let options = CompileOptions {
+        features: WasmFeatures {
+            gc: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (rec
+                (type $list (sub (struct (field $head (mut i32)) (field $tail (ref null $list)))))
+                (type $tagged (sub final $list (struct (field (mut i32)) (field (ref null $list)) (field i8)))))
+            (func (param (ref $tagged)) (result i32)
+                (struct.set $list $head (local.get 0) (i32.const 1))
+                (struct.get_s $tagged 2 (local.get 0))))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    let type_section = vec![
+        0x01, 0x1E, 0x02, 0x4E, 0x02, 0x50, 0x00, 0x5F, 0x02, 0x7F, 0x01, 0x63, 0x00, 0x00, 0x4F,
+        0x01, 0x00, 0x5F, 0x03, 0x7F, 0x01, 0x63, 0x00, 0x00, 0x78, 0x00, 0x60, 0x01, 0x64, 0x01,
+        0x01, 0x7F,
+    ];
+
+    let code = vec![
+        0x20, 0x00, 0x41, 0x01, 0xFB, 0x05, 0x00, 0x00, 0x20, 0x00, 0xFB, 0x03, 0x01, 0x02, 0x0B,
+    ];
+
+    assert!(result
+        .windows(type_section.len())
+        .any(|window| window == type_section));
+    assert!(result.ends_with(&code));
+
+    let result = compile_with_options(
+        "(module (type $a (struct (field i32))) (type (sub $a (struct (field i32)))))".to_owned(),
+        &options,
+    );
+
+    assert!(result.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("Type 1 can't be a subtype of $a, the supertype is final")));
+    assert!(
+        compile("(module (type (struct)))".to_owned()).is_err_and(|errors| errors[0]
+            .message
+            .starts_with("A `struct` type requires the gc feature"))
+    );
+}
+
+#[test]
+fn test_constant_expressions_read_earlier_globals_with_gc() {
+    let options = CompileOptions {
+        features: WasmFeatures::wasm3(),
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (type $t (struct (field i32)))
+            (global $a (ref $t) (struct.new $t (i32.const 1)))
+            (global (ref $t) (global.get $a)))"
+            .to_owned(),
+        &options,
+    )
+    .unwrap();
+
+    assert!(result.ends_with(&[0x64, 0x00, 0x00, 0x23, 0x00, 0x0B]));
+
+    // Globals are initialized in order, so later ones can't be read yet
+    let result = compile_with_options(
+        "(module (global i32 (global.get $b)) (global $b i32 (i32.const 1)))".to_owned(),
+        &options,
+    );
+
+    assert!(result.is_err_and(|errors| errors[0].message.starts_with(
+        "Global $b can't be read in a constant expression, only immutable globals imported or defined before it can"
+    )));
+
+    // Without gc, only imported globals can be read
+    let result =
+        compile("(module (global $a i32 (i32.const 1)) (global i32 (global.get $a)))".to_owned());
+
+    assert!(result.is_err_and(|errors| errors[0].message.starts_with(
+        "Global $a can't be read in a constant expression, only imported immutable globals can"
+    )));
+}
+
+#[test]
+fn test_mvp_features_reject_later_proposals() {
+    let options = CompileOptions {
+        features: WasmFeatures::mvp(),
+        ..Default::default()
+    };
+
+    let compile_mvp = |source: &str| compile_with_options(source.to_owned(), &options);
+
+    assert!(compile_mvp(
+        "(module (memory 1) (func $f (param i32) (result i32) (i32.load (local.get 0))))"
+    )
+    .is_ok());
+
+    let rejected = [
+        (
+            "(module (func $f (param i32) (result i32) (i32.extend8_s (local.get 0))))",
+            "`i32.extend8_s` requires the sign-extension feature",
+        ),
+        (
+            "(module (func $f (param f32) (result i32) (i32.trunc_sat_f32_s (local.get 0))))",
+            "`i32.trunc_sat_f32_s` requires the saturating-float-to-int feature",
+        ),
+        (
+            "(module (func $f (result i32 i32) (i32.const 0) (i32.const 1)))",
+            "A function type with more than one result requires the multi-value feature",
+        ),
+        (
+            "(module (func $f (i32.const 0) (block (param i32) (drop))))",
+            "A block type other than a single result requires the multi-value feature",
+        ),
+        (
+            "(module (memory 1) (data \"passive\"))",
+            "A passive data segment requires the bulk-memory feature",
+        ),
+        (
+            "(module (global $g (mut i32) (i32.const 0)) (export \"g\" (global $g)))",
+            "Exporting a mutable global requires the mutable-globals feature",
+        ),
+        (
+            "(module (func $f (param v128)))",
+            "`v128` requires the simd feature",
+        ),
+        (
+            "(module (memory $m 1) (func $f (drop (memory.size $m))))",
+            "An explicit index on `memory.size` requires the multi-memory feature",
+        ),
+        (
+            "(module
+                (import \"env\" \"base\" (global $b i32))
+                (memory 1)
+                (data (offset (i32.add (global.get $b) (i32.const 16))) \"x\"))",
+            "`i32.add` in a constant expression requires the extended-const feature",
+        ),
+        (
+            "(module (func $f (return_call $f)))",
+            "`return_call` requires the tail-call feature",
+        ),
+    ];
+
+    for (source, message) in rejected {
+        let result = compile_mvp(source);
+
+        assert!(
+            result
+                .as_ref()
+                .is_err_and(|errors| errors[0].message.starts_with(message)),
+            "{:?}",
+            result
+        );
+    }
+
+    // Annotations only exist in the text format, so they are left to be turned off by hand
+    let annotated = "(module (@custom \"name\" \"contents\"))";
+    let without_annotations = CompileOptions {
+        features: WasmFeatures {
+            annotations: false,
+            ..WasmFeatures::mvp()
+        },
+        ..Default::default()
+    };
+
+    assert!(compile_mvp(annotated).is_ok());
+    assert!(
+        compile_with_options(annotated.to_owned(), &without_annotations).is_err_and(|errors| {
+            errors[0]
+                .message
+                .starts_with("The `@custom` annotation requires the annotations feature")
+        })
+    );
+}
+
+#[test]
+fn test_wasm3_features_accept_proposals() {
+    let options = CompileOptions {
+        features: WasmFeatures::wasm3(),
+        ..Default::default()
+    };
+
+    let result = compile_with_options(
+        "(module
+            (type $pair (struct (field i32) (field i32)))
+            (memory 1)
+            (memory i64 1)
+            (func $f (result i32)
+                (drop (struct.new $pair (i32.const 1) (i32.const 2)))
+                (return_call $f)))"
+            .to_owned(),
+        &options,
+    );
+
+    assert!(result.is_ok());
+
+    let threads = compile_with_options("(module (memory 1 1 shared))".to_owned(), &options);
+
+    assert!(threads.is_err_and(|errors| errors[0]
+        .message
+        .starts_with("A shared memory requires the threads feature")));
+}
+
+#[test]
+fn test_diagnostics_describe_errors() {
+    let errors = compile("(module\n  (func $f)\n  (func $f))".to_owned()).unwrap_err();
+    let diagnostic = &errors[0];
+
+    assert!(matches!(diagnostic.error, Error::DuplicateIdentifier(_)));
+    assert_eq!(diagnostic.kind(), DiagnosticKind::Resolve);
+    assert_eq!(diagnostic.code(), "E0303");
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.primary_span(), Span { line: 3, column: 3 });
+    assert_eq!(
+        diagnostic.secondary_spans(),
+        vec![Label {
+            span: Span { line: 2, column: 3 },
+            message: "$f is first defined here".to_owned()
+        }]
+    );
+    assert!(diagnostic
+        .to_string()
+        .starts_with("Duplicate identifier $f in line:\n3 |   (func $f))"));
+
+    let errors = compile("(module (func (result i32) (f32.const 0)))".to_owned()).unwrap_err();
+    let error: &dyn std::error::Error = &errors[0];
+
+    assert_eq!(errors[0].kind(), DiagnosticKind::Validate);
+    assert!(error.to_string().starts_with("Type mismatch"));
+
+    let errors = compile("(module (func \"unterminated))".to_owned()).unwrap_err();
+
+    assert_eq!(errors[0].kind(), DiagnosticKind::Lex);
+    assert_eq!(errors[0].code(), "E0104");
+
+    // Features are checked by every stage, which the diagnostic reports as its kind
+    let options = CompileOptions {
+        features: WasmFeatures {
+            annotations: false,
+            ..WasmFeatures::mvp()
+        },
+        ..Default::default()
+    };
+    let stages = [
+        ("(module (@custom \"name\" \"\"))", DiagnosticKind::Lex),
+        (
+            "(module (memory 1) (func (drop (memory.size 0))))",
+            DiagnosticKind::Parse,
+        ),
+        (
+            "(module (func $f (return_call $f)))",
+            DiagnosticKind::Validate,
+        ),
+    ];
+
+    for (source, kind) in stages {
+        let errors = compile_with_options(source.to_owned(), &options).unwrap_err();
+
+        assert!(matches!(
+            &errors[0].error,
+            Error::DisabledFeature(DisabledFeatureError { kind: found, .. }) if *found == kind
+        ));
+        assert_eq!(errors[0].kind(), kind);
+        assert_eq!(errors[0].code(), "E0413");
+    }
+}
+
+#[test]
+fn test_fields_without_module() {
+    assert_eq!(
+        compile("(type $t (func)) (func (type $t))".to_owned()).unwrap(),
+        compile("(module (type $t (func)) (func (type $t)))".to_owned()).unwrap()
+    );
+
+    let errors = compile("(module\n  (module))".to_owned()).unwrap_err();
+
+    assert!(matches!(errors[0].error, Error::Misplaced(_)));
+    assert_eq!(errors[0].primary_span(), Span { line: 2, column: 3 });
+}
@@ -0,0 +1,369 @@
+use std::fmt::Display;
+
+use crate::{
+    bytecode::{container::BytecodeContainer, ByteValue},
+    parser::{
+        expression::{
+            CompositeDefinition, FieldDefinition, FunctionSignature, GlobalType, Limits,
+            StorageType, TableType,
+        },
+        instructions::{BuiltinType, HeapType},
+    },
+    traits::page_position::PageCursor,
+};
+
+use super::errors::InvalidLimitsError;
+
+/// Largest number of pages a 32-bit memory can hold
+pub const MAX_MEMORY_PAGES: u64 = 65536;
+
+/// Largest number of pages a 64-bit memory can hold
+pub const MAX_MEMORY64_PAGES: u64 = 1 << 48;
+
+pub const MAX_TABLE_ELEMENTS: u64 = u32::MAX as u64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FunctionType {
+    pub params: Vec<BuiltinType>,
+    pub results: Vec<BuiltinType>,
+}
+
+impl From<&FunctionSignature> for FunctionType {
+    fn from(signature: &FunctionSignature) -> Self {
+        Self {
+            params: signature
+                .params
+                .iter()
+                .map(|param| param.parameter_type)
+                .collect(),
+            results: signature.results.clone(),
+        }
+    }
+}
+
+impl Display for FunctionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} -> {}",
+            format_types(&self.params),
+            format_types(&self.results)
+        )
+    }
+}
+
+impl FunctionType {
+    pub fn encode(&self, bytecode: &mut BytecodeContainer) {
+        bytecode.push_byte(ByteValue::FunctionType as u8);
+        encode_types(bytecode, &self.params);
+        encode_types(bytecode, &self.results);
+    }
+}
+
+pub fn encode_types(bytecode: &mut BytecodeContainer, types: &[BuiltinType]) {
+    bytecode.push_u32(types.len() as u32);
+
+    for value_type in types {
+        encode_value_type(bytecode, *value_type);
+    }
+}
+
+/// Nullable abstract references have a single byte shorthand, any other reference
+/// is written out with its heap type
+pub fn encode_value_type(bytecode: &mut BytecodeContainer, value_type: BuiltinType) {
+    let byte = match value_type {
+        BuiltinType::I32 => ByteValue::I32,
+        BuiltinType::F32 => ByteValue::F32,
+        BuiltinType::I64 => ByteValue::I64,
+        BuiltinType::F64 => ByteValue::F64,
+        BuiltinType::V128 => ByteValue::V128,
+        BuiltinType::FuncRef => ByteValue::FuncRef,
+        BuiltinType::ExternRef => ByteValue::ExternRef,
+        BuiltinType::ExnRef => ByteValue::ExnRef,
+        BuiltinType::Ref(reference) => {
+            match (reference.nullable, reference.heap_type.byte()) {
+                (true, Some(byte)) => bytecode.push_byte(byte),
+                (true, None) => {
+                    bytecode.push_byte(ByteValue::NullableRef as u8);
+                    encode_heap_type(bytecode, reference.heap_type);
+                }
+                (false, _) => {
+                    bytecode.push_byte(ByteValue::NonNullableRef as u8);
+                    encode_heap_type(bytecode, reference.heap_type);
+                }
+            }
+
+            return;
+        }
+    };
+
+    bytecode.push_byte(byte as u8);
+}
+
+/// Type indices are written as signed numbers, so that they can't be confused with abstract types
+pub fn encode_heap_type(bytecode: &mut BytecodeContainer, heap_type: HeapType) {
+    match (heap_type, heap_type.byte()) {
+        (_, Some(byte)) => bytecode.push_byte(byte),
+        (HeapType::Concrete(index), None) => bytecode.push_i64(index as i64),
+        (_, None) => unreachable!("only concrete heap types lack a byte"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldType {
+    pub storage_type: StorageType,
+    pub mutable: bool,
+}
+
+impl From<&FieldDefinition> for FieldType {
+    fn from(field: &FieldDefinition) -> Self {
+        Self {
+            storage_type: field.storage_type,
+            mutable: field.mutable,
+        }
+    }
+}
+
+impl FieldType {
+    fn encode(&self, bytecode: &mut BytecodeContainer) {
+        match self.storage_type {
+            StorageType::Value(value_type) => encode_value_type(bytecode, value_type),
+            StorageType::I8 => bytecode.push_byte(ByteValue::I8 as u8),
+            StorageType::I16 => bytecode.push_byte(ByteValue::I16 as u8),
+        }
+
+        if self.mutable {
+            bytecode.push_byte(ByteValue::MUTABLE as u8);
+        } else {
+            bytecode.push_byte(ByteValue::IMMUTABLE as u8);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CompositeType {
+    Func(FunctionType),
+    Struct(Vec<FieldType>),
+    Array(FieldType),
+}
+
+impl From<&CompositeDefinition> for CompositeType {
+    fn from(definition: &CompositeDefinition) -> Self {
+        match definition {
+            CompositeDefinition::Func(signature) => CompositeType::Func(signature.into()),
+            CompositeDefinition::Struct(fields) => {
+                CompositeType::Struct(fields.iter().map(FieldType::from).collect())
+            }
+            CompositeDefinition::Array(element) => CompositeType::Array(element.into()),
+        }
+    }
+}
+
+impl CompositeType {
+    /// Abstract heap type that references to this type are a subtype of
+    pub fn abstract_type(&self) -> HeapType {
+        match self {
+            CompositeType::Func(_) => HeapType::Func,
+            CompositeType::Struct(_) => HeapType::Struct,
+            CompositeType::Array(_) => HeapType::Array,
+        }
+    }
+}
+
+/// Entry of the type section
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DefinedType {
+    pub composite_type: CompositeType,
+    pub is_final: bool,
+    pub supertype: Option<u32>,
+}
+
+impl DefinedType {
+    /// Function type as written before subtyping, which is final and has no supertype
+    pub fn function(function_type: FunctionType) -> Self {
+        Self {
+            composite_type: CompositeType::Func(function_type),
+            is_final: true,
+            supertype: None,
+        }
+    }
+
+    pub fn as_function(&self) -> Option<&FunctionType> {
+        match &self.composite_type {
+            CompositeType::Func(function_type) => Some(function_type),
+            _ => None,
+        }
+    }
+
+    pub fn as_struct(&self) -> Option<&[FieldType]> {
+        match &self.composite_type {
+            CompositeType::Struct(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<FieldType> {
+        match &self.composite_type {
+            CompositeType::Array(element) => Some(*element),
+            _ => None,
+        }
+    }
+
+    /// Every value type the definition mentions, with packed storage left out
+    pub fn value_types(&self) -> Vec<BuiltinType> {
+        match &self.composite_type {
+            CompositeType::Func(function_type) => function_type
+                .params
+                .iter()
+                .chain(&function_type.results)
+                .copied()
+                .collect(),
+            CompositeType::Struct(fields) => fields
+                .iter()
+                .filter(|field| !field.storage_type.is_packed())
+                .map(|field| field.storage_type.unpacked())
+                .collect(),
+            CompositeType::Array(element) if element.storage_type.is_packed() => Vec::new(),
+            CompositeType::Array(element) => vec![element.storage_type.unpacked()],
+        }
+    }
+
+    /// Replaces every type index in the definition, which makes definitions
+    /// of different recursion groups comparable
+    pub fn map_indices(&self, map: impl Fn(u32) -> u32) -> Self {
+        let map_value = |value_type: BuiltinType| match value_type {
+            BuiltinType::Ref(mut reference) => {
+                if let HeapType::Concrete(index) = reference.heap_type {
+                    reference.heap_type = HeapType::Concrete(map(index));
+                }
+
+                BuiltinType::Ref(reference)
+            }
+            value_type => value_type,
+        };
+        let map_field = |field: &FieldType| FieldType {
+            storage_type: match field.storage_type {
+                StorageType::Value(value_type) => StorageType::Value(map_value(value_type)),
+                packed => packed,
+            },
+            mutable: field.mutable,
+        };
+
+        let composite_type = match &self.composite_type {
+            CompositeType::Func(function_type) => CompositeType::Func(FunctionType {
+                params: function_type.params.iter().map(|t| map_value(*t)).collect(),
+                results: function_type
+                    .results
+                    .iter()
+                    .map(|t| map_value(*t))
+                    .collect(),
+            }),
+            CompositeType::Struct(fields) => {
+                CompositeType::Struct(fields.iter().map(map_field).collect())
+            }
+            CompositeType::Array(element) => CompositeType::Array(map_field(element)),
+        };
+
+        Self {
+            composite_type,
+            is_final: self.is_final,
+            supertype: self.supertype.map(&map),
+        }
+    }
+
+    /// Final types without a supertype use the shorter encoding from before subtyping
+    pub fn encode(&self, bytecode: &mut BytecodeContainer) {
+        if let Some(supertype) = self.supertype {
+            bytecode.push_byte(if self.is_final {
+                ByteValue::SubFinalType as u8
+            } else {
+                ByteValue::SubType as u8
+            });
+            bytecode.push_u32(1);
+            bytecode.push_u32(supertype);
+        } else if !self.is_final {
+            bytecode.push_byte(ByteValue::SubType as u8);
+            bytecode.push_u32(0);
+        }
+
+        match &self.composite_type {
+            CompositeType::Func(function_type) => function_type.encode(bytecode),
+            CompositeType::Struct(fields) => {
+                bytecode.push_byte(ByteValue::StructType as u8);
+                bytecode.push_u32(fields.len() as u32);
+
+                for field in fields {
+                    field.encode(bytecode);
+                }
+            }
+            CompositeType::Array(element) => {
+                bytecode.push_byte(ByteValue::ArrayType as u8);
+                element.encode(bytecode);
+            }
+        }
+    }
+}
+
+/// Renders a result type the way the spec writes them, e.g. `[i32 f64]`
+pub fn format_types<'a>(types: impl IntoIterator<Item = &'a BuiltinType>) -> String {
+    let types = types
+        .into_iter()
+        .map(|value_type| value_type.to_string())
+        .collect::<Vec<_>>();
+
+    format!("[{}]", types.join(" "))
+}
+
+/// Checks that the limits are ordered and don't exceed `bound`, which is measured in `unit`
+pub fn validate_limits(
+    limits: &Limits,
+    bound: u64,
+    unit: &str,
+    cursor: PageCursor,
+) -> Result<(), InvalidLimitsError> {
+    let reason = if limits.min > bound {
+        format!("minimum exceeds {} {}", bound, unit)
+    } else if limits.max.is_some_and(|max| max > bound) {
+        format!("maximum exceeds {} {}", bound, unit)
+    } else if limits.max.is_some_and(|max| max < limits.min) {
+        "maximum is smaller than the minimum".to_owned()
+    } else {
+        return Ok(());
+    };
+
+    Err(InvalidLimitsError { reason, cursor })
+}
+
+/// The lowest bit of the flags marks a maximum, the next ones a shared and a 64-bit memory
+pub fn encode_limits(bytecode: &mut BytecodeContainer, limits: &Limits) {
+    let is_64 = limits.index_type == BuiltinType::I64;
+    let flags = limits.max.is_some() as u8 | (limits.shared as u8) << 1 | (is_64 as u8) << 2;
+
+    bytecode.push_byte(flags);
+    bytecode.push_u64(limits.min);
+
+    if let Some(max) = limits.max {
+        bytecode.push_u64(max);
+    }
+}
+
+pub fn encode_global_type(bytecode: &mut BytecodeContainer, global_type: &GlobalType) {
+    encode_value_type(bytecode, global_type.value_type);
+
+    if global_type.mutable {
+        bytecode.push_byte(ByteValue::MUTABLE as u8);
+    } else {
+        bytecode.push_byte(ByteValue::IMMUTABLE as u8);
+    }
+}
+
+/// Tags only come in the exception attribute, followed by the type of their payload
+pub fn encode_tag_type(bytecode: &mut BytecodeContainer, type_index: u32) {
+    bytecode.push_byte(0x00);
+    bytecode.push_u32(type_index);
+}
+
+pub fn encode_table_type(bytecode: &mut BytecodeContainer, table_type: &TableType) {
+    encode_value_type(bytecode, table_type.element_type);
+    encode_limits(bytecode, &table_type.limits);
+}
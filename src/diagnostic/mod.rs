@@ -0,0 +1,233 @@
+use std::fmt;
+
+use crate::{source::Source, traits::error_display::ErrorDisplay};
+
+// The payloads of the variants, along with the types of their fields,
+// so they can be named outside of the crate
+pub use crate::{
+    compiler::errors::{
+        DuplicateExportError, DuplicateIdentifierError, ImmutableFieldError, ImmutableGlobalError,
+        ImportAfterDefinitionError, InvalidAlignmentError, InvalidLaneError, InvalidLimitsError,
+        InvalidSubtypeError, MismatchedLabelError, MisplacedError, MisplacedInstructionError,
+        MultipleDefinitionsError, NonConstantGlobalError, NonConstantInstructionError,
+        SignatureMismatchError, TypeMismatchError, UndeclaredReferenceError,
+        UninitializedLocalError, UnknownIndexError,
+    },
+    features::errors::DisabledFeatureError,
+    parser::errors::{
+        ConstantOutOfRangeError, ExpectedIdentifierError, ExpectedIndexError, ExpectedIntegerError,
+        ExpectedMethodError, ExpectedNumberError, ExpectedStringError, ExpectedTokenError,
+        ExpectedTypeError, MalformedNameError, UnexpectedEndError, UnexpectedTokenError,
+        UnknownTypeError,
+    },
+    parser::instructions::Mnemonic,
+    shared::{Identifier, Index},
+    tokenizer::{
+        errors::{
+            InvalidEscapeError, InvalidNumberError, UnclosedAnnotationError,
+            UnrecognizedKeywordError, UnrecognizedTokenError, UnterminatedStringError,
+        },
+        TokenType,
+    },
+    traits::page_position::PageCursor,
+};
+
+/// Stage of the compiler that rejected the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// Splitting the source into tokens
+    Lex,
+    /// Building the syntax tree from the tokens
+    Parse,
+    /// Looking up the identifiers and indices the module refers to
+    Resolve,
+    /// Checking the module against the rules of the spec
+    Validate,
+    /// Writing out the binary
+    Encode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Position in the source, with lines counted from one and columns from zero
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl From<PageCursor> for Span {
+    fn from(cursor: PageCursor) -> Self {
+        Self {
+            line: cursor.line,
+            column: cursor.column,
+        }
+    }
+}
+
+/// A position related to a diagnostic, like an earlier definition it conflicts with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Generates a variant for every error struct, along with the stage it belongs to and
+/// its code. Codes are part of the public interface, so they must never be reused.
+/// Errors raised by several stages leave out the stage and carry it themselves
+macro_rules! define_errors {
+    (@kind $error:ident $kind:ident) => { DiagnosticKind::$kind };
+    (@kind $error:ident) => { $error.kind };
+
+    [ $( $variant:ident($class:ident) = $( $kind:ident )? $code:literal ),+ $(,)? ] => {
+        /// Everything that can go wrong while compiling a module
+        #[derive(Debug)]
+        pub enum Error {
+            $( $variant($class), )+
+        }
+
+        impl Error {
+            #[allow(unused_variables)]
+            pub fn kind(&self) -> DiagnosticKind {
+                match self {
+                    $( Error::$variant(error) => define_errors!(@kind error $( $kind )?), )+
+                }
+            }
+
+            pub fn code(&self) -> &'static str {
+                match self {
+                    $( Error::$variant(_) => $code, )+
+                }
+            }
+
+            /// Where in the source the error occurred
+            pub fn span(&self) -> Span {
+                match self {
+                    $( Error::$variant(error) => error.cursor.into(), )+
+                }
+            }
+        }
+
+        impl ErrorDisplay for Error {
+            fn display(&self, source: Source) -> String {
+                match self {
+                    $( Error::$variant(error) => error.display(source), )+
+                }
+            }
+        }
+
+        $(
+            impl From<$class> for Error {
+                fn from(error: $class) -> Self {
+                    Error::$variant(error)
+                }
+            }
+        )+
+    };
+}
+
+define_errors![
+    UnrecognizedToken(UnrecognizedTokenError) = Lex "E0101",
+    UnrecognizedKeyword(UnrecognizedKeywordError) = Lex "E0102",
+    InvalidNumber(InvalidNumberError) = Lex "E0103",
+    UnterminatedString(UnterminatedStringError) = Lex "E0104",
+    InvalidEscape(InvalidEscapeError) = Lex "E0105",
+    UnclosedAnnotation(UnclosedAnnotationError) = Lex "E0106",
+    ExpectedToken(ExpectedTokenError) = Parse "E0201",
+    ExpectedIdentifier(ExpectedIdentifierError) = Parse "E0202",
+    ExpectedString(ExpectedStringError) = Parse "E0203",
+    ExpectedType(ExpectedTypeError) = Parse "E0204",
+    ExpectedMethod(ExpectedMethodError) = Parse "E0205",
+    ExpectedIndex(ExpectedIndexError) = Parse "E0206",
+    ExpectedInteger(ExpectedIntegerError) = Parse "E0207",
+    ExpectedNumber(ExpectedNumberError) = Parse "E0208",
+    ConstantOutOfRange(ConstantOutOfRangeError) = Parse "E0209",
+    UnexpectedToken(UnexpectedTokenError) = Parse "E0210",
+    UnexpectedEnd(UnexpectedEndError) = Parse "E0211",
+    MalformedName(MalformedNameError) = Parse "E0212",
+    Misplaced(MisplacedError) = Parse "E0213",
+    UnknownType(UnknownTypeError) = Resolve "E0301",
+    UnknownIndex(UnknownIndexError) = Resolve "E0302",
+    DuplicateIdentifier(DuplicateIdentifierError) = Resolve "E0303",
+    DuplicateExport(DuplicateExportError) = Resolve "E0304",
+    MismatchedLabel(MismatchedLabelError) = Resolve "E0305",
+    TypeMismatch(TypeMismatchError) = Validate "E0401",
+    MisplacedInstruction(MisplacedInstructionError) = Validate "E0402",
+    InvalidLimits(InvalidLimitsError) = Validate "E0403",
+    MultipleDefinitions(MultipleDefinitionsError) = Validate "E0404",
+    InvalidAlignment(InvalidAlignmentError) = Validate "E0405",
+    InvalidLane(InvalidLaneError) = Validate "E0406",
+    NonConstantInstruction(NonConstantInstructionError) = Validate "E0407",
+    NonConstantGlobal(NonConstantGlobalError) = Validate "E0408",
+    ImmutableGlobal(ImmutableGlobalError) = Validate "E0409",
+    UndeclaredReference(UndeclaredReferenceError) = Validate "E0410",
+    ImportAfterDefinition(ImportAfterDefinitionError) = Validate "E0411",
+    SignatureMismatch(SignatureMismatchError) = Validate "E0412",
+    DisabledFeature(DisabledFeatureError) = "E0413",
+    InvalidSubtype(InvalidSubtypeError) = Validate "E0414",
+    ImmutableField(ImmutableFieldError) = Validate "E0415",
+    UninitializedLocal(UninitializedLocalError) = Validate "E0416",
+];
+
+impl Error {
+    /// Other places the error refers to, like the definition a duplicate conflicts with
+    pub fn secondary_spans(&self) -> Vec<Label> {
+        match self {
+            Error::DuplicateIdentifier(error) => vec![Label {
+                span: error.previous.into(),
+                message: format!("{} is first defined here", error.id),
+            }],
+            Error::DuplicateExport(error) => vec![Label {
+                span: error.previous.into(),
+                message: format!("\"{}\" is first exported here", error.name),
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// An error along with its message, rendered with the source lines it points to
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub error: Error,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(error: Error, source: Source) -> Self {
+        Self {
+            message: error.display(source),
+            severity: Severity::Error,
+            error,
+        }
+    }
+
+    pub fn kind(&self) -> DiagnosticKind {
+        self.error.kind()
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.error.code()
+    }
+
+    pub fn primary_span(&self) -> Span {
+        self.error.span()
+    }
+
+    pub fn secondary_spans(&self) -> Vec<Label> {
+        self.error.secondary_spans()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
@@ -0,0 +1,28 @@
+use crate::{
+    diagnostic::DiagnosticKind,
+    source::Source,
+    traits::{error_display::ErrorDisplay, page_position::PageCursor},
+};
+
+#[derive(Debug)]
+pub struct DisabledFeatureError {
+    /// What needs the feature, already formatted for the message
+    pub construct: String,
+    pub feature: &'static str,
+    /// Stage that found the construct, since every stage checks the features
+    pub kind: DiagnosticKind,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for DisabledFeatureError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("feature error should point to valid position");
+
+        format!(
+            "{} requires the {} feature in line:\n{}",
+            self.construct, self.feature, position
+        )
+    }
+}
@@ -0,0 +1,167 @@
+pub mod errors;
+
+/// Proposals accepted on top of the MVP, which every stage from the tokenizer
+/// to the validator checks before accepting the constructs they introduced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmFeatures {
+    /// Importing and exporting mutable globals
+    pub mutable_globals: bool,
+    /// `i32.extend8_s` and the other sign extension instructions
+    pub sign_extension: bool,
+    /// The `trunc_sat` conversions, which saturate instead of trapping
+    pub saturating_float_to_int: bool,
+    /// Several results for functions and blocks, and params for blocks
+    pub multi_value: bool,
+    /// Passive segments and the instructions copying and filling memories and tables
+    pub bulk_memory: bool,
+    /// Reference typed values, the instructions working on them and multiple tables
+    pub reference_types: bool,
+    /// `v128` values and the vector instructions
+    pub simd: bool,
+    /// `return_call` and the other tail calls
+    pub tail_call: bool,
+    /// Tags, `exnref` and the instructions throwing and catching exceptions
+    pub exceptions: bool,
+    /// Shared memories and atomic instructions
+    pub threads: bool,
+    /// Several memories and memory indices on the instructions accessing them
+    pub multi_memory: bool,
+    /// Memories addressed with `i64`
+    pub memory64: bool,
+    /// Integer addition, subtraction and multiplication in constant expressions
+    pub extended_const: bool,
+    /// Non-nullable references, references to concrete types and `call_ref`
+    pub function_references: bool,
+    /// Struct, array and recursive types, subtyping and the instructions working on them,
+    /// which implies function references
+    pub gc: bool,
+    /// Annotations like `(@custom ...)`, which only exist in the text format and
+    /// are therefore enabled by every preset
+    pub annotations: bool,
+}
+
+/// Names of the features as they appear in diagnostics and command line flags
+pub const FEATURE_NAMES: [&str; 16] = [
+    "mutable-globals",
+    "sign-extension",
+    "saturating-float-to-int",
+    "multi-value",
+    "bulk-memory",
+    "reference-types",
+    "simd",
+    "tail-call",
+    "exception-handling",
+    "threads",
+    "multi-memory",
+    "memory64",
+    "extended-const",
+    "function-references",
+    "gc",
+    "annotations",
+];
+
+impl WasmFeatures {
+    /// The original release of WebAssembly, without any proposals besides annotations
+    pub fn mvp() -> Self {
+        Self {
+            mutable_globals: false,
+            sign_extension: false,
+            saturating_float_to_int: false,
+            multi_value: false,
+            bulk_memory: false,
+            reference_types: false,
+            simd: false,
+            tail_call: false,
+            exceptions: false,
+            threads: false,
+            multi_memory: false,
+            memory64: false,
+            extended_const: false,
+            function_references: false,
+            gc: false,
+            annotations: true,
+        }
+    }
+
+    /// Proposals merged into the 2.0 release of the spec
+    pub fn wasm2() -> Self {
+        Self {
+            mutable_globals: true,
+            sign_extension: true,
+            saturating_float_to_int: true,
+            multi_value: true,
+            bulk_memory: true,
+            reference_types: true,
+            simd: true,
+            ..Self::mvp()
+        }
+    }
+
+    /// Proposals merged into the 3.0 release of the spec, which leaves out threads
+    pub fn wasm3() -> Self {
+        Self {
+            tail_call: true,
+            exceptions: true,
+            multi_memory: true,
+            memory64: true,
+            extended_const: true,
+            function_references: true,
+            gc: true,
+            ..Self::wasm2()
+        }
+    }
+
+    /// Looks up a feature by its name in [FEATURE_NAMES], with function
+    /// references also enabled by gc and unknown features never enabled
+    pub fn is_enabled(&self, name: &str) -> bool {
+        let mut features = *self;
+
+        match name {
+            "function-references" => self.function_references || self.gc,
+            _ => features.flag(name).is_some_and(|flag| *flag),
+        }
+    }
+
+    /// Turns a feature on or off by its name, returning whether it exists
+    pub fn set(&mut self, name: &str, enabled: bool) -> bool {
+        match self.flag(name) {
+            Some(flag) => {
+                *flag = enabled;
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn flag(&mut self, name: &str) -> Option<&mut bool> {
+        let flag = match name {
+            "mutable-globals" => &mut self.mutable_globals,
+            "sign-extension" => &mut self.sign_extension,
+            "saturating-float-to-int" => &mut self.saturating_float_to_int,
+            "multi-value" => &mut self.multi_value,
+            "bulk-memory" => &mut self.bulk_memory,
+            "reference-types" => &mut self.reference_types,
+            "simd" => &mut self.simd,
+            "tail-call" => &mut self.tail_call,
+            "exception-handling" => &mut self.exceptions,
+            "threads" => &mut self.threads,
+            "multi-memory" => &mut self.multi_memory,
+            "memory64" => &mut self.memory64,
+            "extended-const" => &mut self.extended_const,
+            "function-references" => &mut self.function_references,
+            "gc" => &mut self.gc,
+            "annotations" => &mut self.annotations,
+            _ => return None,
+        };
+
+        Some(flag)
+    }
+}
+
+/// The 2.0 release
+impl Default for WasmFeatures {
+    fn default() -> Self {
+        Self::wasm2()
+    }
+}
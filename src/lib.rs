@@ -1,15 +1,21 @@
+pub use compiler::CompileOptions;
+pub use diagnostic::{Diagnostic, Error};
+pub use features::WasmFeatures;
 use source::Source;
 use tokenizer::generate_tokens;
 
 pub mod bytecode;
 pub mod compiler;
+pub mod diagnostic;
+pub mod features;
 mod parser;
 mod shared;
 mod source;
 mod tokenizer;
 mod traits;
 
-/// Take in the WAT source code and returns (TODO)
+/// Take in the WAT source code and return the bytes of the compiled module,
+/// or the diagnostics explaining why compilation failed
 ///
 /// # Arguments
 ///
@@ -20,38 +26,52 @@ mod traits;
 /// ```
 /// use wat_to_wasm::compile;
 ///
-/// compile("(module)".to_owned());
+/// let bytes = compile("(module)".to_owned()).unwrap();
+///
+/// assert_eq!(bytes, [0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00]);
 /// ```
-pub fn compile(input: String) -> Result<(), ()> {
+pub fn compile(input: String) -> Result<Vec<u8>, Vec<Diagnostic>> {
+    compile_with_options(input, &CompileOptions::default())
+}
+
+/// Like [compile], with [CompileOptions] controlling the optional parts of the output
+///
+/// # Examples
+///
+/// ```
+/// use wat_to_wasm::{compile_with_options, CompileOptions};
+///
+/// let options = CompileOptions {
+///     debug_names: true,
+///     ..Default::default()
+/// };
+/// let bytes = compile_with_options("(module $demo)".to_owned(), &options).unwrap();
+///
+/// assert!(bytes.ends_with(b"\x04name\x00\x05\x04demo"));
+/// ```
+pub fn compile_with_options(
+    input: String,
+    options: &CompileOptions,
+) -> Result<Vec<u8>, Vec<Diagnostic>> {
     let source = Source::from(input);
 
-    let tokens = match generate_tokens(source.clone()) {
+    let tokens = match generate_tokens(source.clone(), &options.features) {
         Ok(tokens) => tokens,
         Err(errors) => {
-            for err in errors {
-                eprintln!("{}", err.display(source.clone()));
-            }
-            return Err(());
-        }
-    };
-
-    let syntax_tree = match parser::parse_tokens(tokens) {
-        Ok(tree) => tree,
-        Err(err) => {
-            eprintln!("{}", err.display(source));
-
-            return Err(());
+            return Err(errors
+                .into_iter()
+                .map(|err| Diagnostic::new(err, source.clone()))
+                .collect())
         }
     };
 
-    match compiler::compile(syntax_tree) {
+    let syntax_tree = match parser::parse_tokens(tokens, &options.features) {
         Ok(tree) => tree,
-        Err(err) => {
-            eprintln!("{}", err.display(source));
-
-            return Err(());
-        }
+        Err(err) => return Err(vec![Diagnostic::new(err, source)]),
     };
 
-    Ok(())
+    match compiler::compile(syntax_tree, options) {
+        Ok(bytecode) => Ok(bytecode.data),
+        Err(err) => Err(vec![Diagnostic::new(err, source)]),
+    }
 }
@@ -1,9 +1,22 @@
-use std::{env, fs, time::Instant};
+use std::{env, fs, path::Path, process::ExitCode, time::Instant};
 
-use wat_to_wasm::compile;
+use wat_to_wasm::{compile_with_options, CompileOptions, WasmFeatures};
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+fn main() -> ExitCode {
+    let (mut flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
+
+    // Presets replace every feature, so they go first to keep the individual toggles
+    flags.sort_by_key(|flag| !flag.starts_with("--preset="));
+
+    let mut options = CompileOptions::default();
+
+    for flag in &flags {
+        if !apply_flag(&mut options, flag) {
+            eprintln!("Unknown option {}", flag);
+            return ExitCode::FAILURE;
+        }
+    }
 
     let file_path = match &args.as_slice() {
         [_, name, ..] => name,
@@ -11,30 +24,87 @@ fn main() {
         [] => unreachable!(),
     };
 
+    let output_path = match &args.as_slice() {
+        [_, _, output, ..] => output.to_owned(),
+        _ => Path::new(file_path)
+            .with_extension("wasm")
+            .to_string_lossy()
+            .into_owned(),
+    };
+
     let source = if let Ok(file_contents) = read_file(file_path) {
         println!("Compiling file {}", file_path);
         file_contents
     } else {
         eprintln!("Failed to open file");
-        return;
+        return ExitCode::FAILURE;
     };
 
     let start_time = Instant::now();
 
-    match compile(source) {
-        Ok(_) => {
+    match compile_with_options(source, &options) {
+        Ok(bytes) => {
             println!(
                 "Compilation successful in {}μs",
                 start_time.elapsed().as_micros()
-            )
+            );
+
+            if fs::write(&output_path, bytes).is_err() {
+                eprintln!("Failed to write {}", output_path);
+                return ExitCode::FAILURE;
+            }
+
+            ExitCode::SUCCESS
+        }
+        Err(errors) => {
+            for err in errors {
+                eprintln!("error[{}]: {}", err.code(), err);
+            }
+
+            eprintln!(
+                "Compilation failed in {}μs",
+                start_time.elapsed().as_micros()
+            );
+
+            ExitCode::FAILURE
         }
-        Err(_) => eprintln!(
-            "Compilation failed in {}μs",
-            start_time.elapsed().as_micros()
-        ),
     }
 }
 
 fn read_file(path: &str) -> Result<String, std::io::Error> {
     fs::read_to_string(path)
 }
+
+/// Applies a command line flag to the options, returning whether it is known.
+/// Features are toggled one by one with `--enable-<feature>` and `--disable-<feature>`,
+/// or all at once with `--preset=mvp`, `--preset=wasm2` or `--preset=wasm3`
+fn apply_flag(options: &mut CompileOptions, flag: &str) -> bool {
+    match flag {
+        "--debug-names" => options.debug_names = true,
+        "--producers" => options.producers = true,
+        "--preset=mvp" => options.features = WasmFeatures::mvp(),
+        "--preset=wasm2" => options.features = WasmFeatures::wasm2(),
+        "--preset=wasm3" => options.features = WasmFeatures::wasm3(),
+        _ => {
+            if let Some(feature) = flag.strip_prefix("--enable-") {
+                return options.features.set(feature, true);
+            }
+
+            if let Some(feature) = flag.strip_prefix("--disable-") {
+                return options.features.set(feature, false);
+            }
+
+            let Some((name, version)) = flag
+                .strip_prefix("--sdk=")
+                .and_then(|sdk| sdk.split_once('@'))
+            else {
+                return false;
+            };
+
+            options.producers = true;
+            options.sdks.push((name.to_owned(), version.to_owned()));
+        }
+    }
+
+    true
+}
@@ -1,4 +1,5 @@
 use crate::{
+    shared::Identifier,
     source::Source,
     tokenizer::TokenType,
     traits::{error_display::ErrorDisplay, page_position::PageCursor},
@@ -95,3 +96,129 @@ impl ErrorDisplay for ExpectedMethodError {
         }
     }
 }
+
+#[derive(Debug)]
+pub struct ExpectedIndexError {
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for ExpectedIndexError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("parser error should point to valid position");
+
+        format!("Expected index or identifier in line:\n{}", position)
+    }
+}
+
+#[derive(Debug)]
+pub struct ExpectedIntegerError {
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for ExpectedIntegerError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("parser error should point to valid position");
+
+        format!("Expected integer in line:\n{}", position)
+    }
+}
+
+#[derive(Debug)]
+pub struct ExpectedNumberError {
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for ExpectedNumberError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("parser error should point to valid position");
+
+        format!("Expected number in line:\n{}", position)
+    }
+}
+
+#[derive(Debug)]
+pub struct ConstantOutOfRangeError {
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for ConstantOutOfRangeError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("parser error should point to valid position");
+
+        format!("Constant out of range in line:\n{}", position)
+    }
+}
+
+#[derive(Debug)]
+pub struct UnexpectedTokenError {
+    pub token_type: TokenType,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for UnexpectedTokenError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("parser error should point to valid position");
+
+        format!(
+            "Unexpected token {:?} in line:\n{}",
+            self.token_type, position
+        )
+    }
+}
+
+/// Reference to a type identifier that no type of the module is named with
+#[derive(Debug)]
+pub struct UnknownTypeError {
+    pub id: Identifier,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for UnknownTypeError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("parser error should point to valid position");
+
+        format!("Unknown type {} in line:\n{}", self.id, position)
+    }
+}
+
+#[derive(Debug)]
+pub struct UnexpectedEndError {
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for UnexpectedEndError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("parser error should point to valid position");
+
+        format!("Unexpected end of input after line:\n{}", position)
+    }
+}
+
+#[derive(Debug)]
+pub struct MalformedNameError {
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for MalformedNameError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("parser error should point to valid position");
+
+        format!("Names must be valid UTF-8 in line:\n{}", position)
+    }
+}
@@ -1,48 +1,349 @@
-use crate::shared::Identifier;
+use std::fmt::Display;
 
-use super::instructions::BuiltinType;
+use crate::{
+    bytecode::SectionId,
+    shared::{Identifier, Index},
+    traits::page_position::PageCursor,
+};
 
-pub struct UnaryOperation {
-    operand: Expr,
-}
+use super::instructions::{BuiltinType, Instruction};
 
-#[derive(Default, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct Module {
+    /// Name of the module, kept for the name section
+    pub id: Option<Identifier>,
     pub exprs: Vec<Expr>,
+    pub cursor: PageCursor,
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeDefinition {
+    pub id: Option<Identifier>,
+    pub composite: CompositeDefinition,
+    /// Types written without `sub` are final
+    pub is_final: bool,
+    pub supertypes: Vec<Index>,
+    pub cursor: PageCursor,
+}
+
+/// Types that can refer to each other regardless of their order, every other
+/// type definition forms a group of its own
+#[derive(Debug, Clone)]
+pub struct RecursionGroup {
+    pub types: Vec<TypeDefinition>,
+    pub cursor: PageCursor,
+}
+
+#[derive(Debug, Clone)]
+pub enum CompositeDefinition {
+    Func(FunctionSignature),
+    Struct(Vec<FieldDefinition>),
+    Array(FieldDefinition),
+}
+
+/// Field of a struct or element of an array, of which only struct fields can be named
+#[derive(Debug, Clone)]
+pub struct FieldDefinition {
+    pub id: Option<Identifier>,
+    pub storage_type: StorageType,
+    pub mutable: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StorageType {
+    Value(BuiltinType),
+    /// Packed integers, which are extended to `i32` when read
+    I8,
+    I16,
+}
+
+impl StorageType {
+    /// Type of the values that are read from and written to the storage
+    pub fn unpacked(&self) -> BuiltinType {
+        match self {
+            StorageType::Value(value_type) => *value_type,
+            StorageType::I8 | StorageType::I16 => BuiltinType::I32,
+        }
+    }
+
+    pub fn is_packed(&self) -> bool {
+        !matches!(self, StorageType::Value(_))
+    }
+}
+
+impl Display for StorageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageType::Value(value_type) => write!(f, "{}", value_type),
+            StorageType::I8 => write!(f, "i8"),
+            StorageType::I16 => write!(f, "i16"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FunctionSignature {
+    pub params: Vec<Param>,
+    pub results: Vec<BuiltinType>,
+}
+
+/// Reference to a function type, either through an index into the
+/// type section or an inline signature
+#[derive(Debug, Clone, Default)]
+pub struct TypeUse {
+    pub index: Option<Index>,
+    pub signature: FunctionSignature,
 }
 
 #[derive(Debug, Clone)]
 pub struct FunctionDefinition {
     pub id: Option<Identifier>,
-    pub body: Vec<Expr>,
+    pub type_use: TypeUse,
+    pub locals: Vec<Local>,
+    pub body: Vec<Instruction>,
+    pub cursor: PageCursor,
+}
+
+/// Size bounds of a memory, in pages of 64KiB
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limits {
+    pub min: u64,
+    pub max: Option<u64>,
+    /// Only memories can be shared between threads
+    pub shared: bool,
+    /// Type of the addresses into a memory, which is `i64` for 64-bit memories
+    pub index_type: BuiltinType,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryDefinition {
+    pub id: Option<Identifier>,
+    pub limits: Limits,
+    /// Contents of an inline `(data ...)` field, written to the start of the memory
+    pub inline_data: Option<Vec<u8>>,
+    pub cursor: PageCursor,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableType {
+    pub limits: Limits,
+    /// Reference type of the elements
+    pub element_type: BuiltinType,
+}
+
+#[derive(Debug, Clone)]
+pub struct TableDefinition {
+    pub id: Option<Identifier>,
+    pub table_type: TableType,
+    /// Contents of an inline `(elem ...)` field, written to the start of the table
+    pub inline_elements: Option<ElementItems>,
+    /// Constant expression every element starts out as, instead of null
+    pub init: Option<Vec<Instruction>>,
+    pub cursor: PageCursor,
+}
+
+#[derive(Debug, Clone)]
+pub enum ElementMode {
+    Passive,
+    /// Only forward declares the functions for `ref.func`
+    Declarative,
+    Active {
+        table: Index,
+        offset: Vec<Instruction>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum ElementItems {
+    Functions(Vec<Index>),
+    /// Constant expressions producing references of the given type
+    Expressions(BuiltinType, Vec<Vec<Instruction>>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ElementDefinition {
+    pub id: Option<Identifier>,
+    pub mode: ElementMode,
+    pub items: ElementItems,
+    pub cursor: PageCursor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobalType {
+    pub value_type: BuiltinType,
+    pub mutable: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct GlobalDefinition {
+    pub id: Option<Identifier>,
+    pub global_type: GlobalType,
+    /// Constant expression computing the initial value
+    pub init: Vec<Instruction>,
+    pub cursor: PageCursor,
+}
+
+/// Exception tag, whose type lists the values an exception carries
+#[derive(Debug, Clone)]
+pub struct TagDefinition {
+    pub id: Option<Identifier>,
+    pub type_use: TypeUse,
+    pub cursor: PageCursor,
+}
+
+#[derive(Debug, Clone)]
+pub enum DataMode {
+    /// Only copied into memory by `memory.init`
+    Passive,
+    /// Written to `memory` at the address computed by `offset` on instantiation
+    Active {
+        memory: Index,
+        offset: Vec<Instruction>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct DataDefinition {
+    pub id: Option<Identifier>,
+    pub mode: DataMode,
+    pub bytes: Vec<u8>,
+    pub cursor: PageCursor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExternalKind {
+    Func = 0x00,
+    Table = 0x01,
+    Memory = 0x02,
+    Global = 0x03,
+    Tag = 0x04,
+}
+
+#[derive(Debug, Clone)]
+pub enum ImportDescription {
+    Func(TypeUse),
+    Table(TableType),
+    Memory(Limits),
+    Global(GlobalType),
+    Tag(TypeUse),
+}
+
+impl ImportDescription {
+    pub fn kind(&self) -> ExternalKind {
+        match self {
+            ImportDescription::Func(_) => ExternalKind::Func,
+            ImportDescription::Table(_) => ExternalKind::Table,
+            ImportDescription::Memory(_) => ExternalKind::Memory,
+            ImportDescription::Global(_) => ExternalKind::Global,
+            ImportDescription::Tag(_) => ExternalKind::Tag,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    pub id: Option<Identifier>,
+    pub description: ImportDescription,
+    pub cursor: PageCursor,
 }
 
 #[derive(Debug, Clone)]
 pub struct Export {
     pub name: String,
-    pub expr: Expr,
+    pub kind: ExternalKind,
+    pub index: Index,
+    pub cursor: PageCursor,
+}
+
+#[derive(Debug, Clone)]
+pub struct StartDefinition {
+    pub function: Index,
+    pub cursor: PageCursor,
+}
+
+/// Position of a custom section relative to a standard section, which doesn't have to be present
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomPlacement {
+    /// Ahead of every other section
+    First,
+    Before(SectionId),
+    After(SectionId),
+    /// Behind every other section, including the ones the compiler adds like `name`
+    Last,
+}
+
+/// Custom section given by a `(@custom ...)` annotation
+#[derive(Debug, Clone)]
+pub struct CustomDefinition {
+    pub name: String,
+    pub placement: CustomPlacement,
+    pub bytes: Vec<u8>,
+    pub cursor: PageCursor,
 }
 
 #[derive(Debug, Clone)]
 pub struct Param {
-    pub id: Identifier,
+    pub id: Option<Identifier>,
     pub parameter_type: BuiltinType,
 }
 
+#[derive(Debug, Clone)]
+pub struct Local {
+    pub id: Option<Identifier>,
+    pub local_type: BuiltinType,
+}
+
 #[derive(Debug, Clone)]
 pub enum Expr {
     Module(Module),
+    Type(TypeDefinition),
+    Rec(RecursionGroup),
     Func(FunctionDefinition),
-    FuncReference(Identifier),
+    Table(TableDefinition),
+    Memory(MemoryDefinition),
+    Global(GlobalDefinition),
+    Tag(TagDefinition),
+    Elem(ElementDefinition),
+    Data(DataDefinition),
+    Import(Box<Import>),
     Export(Box<Export>),
-    Param(Box<Param>),
-    Result(BuiltinType),
-    LocalGet(Identifier),
-    IntOp(BuiltinType, IntOp),
+    Start(StartDefinition),
+    Custom(CustomDefinition),
 }
 
-// https://github.com/WebAssembly/spec/blob/1291af309889a5900cdad9c38526e457cb77eac3/interpreter/syntax/ast.ml#L26
-#[derive(Debug, Clone)]
-pub enum IntOp {
-    Add,
+impl Expr {
+    /// Index space, identifier and position of fields that define an exportable item
+    pub fn definition(&self) -> Option<(ExternalKind, Option<&Identifier>, PageCursor)> {
+        match self {
+            Expr::Func(definition) => Some((
+                ExternalKind::Func,
+                definition.id.as_ref(),
+                definition.cursor,
+            )),
+            Expr::Table(definition) => Some((
+                ExternalKind::Table,
+                definition.id.as_ref(),
+                definition.cursor,
+            )),
+            Expr::Memory(definition) => Some((
+                ExternalKind::Memory,
+                definition.id.as_ref(),
+                definition.cursor,
+            )),
+            Expr::Global(definition) => Some((
+                ExternalKind::Global,
+                definition.id.as_ref(),
+                definition.cursor,
+            )),
+            Expr::Tag(definition) => {
+                Some((ExternalKind::Tag, definition.id.as_ref(), definition.cursor))
+            }
+            Expr::Import(import) => {
+                Some((import.description.kind(), import.id.as_ref(), import.cursor))
+            }
+            _ => None,
+        }
+    }
 }
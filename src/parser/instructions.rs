@@ -1,19 +1,1236 @@
-pub enum InstructionType {
-    Add,
-    Sub,
-    Mul,
-    Div(SignedAware),
-}
+use std::fmt::Display;
 
-#[derive(Debug, Clone)]
+use crate::{
+    bytecode::{ByteValue, Opcode},
+    shared::{Identifier, Index},
+    traits::page_position::PageCursor,
+};
+
+use super::expression::TypeUse;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BuiltinType {
     I32,
     F32,
     I64,
     F64,
+    V128,
+    FuncRef,
+    ExternRef,
+    ExnRef,
+    /// Any other reference type, the nullable `func`, `extern` and `exn` references
+    /// are always written as the variants above
+    Ref(RefType),
+}
+
+impl BuiltinType {
+    /// Builds a reference type, using the shorthand variants where one exists
+    /// so that equal types compare equal
+    pub fn reference(nullable: bool, heap_type: HeapType) -> Self {
+        match (nullable, heap_type) {
+            (true, HeapType::Func) => BuiltinType::FuncRef,
+            (true, HeapType::Extern) => BuiltinType::ExternRef,
+            (true, HeapType::Exn) => BuiltinType::ExnRef,
+            _ => BuiltinType::Ref(RefType {
+                nullable,
+                heap_type,
+            }),
+        }
+    }
+
+    pub fn is_reference(&self) -> bool {
+        self.as_reference().is_some()
+    }
+
+    /// Views any reference type in its general form
+    pub fn as_reference(&self) -> Option<RefType> {
+        let (nullable, heap_type) = match self {
+            BuiltinType::FuncRef => (true, HeapType::Func),
+            BuiltinType::ExternRef => (true, HeapType::Extern),
+            BuiltinType::ExnRef => (true, HeapType::Exn),
+            BuiltinType::Ref(reference) => return Some(*reference),
+            _ => return None,
+        };
+
+        Some(RefType {
+            nullable,
+            heap_type,
+        })
+    }
+
+    /// Locals and fields of non-nullable reference types have no default value to start out with
+    pub fn is_defaultable(&self) -> bool {
+        self.as_reference()
+            .is_none_or(|reference| reference.nullable)
+    }
+}
+
+impl Display for BuiltinType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuiltinType::I32 => write!(f, "i32"),
+            BuiltinType::F32 => write!(f, "f32"),
+            BuiltinType::I64 => write!(f, "i64"),
+            BuiltinType::F64 => write!(f, "f64"),
+            BuiltinType::V128 => write!(f, "v128"),
+            BuiltinType::FuncRef => write!(f, "funcref"),
+            BuiltinType::ExternRef => write!(f, "externref"),
+            BuiltinType::ExnRef => write!(f, "exnref"),
+            BuiltinType::Ref(reference) => write!(f, "{}", reference),
+        }
+    }
+}
+
+/// Reference to a value of `heap_type`, which may be null if `nullable` is set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RefType {
+    pub nullable: bool,
+    pub heap_type: HeapType,
+}
+
+impl Display for RefType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let shorthand = match self.heap_type {
+            HeapType::Func => "funcref",
+            HeapType::Extern => "externref",
+            HeapType::Exn => "exnref",
+            HeapType::Any => "anyref",
+            HeapType::Eq => "eqref",
+            HeapType::I31 => "i31ref",
+            HeapType::Struct => "structref",
+            HeapType::Array => "arrayref",
+            HeapType::None => "nullref",
+            HeapType::NoFunc => "nullfuncref",
+            HeapType::NoExtern => "nullexternref",
+            HeapType::NoExn => "nullexnref",
+            HeapType::Concrete(_) => "",
+        };
+
+        match (self.nullable, shorthand) {
+            (true, "") => write!(f, "(ref null {})", self.heap_type),
+            (true, shorthand) => write!(f, "{}", shorthand),
+            (false, _) => write!(f, "(ref {})", self.heap_type),
+        }
+    }
+}
+
+/// What a reference points to, either one of the abstract hierarchies or a type of the module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeapType {
+    Func,
+    Extern,
+    Exn,
+    Any,
+    Eq,
+    I31,
+    Struct,
+    Array,
+    /// Bottom types, which only contain null
+    None,
+    NoFunc,
+    NoExtern,
+    NoExn,
+    /// Index into the type section, type identifiers are resolved while parsing
+    Concrete(u32),
+}
+
+impl HeapType {
+    /// Abstract heap types are encoded as a single byte, concrete ones as their type index
+    pub fn byte(&self) -> Option<u8> {
+        let byte = match self {
+            HeapType::Func => ByteValue::FuncRef as u8,
+            HeapType::Extern => ByteValue::ExternRef as u8,
+            HeapType::Exn => ByteValue::ExnRef as u8,
+            HeapType::Any => 0x6E,
+            HeapType::Eq => 0x6D,
+            HeapType::I31 => 0x6C,
+            HeapType::Struct => 0x6B,
+            HeapType::Array => 0x6A,
+            HeapType::None => 0x71,
+            HeapType::NoFunc => 0x73,
+            HeapType::NoExtern => 0x72,
+            HeapType::NoExn => 0x74,
+            HeapType::Concrete(_) => return None,
+        };
+
+        Some(byte)
+    }
+
+    /// Abstract heap types this one is a subtype of, including itself, where
+    /// concrete types depend on the module and have none
+    pub fn abstract_supertypes(&self) -> &'static [HeapType] {
+        match self {
+            HeapType::Func => &[HeapType::Func],
+            HeapType::Extern => &[HeapType::Extern],
+            HeapType::Exn => &[HeapType::Exn],
+            HeapType::Any => &[HeapType::Any],
+            HeapType::Eq => &[HeapType::Eq, HeapType::Any],
+            HeapType::I31 => &[HeapType::I31, HeapType::Eq, HeapType::Any],
+            HeapType::Struct => &[HeapType::Struct, HeapType::Eq, HeapType::Any],
+            HeapType::Array => &[HeapType::Array, HeapType::Eq, HeapType::Any],
+            HeapType::None => &[
+                HeapType::None,
+                HeapType::I31,
+                HeapType::Struct,
+                HeapType::Array,
+                HeapType::Eq,
+                HeapType::Any,
+            ],
+            HeapType::NoFunc => &[HeapType::NoFunc, HeapType::Func],
+            HeapType::NoExtern => &[HeapType::NoExtern, HeapType::Extern],
+            HeapType::NoExn => &[HeapType::NoExn, HeapType::Exn],
+            HeapType::Concrete(_) => &[],
+        }
+    }
+
+    /// Bottom types are subtypes of every type in their hierarchy, concrete ones included
+    pub fn is_bottom(&self) -> bool {
+        matches!(
+            self,
+            HeapType::None | HeapType::NoFunc | HeapType::NoExtern | HeapType::NoExn
+        )
+    }
+}
+
+impl Display for HeapType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeapType::Func => write!(f, "func"),
+            HeapType::Extern => write!(f, "extern"),
+            HeapType::Exn => write!(f, "exn"),
+            HeapType::Any => write!(f, "any"),
+            HeapType::Eq => write!(f, "eq"),
+            HeapType::I31 => write!(f, "i31"),
+            HeapType::Struct => write!(f, "struct"),
+            HeapType::Array => write!(f, "array"),
+            HeapType::None => write!(f, "none"),
+            HeapType::NoFunc => write!(f, "nofunc"),
+            HeapType::NoExtern => write!(f, "noextern"),
+            HeapType::NoExn => write!(f, "noexn"),
+            HeapType::Concrete(index) => write!(f, "{}", index),
+        }
+    }
+}
+
+/// Generates [Mnemonic] from a table of instruction keywords and opcodes.
+///
+/// Instructions whose operand and result types are fixed list them after the opcode,
+/// the rest are typed by the compiler based on their immediates.
+macro_rules! define_mnemonics {
+    [ $( $variant:ident = $keyword:literal, $opcode:literal $( $sub_opcode:literal )? $( , [ $( $param:ident ),* ] -> [ $( $result:ident ),* ] )? ; )+ ] => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Mnemonic {
+            $( $variant, )+
+        }
+
+        impl Mnemonic {
+            pub fn from_keyword(keyword: &str) -> Option<Self> {
+                match keyword {
+                    $( $keyword => Some(Self::$variant), )+
+                    _ => None,
+                }
+            }
+
+            pub fn keyword(&self) -> &'static str {
+                match self {
+                    $( Self::$variant => $keyword, )+
+                }
+            }
+
+            pub fn opcode(&self) -> Opcode {
+                match self {
+                    $( Self::$variant => define_mnemonics!(@opcode $opcode $( $sub_opcode )?), )+
+                }
+            }
+
+            /// Operand and result types for instructions that are typed independently of their context
+            pub fn signature(&self) -> Option<(&'static [BuiltinType], &'static [BuiltinType])> {
+                match self {
+                    $( Self::$variant => define_mnemonics!(@signature $( [ $( $param ),* ] -> [ $( $result ),* ] )? ), )+
+                }
+            }
+        }
+    };
+
+    (@opcode $opcode:literal) => { Opcode::Single($opcode) };
+    (@opcode $prefix:literal $sub_opcode:literal) => { Opcode::Prefixed($prefix, $sub_opcode) };
+
+    (@signature) => { None };
+    (@signature [ $( $param:ident ),* ] -> [ $( $result:ident ),* ]) => {
+        Some((&[ $( BuiltinType::$param ),* ], &[ $( BuiltinType::$result ),* ]))
+    };
+}
+
+define_mnemonics![
+    // Control
+    Unreachable = "unreachable", 0x00;
+    Nop = "nop", 0x01, [] -> [];
+    Block = "block", 0x02;
+    Loop = "loop", 0x03;
+    If = "if", 0x04;
+    Else = "else", 0x05;
+    Throw = "throw", 0x08;
+    ThrowRef = "throw_ref", 0x0A;
+    End = "end", 0x0B;
+    Br = "br", 0x0C;
+    BrIf = "br_if", 0x0D;
+    BrTable = "br_table", 0x0E;
+    Return = "return", 0x0F;
+    Call = "call", 0x10;
+    CallIndirect = "call_indirect", 0x11;
+    ReturnCall = "return_call", 0x12;
+    ReturnCallIndirect = "return_call_indirect", 0x13;
+    CallRef = "call_ref", 0x14;
+    ReturnCallRef = "return_call_ref", 0x15;
+    TryTable = "try_table", 0x1F;
+
+    // Parametric
+    Drop = "drop", 0x1A;
+    Select = "select", 0x1B;
+
+    // Variable
+    LocalGet = "local.get", 0x20;
+    LocalSet = "local.set", 0x21;
+    LocalTee = "local.tee", 0x22;
+    GlobalGet = "global.get", 0x23;
+    GlobalSet = "global.set", 0x24;
+
+    // Memory
+    I32Load = "i32.load", 0x28, [I32] -> [I32];
+    I64Load = "i64.load", 0x29, [I32] -> [I64];
+    F32Load = "f32.load", 0x2A, [I32] -> [F32];
+    F64Load = "f64.load", 0x2B, [I32] -> [F64];
+    I32Load8S = "i32.load8_s", 0x2C, [I32] -> [I32];
+    I32Load8U = "i32.load8_u", 0x2D, [I32] -> [I32];
+    I32Load16S = "i32.load16_s", 0x2E, [I32] -> [I32];
+    I32Load16U = "i32.load16_u", 0x2F, [I32] -> [I32];
+    I64Load8S = "i64.load8_s", 0x30, [I32] -> [I64];
+    I64Load8U = "i64.load8_u", 0x31, [I32] -> [I64];
+    I64Load16S = "i64.load16_s", 0x32, [I32] -> [I64];
+    I64Load16U = "i64.load16_u", 0x33, [I32] -> [I64];
+    I64Load32S = "i64.load32_s", 0x34, [I32] -> [I64];
+    I64Load32U = "i64.load32_u", 0x35, [I32] -> [I64];
+    I32Store = "i32.store", 0x36, [I32, I32] -> [];
+    I64Store = "i64.store", 0x37, [I32, I64] -> [];
+    F32Store = "f32.store", 0x38, [I32, F32] -> [];
+    F64Store = "f64.store", 0x39, [I32, F64] -> [];
+    I32Store8 = "i32.store8", 0x3A, [I32, I32] -> [];
+    I32Store16 = "i32.store16", 0x3B, [I32, I32] -> [];
+    I64Store8 = "i64.store8", 0x3C, [I32, I64] -> [];
+    I64Store16 = "i64.store16", 0x3D, [I32, I64] -> [];
+    I64Store32 = "i64.store32", 0x3E, [I32, I64] -> [];
+    MemorySize = "memory.size", 0x3F, [] -> [I32];
+    MemoryGrow = "memory.grow", 0x40, [I32] -> [I32];
+
+    // Constants
+    I32Const = "i32.const", 0x41, [] -> [I32];
+    I64Const = "i64.const", 0x42, [] -> [I64];
+    F32Const = "f32.const", 0x43, [] -> [F32];
+    F64Const = "f64.const", 0x44, [] -> [F64];
+
+    // Comparison
+    I32Eqz = "i32.eqz", 0x45, [I32] -> [I32];
+    I32Eq = "i32.eq", 0x46, [I32, I32] -> [I32];
+    I32Ne = "i32.ne", 0x47, [I32, I32] -> [I32];
+    I32LtS = "i32.lt_s", 0x48, [I32, I32] -> [I32];
+    I32LtU = "i32.lt_u", 0x49, [I32, I32] -> [I32];
+    I32GtS = "i32.gt_s", 0x4A, [I32, I32] -> [I32];
+    I32GtU = "i32.gt_u", 0x4B, [I32, I32] -> [I32];
+    I32LeS = "i32.le_s", 0x4C, [I32, I32] -> [I32];
+    I32LeU = "i32.le_u", 0x4D, [I32, I32] -> [I32];
+    I32GeS = "i32.ge_s", 0x4E, [I32, I32] -> [I32];
+    I32GeU = "i32.ge_u", 0x4F, [I32, I32] -> [I32];
+
+    I64Eqz = "i64.eqz", 0x50, [I64] -> [I32];
+    I64Eq = "i64.eq", 0x51, [I64, I64] -> [I32];
+    I64Ne = "i64.ne", 0x52, [I64, I64] -> [I32];
+    I64LtS = "i64.lt_s", 0x53, [I64, I64] -> [I32];
+    I64LtU = "i64.lt_u", 0x54, [I64, I64] -> [I32];
+    I64GtS = "i64.gt_s", 0x55, [I64, I64] -> [I32];
+    I64GtU = "i64.gt_u", 0x56, [I64, I64] -> [I32];
+    I64LeS = "i64.le_s", 0x57, [I64, I64] -> [I32];
+    I64LeU = "i64.le_u", 0x58, [I64, I64] -> [I32];
+    I64GeS = "i64.ge_s", 0x59, [I64, I64] -> [I32];
+    I64GeU = "i64.ge_u", 0x5A, [I64, I64] -> [I32];
+
+    F32Eq = "f32.eq", 0x5B, [F32, F32] -> [I32];
+    F32Ne = "f32.ne", 0x5C, [F32, F32] -> [I32];
+    F32Lt = "f32.lt", 0x5D, [F32, F32] -> [I32];
+    F32Gt = "f32.gt", 0x5E, [F32, F32] -> [I32];
+    F32Le = "f32.le", 0x5F, [F32, F32] -> [I32];
+    F32Ge = "f32.ge", 0x60, [F32, F32] -> [I32];
+
+    F64Eq = "f64.eq", 0x61, [F64, F64] -> [I32];
+    F64Ne = "f64.ne", 0x62, [F64, F64] -> [I32];
+    F64Lt = "f64.lt", 0x63, [F64, F64] -> [I32];
+    F64Gt = "f64.gt", 0x64, [F64, F64] -> [I32];
+    F64Le = "f64.le", 0x65, [F64, F64] -> [I32];
+    F64Ge = "f64.ge", 0x66, [F64, F64] -> [I32];
+
+    // Integer arithmetic
+    I32Clz = "i32.clz", 0x67, [I32] -> [I32];
+    I32Ctz = "i32.ctz", 0x68, [I32] -> [I32];
+    I32Popcnt = "i32.popcnt", 0x69, [I32] -> [I32];
+    I32Add = "i32.add", 0x6A, [I32, I32] -> [I32];
+    I32Sub = "i32.sub", 0x6B, [I32, I32] -> [I32];
+    I32Mul = "i32.mul", 0x6C, [I32, I32] -> [I32];
+    I32DivS = "i32.div_s", 0x6D, [I32, I32] -> [I32];
+    I32DivU = "i32.div_u", 0x6E, [I32, I32] -> [I32];
+    I32RemS = "i32.rem_s", 0x6F, [I32, I32] -> [I32];
+    I32RemU = "i32.rem_u", 0x70, [I32, I32] -> [I32];
+    I32And = "i32.and", 0x71, [I32, I32] -> [I32];
+    I32Or = "i32.or", 0x72, [I32, I32] -> [I32];
+    I32Xor = "i32.xor", 0x73, [I32, I32] -> [I32];
+    I32Shl = "i32.shl", 0x74, [I32, I32] -> [I32];
+    I32ShrS = "i32.shr_s", 0x75, [I32, I32] -> [I32];
+    I32ShrU = "i32.shr_u", 0x76, [I32, I32] -> [I32];
+    I32Rotl = "i32.rotl", 0x77, [I32, I32] -> [I32];
+    I32Rotr = "i32.rotr", 0x78, [I32, I32] -> [I32];
+
+    I64Clz = "i64.clz", 0x79, [I64] -> [I64];
+    I64Ctz = "i64.ctz", 0x7A, [I64] -> [I64];
+    I64Popcnt = "i64.popcnt", 0x7B, [I64] -> [I64];
+    I64Add = "i64.add", 0x7C, [I64, I64] -> [I64];
+    I64Sub = "i64.sub", 0x7D, [I64, I64] -> [I64];
+    I64Mul = "i64.mul", 0x7E, [I64, I64] -> [I64];
+    I64DivS = "i64.div_s", 0x7F, [I64, I64] -> [I64];
+    I64DivU = "i64.div_u", 0x80, [I64, I64] -> [I64];
+    I64RemS = "i64.rem_s", 0x81, [I64, I64] -> [I64];
+    I64RemU = "i64.rem_u", 0x82, [I64, I64] -> [I64];
+    I64And = "i64.and", 0x83, [I64, I64] -> [I64];
+    I64Or = "i64.or", 0x84, [I64, I64] -> [I64];
+    I64Xor = "i64.xor", 0x85, [I64, I64] -> [I64];
+    I64Shl = "i64.shl", 0x86, [I64, I64] -> [I64];
+    I64ShrS = "i64.shr_s", 0x87, [I64, I64] -> [I64];
+    I64ShrU = "i64.shr_u", 0x88, [I64, I64] -> [I64];
+    I64Rotl = "i64.rotl", 0x89, [I64, I64] -> [I64];
+    I64Rotr = "i64.rotr", 0x8A, [I64, I64] -> [I64];
+
+    // Floating point arithmetic
+    F32Abs = "f32.abs", 0x8B, [F32] -> [F32];
+    F32Neg = "f32.neg", 0x8C, [F32] -> [F32];
+    F32Ceil = "f32.ceil", 0x8D, [F32] -> [F32];
+    F32Floor = "f32.floor", 0x8E, [F32] -> [F32];
+    F32Trunc = "f32.trunc", 0x8F, [F32] -> [F32];
+    F32Nearest = "f32.nearest", 0x90, [F32] -> [F32];
+    F32Sqrt = "f32.sqrt", 0x91, [F32] -> [F32];
+    F32Add = "f32.add", 0x92, [F32, F32] -> [F32];
+    F32Sub = "f32.sub", 0x93, [F32, F32] -> [F32];
+    F32Mul = "f32.mul", 0x94, [F32, F32] -> [F32];
+    F32Div = "f32.div", 0x95, [F32, F32] -> [F32];
+    F32Min = "f32.min", 0x96, [F32, F32] -> [F32];
+    F32Max = "f32.max", 0x97, [F32, F32] -> [F32];
+    F32Copysign = "f32.copysign", 0x98, [F32, F32] -> [F32];
+
+    F64Abs = "f64.abs", 0x99, [F64] -> [F64];
+    F64Neg = "f64.neg", 0x9A, [F64] -> [F64];
+    F64Ceil = "f64.ceil", 0x9B, [F64] -> [F64];
+    F64Floor = "f64.floor", 0x9C, [F64] -> [F64];
+    F64Trunc = "f64.trunc", 0x9D, [F64] -> [F64];
+    F64Nearest = "f64.nearest", 0x9E, [F64] -> [F64];
+    F64Sqrt = "f64.sqrt", 0x9F, [F64] -> [F64];
+    F64Add = "f64.add", 0xA0, [F64, F64] -> [F64];
+    F64Sub = "f64.sub", 0xA1, [F64, F64] -> [F64];
+    F64Mul = "f64.mul", 0xA2, [F64, F64] -> [F64];
+    F64Div = "f64.div", 0xA3, [F64, F64] -> [F64];
+    F64Min = "f64.min", 0xA4, [F64, F64] -> [F64];
+    F64Max = "f64.max", 0xA5, [F64, F64] -> [F64];
+    F64Copysign = "f64.copysign", 0xA6, [F64, F64] -> [F64];
+
+    // Conversions
+    I32WrapI64 = "i32.wrap_i64", 0xA7, [I64] -> [I32];
+    I32TruncF32S = "i32.trunc_f32_s", 0xA8, [F32] -> [I32];
+    I32TruncF32U = "i32.trunc_f32_u", 0xA9, [F32] -> [I32];
+    I32TruncF64S = "i32.trunc_f64_s", 0xAA, [F64] -> [I32];
+    I32TruncF64U = "i32.trunc_f64_u", 0xAB, [F64] -> [I32];
+    I64ExtendI32S = "i64.extend_i32_s", 0xAC, [I32] -> [I64];
+    I64ExtendI32U = "i64.extend_i32_u", 0xAD, [I32] -> [I64];
+    I64TruncF32S = "i64.trunc_f32_s", 0xAE, [F32] -> [I64];
+    I64TruncF32U = "i64.trunc_f32_u", 0xAF, [F32] -> [I64];
+    I64TruncF64S = "i64.trunc_f64_s", 0xB0, [F64] -> [I64];
+    I64TruncF64U = "i64.trunc_f64_u", 0xB1, [F64] -> [I64];
+    F32ConvertI32S = "f32.convert_i32_s", 0xB2, [I32] -> [F32];
+    F32ConvertI32U = "f32.convert_i32_u", 0xB3, [I32] -> [F32];
+    F32ConvertI64S = "f32.convert_i64_s", 0xB4, [I64] -> [F32];
+    F32ConvertI64U = "f32.convert_i64_u", 0xB5, [I64] -> [F32];
+    F32DemoteF64 = "f32.demote_f64", 0xB6, [F64] -> [F32];
+    F64ConvertI32S = "f64.convert_i32_s", 0xB7, [I32] -> [F64];
+    F64ConvertI32U = "f64.convert_i32_u", 0xB8, [I32] -> [F64];
+    F64ConvertI64S = "f64.convert_i64_s", 0xB9, [I64] -> [F64];
+    F64ConvertI64U = "f64.convert_i64_u", 0xBA, [I64] -> [F64];
+    F64PromoteF32 = "f64.promote_f32", 0xBB, [F32] -> [F64];
+    I32ReinterpretF32 = "i32.reinterpret_f32", 0xBC, [F32] -> [I32];
+    I64ReinterpretF64 = "i64.reinterpret_f64", 0xBD, [F64] -> [I64];
+    F32ReinterpretI32 = "f32.reinterpret_i32", 0xBE, [I32] -> [F32];
+    F64ReinterpretI64 = "f64.reinterpret_i64", 0xBF, [I64] -> [F64];
+
+    // Sign extension
+    I32Extend8S = "i32.extend8_s", 0xC0, [I32] -> [I32];
+    I32Extend16S = "i32.extend16_s", 0xC1, [I32] -> [I32];
+    I64Extend8S = "i64.extend8_s", 0xC2, [I64] -> [I64];
+    I64Extend16S = "i64.extend16_s", 0xC3, [I64] -> [I64];
+    I64Extend32S = "i64.extend32_s", 0xC4, [I64] -> [I64];
+
+    // Non-trapping float-to-int conversion
+    I32TruncSatF32S = "i32.trunc_sat_f32_s", 0xFC 0, [F32] -> [I32];
+    I32TruncSatF32U = "i32.trunc_sat_f32_u", 0xFC 1, [F32] -> [I32];
+    I32TruncSatF64S = "i32.trunc_sat_f64_s", 0xFC 2, [F64] -> [I32];
+    I32TruncSatF64U = "i32.trunc_sat_f64_u", 0xFC 3, [F64] -> [I32];
+    I64TruncSatF32S = "i64.trunc_sat_f32_s", 0xFC 4, [F32] -> [I64];
+    I64TruncSatF32U = "i64.trunc_sat_f32_u", 0xFC 5, [F32] -> [I64];
+    I64TruncSatF64S = "i64.trunc_sat_f64_s", 0xFC 6, [F64] -> [I64];
+    I64TruncSatF64U = "i64.trunc_sat_f64_u", 0xFC 7, [F64] -> [I64];
+
+    // Table
+    TableGet = "table.get", 0x25;
+    TableSet = "table.set", 0x26;
+    TableGrow = "table.grow", 0xFC 15;
+    TableSize = "table.size", 0xFC 16;
+    TableFill = "table.fill", 0xFC 17;
+
+    // Reference
+    RefNull = "ref.null", 0xD0;
+    RefIsNull = "ref.is_null", 0xD1;
+    RefFunc = "ref.func", 0xD2;
+    RefEq = "ref.eq", 0xD3;
+    RefAsNonNull = "ref.as_non_null", 0xD4;
+    BrOnNull = "br_on_null", 0xD5;
+    BrOnNonNull = "br_on_non_null", 0xD6;
+
+    // Garbage collection, the nullable forms of `ref.test` and `ref.cast` use the next opcode
+    StructNew = "struct.new", 0xFB 0;
+    StructNewDefault = "struct.new_default", 0xFB 1;
+    StructGet = "struct.get", 0xFB 2;
+    StructGetS = "struct.get_s", 0xFB 3;
+    StructGetU = "struct.get_u", 0xFB 4;
+    StructSet = "struct.set", 0xFB 5;
+    ArrayNew = "array.new", 0xFB 6;
+    ArrayNewDefault = "array.new_default", 0xFB 7;
+    ArrayNewFixed = "array.new_fixed", 0xFB 8;
+    ArrayNewData = "array.new_data", 0xFB 9;
+    ArrayNewElem = "array.new_elem", 0xFB 10;
+    ArrayGet = "array.get", 0xFB 11;
+    ArrayGetS = "array.get_s", 0xFB 12;
+    ArrayGetU = "array.get_u", 0xFB 13;
+    ArraySet = "array.set", 0xFB 14;
+    ArrayLen = "array.len", 0xFB 15;
+    ArrayFill = "array.fill", 0xFB 16;
+    ArrayCopy = "array.copy", 0xFB 17;
+    ArrayInitData = "array.init_data", 0xFB 18;
+    ArrayInitElem = "array.init_elem", 0xFB 19;
+    RefTest = "ref.test", 0xFB 20;
+    RefCast = "ref.cast", 0xFB 22;
+    BrOnCast = "br_on_cast", 0xFB 24;
+    BrOnCastFail = "br_on_cast_fail", 0xFB 25;
+    AnyConvertExtern = "any.convert_extern", 0xFB 26;
+    ExternConvertAny = "extern.convert_any", 0xFB 27;
+    RefI31 = "ref.i31", 0xFB 28;
+    I31GetS = "i31.get_s", 0xFB 29;
+    I31GetU = "i31.get_u", 0xFB 30;
+
+    // Bulk memory
+    MemoryInit = "memory.init", 0xFC 8, [I32, I32, I32] -> [];
+    DataDrop = "data.drop", 0xFC 9, [] -> [];
+    MemoryCopy = "memory.copy", 0xFC 10, [I32, I32, I32] -> [];
+    MemoryFill = "memory.fill", 0xFC 11, [I32, I32, I32] -> [];
+    TableInit = "table.init", 0xFC 12, [I32, I32, I32] -> [];
+    ElemDrop = "elem.drop", 0xFC 13, [] -> [];
+    TableCopy = "table.copy", 0xFC 14, [I32, I32, I32] -> [];
+
+    // Atomic memory
+    MemoryAtomicNotify = "memory.atomic.notify", 0xFE 0, [I32, I32] -> [I32];
+    MemoryAtomicWait32 = "memory.atomic.wait32", 0xFE 1, [I32, I32, I64] -> [I32];
+    MemoryAtomicWait64 = "memory.atomic.wait64", 0xFE 2, [I32, I64, I64] -> [I32];
+    AtomicFence = "atomic.fence", 0xFE 3, [] -> [];
+    I32AtomicLoad = "i32.atomic.load", 0xFE 16, [I32] -> [I32];
+    I64AtomicLoad = "i64.atomic.load", 0xFE 17, [I32] -> [I64];
+    I32AtomicLoad8U = "i32.atomic.load8_u", 0xFE 18, [I32] -> [I32];
+    I32AtomicLoad16U = "i32.atomic.load16_u", 0xFE 19, [I32] -> [I32];
+    I64AtomicLoad8U = "i64.atomic.load8_u", 0xFE 20, [I32] -> [I64];
+    I64AtomicLoad16U = "i64.atomic.load16_u", 0xFE 21, [I32] -> [I64];
+    I64AtomicLoad32U = "i64.atomic.load32_u", 0xFE 22, [I32] -> [I64];
+    I32AtomicStore = "i32.atomic.store", 0xFE 23, [I32, I32] -> [];
+    I64AtomicStore = "i64.atomic.store", 0xFE 24, [I32, I64] -> [];
+    I32AtomicStore8 = "i32.atomic.store8", 0xFE 25, [I32, I32] -> [];
+    I32AtomicStore16 = "i32.atomic.store16", 0xFE 26, [I32, I32] -> [];
+    I64AtomicStore8 = "i64.atomic.store8", 0xFE 27, [I32, I64] -> [];
+    I64AtomicStore16 = "i64.atomic.store16", 0xFE 28, [I32, I64] -> [];
+    I64AtomicStore32 = "i64.atomic.store32", 0xFE 29, [I32, I64] -> [];
+    I32AtomicRmwAdd = "i32.atomic.rmw.add", 0xFE 30, [I32, I32] -> [I32];
+    I64AtomicRmwAdd = "i64.atomic.rmw.add", 0xFE 31, [I32, I64] -> [I64];
+    I32AtomicRmw8AddU = "i32.atomic.rmw8.add_u", 0xFE 32, [I32, I32] -> [I32];
+    I32AtomicRmw16AddU = "i32.atomic.rmw16.add_u", 0xFE 33, [I32, I32] -> [I32];
+    I64AtomicRmw8AddU = "i64.atomic.rmw8.add_u", 0xFE 34, [I32, I64] -> [I64];
+    I64AtomicRmw16AddU = "i64.atomic.rmw16.add_u", 0xFE 35, [I32, I64] -> [I64];
+    I64AtomicRmw32AddU = "i64.atomic.rmw32.add_u", 0xFE 36, [I32, I64] -> [I64];
+    I32AtomicRmwSub = "i32.atomic.rmw.sub", 0xFE 37, [I32, I32] -> [I32];
+    I64AtomicRmwSub = "i64.atomic.rmw.sub", 0xFE 38, [I32, I64] -> [I64];
+    I32AtomicRmw8SubU = "i32.atomic.rmw8.sub_u", 0xFE 39, [I32, I32] -> [I32];
+    I32AtomicRmw16SubU = "i32.atomic.rmw16.sub_u", 0xFE 40, [I32, I32] -> [I32];
+    I64AtomicRmw8SubU = "i64.atomic.rmw8.sub_u", 0xFE 41, [I32, I64] -> [I64];
+    I64AtomicRmw16SubU = "i64.atomic.rmw16.sub_u", 0xFE 42, [I32, I64] -> [I64];
+    I64AtomicRmw32SubU = "i64.atomic.rmw32.sub_u", 0xFE 43, [I32, I64] -> [I64];
+    I32AtomicRmwAnd = "i32.atomic.rmw.and", 0xFE 44, [I32, I32] -> [I32];
+    I64AtomicRmwAnd = "i64.atomic.rmw.and", 0xFE 45, [I32, I64] -> [I64];
+    I32AtomicRmw8AndU = "i32.atomic.rmw8.and_u", 0xFE 46, [I32, I32] -> [I32];
+    I32AtomicRmw16AndU = "i32.atomic.rmw16.and_u", 0xFE 47, [I32, I32] -> [I32];
+    I64AtomicRmw8AndU = "i64.atomic.rmw8.and_u", 0xFE 48, [I32, I64] -> [I64];
+    I64AtomicRmw16AndU = "i64.atomic.rmw16.and_u", 0xFE 49, [I32, I64] -> [I64];
+    I64AtomicRmw32AndU = "i64.atomic.rmw32.and_u", 0xFE 50, [I32, I64] -> [I64];
+    I32AtomicRmwOr = "i32.atomic.rmw.or", 0xFE 51, [I32, I32] -> [I32];
+    I64AtomicRmwOr = "i64.atomic.rmw.or", 0xFE 52, [I32, I64] -> [I64];
+    I32AtomicRmw8OrU = "i32.atomic.rmw8.or_u", 0xFE 53, [I32, I32] -> [I32];
+    I32AtomicRmw16OrU = "i32.atomic.rmw16.or_u", 0xFE 54, [I32, I32] -> [I32];
+    I64AtomicRmw8OrU = "i64.atomic.rmw8.or_u", 0xFE 55, [I32, I64] -> [I64];
+    I64AtomicRmw16OrU = "i64.atomic.rmw16.or_u", 0xFE 56, [I32, I64] -> [I64];
+    I64AtomicRmw32OrU = "i64.atomic.rmw32.or_u", 0xFE 57, [I32, I64] -> [I64];
+    I32AtomicRmwXor = "i32.atomic.rmw.xor", 0xFE 58, [I32, I32] -> [I32];
+    I64AtomicRmwXor = "i64.atomic.rmw.xor", 0xFE 59, [I32, I64] -> [I64];
+    I32AtomicRmw8XorU = "i32.atomic.rmw8.xor_u", 0xFE 60, [I32, I32] -> [I32];
+    I32AtomicRmw16XorU = "i32.atomic.rmw16.xor_u", 0xFE 61, [I32, I32] -> [I32];
+    I64AtomicRmw8XorU = "i64.atomic.rmw8.xor_u", 0xFE 62, [I32, I64] -> [I64];
+    I64AtomicRmw16XorU = "i64.atomic.rmw16.xor_u", 0xFE 63, [I32, I64] -> [I64];
+    I64AtomicRmw32XorU = "i64.atomic.rmw32.xor_u", 0xFE 64, [I32, I64] -> [I64];
+    I32AtomicRmwXchg = "i32.atomic.rmw.xchg", 0xFE 65, [I32, I32] -> [I32];
+    I64AtomicRmwXchg = "i64.atomic.rmw.xchg", 0xFE 66, [I32, I64] -> [I64];
+    I32AtomicRmw8XchgU = "i32.atomic.rmw8.xchg_u", 0xFE 67, [I32, I32] -> [I32];
+    I32AtomicRmw16XchgU = "i32.atomic.rmw16.xchg_u", 0xFE 68, [I32, I32] -> [I32];
+    I64AtomicRmw8XchgU = "i64.atomic.rmw8.xchg_u", 0xFE 69, [I32, I64] -> [I64];
+    I64AtomicRmw16XchgU = "i64.atomic.rmw16.xchg_u", 0xFE 70, [I32, I64] -> [I64];
+    I64AtomicRmw32XchgU = "i64.atomic.rmw32.xchg_u", 0xFE 71, [I32, I64] -> [I64];
+    I32AtomicRmwCmpxchg = "i32.atomic.rmw.cmpxchg", 0xFE 72, [I32, I32, I32] -> [I32];
+    I64AtomicRmwCmpxchg = "i64.atomic.rmw.cmpxchg", 0xFE 73, [I32, I64, I64] -> [I64];
+    I32AtomicRmw8CmpxchgU = "i32.atomic.rmw8.cmpxchg_u", 0xFE 74, [I32, I32, I32] -> [I32];
+    I32AtomicRmw16CmpxchgU = "i32.atomic.rmw16.cmpxchg_u", 0xFE 75, [I32, I32, I32] -> [I32];
+    I64AtomicRmw8CmpxchgU = "i64.atomic.rmw8.cmpxchg_u", 0xFE 76, [I32, I64, I64] -> [I64];
+    I64AtomicRmw16CmpxchgU = "i64.atomic.rmw16.cmpxchg_u", 0xFE 77, [I32, I64, I64] -> [I64];
+    I64AtomicRmw32CmpxchgU = "i64.atomic.rmw32.cmpxchg_u", 0xFE 78, [I32, I64, I64] -> [I64];
+
+    // Vector
+    V128Load = "v128.load", 0xFD 0, [I32] -> [V128];
+    V128Load8x8S = "v128.load8x8_s", 0xFD 1, [I32] -> [V128];
+    V128Load8x8U = "v128.load8x8_u", 0xFD 2, [I32] -> [V128];
+    V128Load16x4S = "v128.load16x4_s", 0xFD 3, [I32] -> [V128];
+    V128Load16x4U = "v128.load16x4_u", 0xFD 4, [I32] -> [V128];
+    V128Load32x2S = "v128.load32x2_s", 0xFD 5, [I32] -> [V128];
+    V128Load32x2U = "v128.load32x2_u", 0xFD 6, [I32] -> [V128];
+    V128Load8Splat = "v128.load8_splat", 0xFD 7, [I32] -> [V128];
+    V128Load16Splat = "v128.load16_splat", 0xFD 8, [I32] -> [V128];
+    V128Load32Splat = "v128.load32_splat", 0xFD 9, [I32] -> [V128];
+    V128Load64Splat = "v128.load64_splat", 0xFD 10, [I32] -> [V128];
+    V128Store = "v128.store", 0xFD 11, [I32, V128] -> [];
+    V128Const = "v128.const", 0xFD 12, [] -> [V128];
+    I8x16Shuffle = "i8x16.shuffle", 0xFD 13, [V128, V128] -> [V128];
+    I8x16Swizzle = "i8x16.swizzle", 0xFD 14, [V128, V128] -> [V128];
+    I8x16Splat = "i8x16.splat", 0xFD 15, [I32] -> [V128];
+    I16x8Splat = "i16x8.splat", 0xFD 16, [I32] -> [V128];
+    I32x4Splat = "i32x4.splat", 0xFD 17, [I32] -> [V128];
+    I64x2Splat = "i64x2.splat", 0xFD 18, [I64] -> [V128];
+    F32x4Splat = "f32x4.splat", 0xFD 19, [F32] -> [V128];
+    F64x2Splat = "f64x2.splat", 0xFD 20, [F64] -> [V128];
+    I8x16ExtractLaneS = "i8x16.extract_lane_s", 0xFD 21, [V128] -> [I32];
+    I8x16ExtractLaneU = "i8x16.extract_lane_u", 0xFD 22, [V128] -> [I32];
+    I8x16ReplaceLane = "i8x16.replace_lane", 0xFD 23, [V128, I32] -> [V128];
+    I16x8ExtractLaneS = "i16x8.extract_lane_s", 0xFD 24, [V128] -> [I32];
+    I16x8ExtractLaneU = "i16x8.extract_lane_u", 0xFD 25, [V128] -> [I32];
+    I16x8ReplaceLane = "i16x8.replace_lane", 0xFD 26, [V128, I32] -> [V128];
+    I32x4ExtractLane = "i32x4.extract_lane", 0xFD 27, [V128] -> [I32];
+    I32x4ReplaceLane = "i32x4.replace_lane", 0xFD 28, [V128, I32] -> [V128];
+    I64x2ExtractLane = "i64x2.extract_lane", 0xFD 29, [V128] -> [I64];
+    I64x2ReplaceLane = "i64x2.replace_lane", 0xFD 30, [V128, I64] -> [V128];
+    F32x4ExtractLane = "f32x4.extract_lane", 0xFD 31, [V128] -> [F32];
+    F32x4ReplaceLane = "f32x4.replace_lane", 0xFD 32, [V128, F32] -> [V128];
+    F64x2ExtractLane = "f64x2.extract_lane", 0xFD 33, [V128] -> [F64];
+    F64x2ReplaceLane = "f64x2.replace_lane", 0xFD 34, [V128, F64] -> [V128];
+    I8x16Eq = "i8x16.eq", 0xFD 35, [V128, V128] -> [V128];
+    I8x16Ne = "i8x16.ne", 0xFD 36, [V128, V128] -> [V128];
+    I8x16LtS = "i8x16.lt_s", 0xFD 37, [V128, V128] -> [V128];
+    I8x16LtU = "i8x16.lt_u", 0xFD 38, [V128, V128] -> [V128];
+    I8x16GtS = "i8x16.gt_s", 0xFD 39, [V128, V128] -> [V128];
+    I8x16GtU = "i8x16.gt_u", 0xFD 40, [V128, V128] -> [V128];
+    I8x16LeS = "i8x16.le_s", 0xFD 41, [V128, V128] -> [V128];
+    I8x16LeU = "i8x16.le_u", 0xFD 42, [V128, V128] -> [V128];
+    I8x16GeS = "i8x16.ge_s", 0xFD 43, [V128, V128] -> [V128];
+    I8x16GeU = "i8x16.ge_u", 0xFD 44, [V128, V128] -> [V128];
+    I16x8Eq = "i16x8.eq", 0xFD 45, [V128, V128] -> [V128];
+    I16x8Ne = "i16x8.ne", 0xFD 46, [V128, V128] -> [V128];
+    I16x8LtS = "i16x8.lt_s", 0xFD 47, [V128, V128] -> [V128];
+    I16x8LtU = "i16x8.lt_u", 0xFD 48, [V128, V128] -> [V128];
+    I16x8GtS = "i16x8.gt_s", 0xFD 49, [V128, V128] -> [V128];
+    I16x8GtU = "i16x8.gt_u", 0xFD 50, [V128, V128] -> [V128];
+    I16x8LeS = "i16x8.le_s", 0xFD 51, [V128, V128] -> [V128];
+    I16x8LeU = "i16x8.le_u", 0xFD 52, [V128, V128] -> [V128];
+    I16x8GeS = "i16x8.ge_s", 0xFD 53, [V128, V128] -> [V128];
+    I16x8GeU = "i16x8.ge_u", 0xFD 54, [V128, V128] -> [V128];
+    I32x4Eq = "i32x4.eq", 0xFD 55, [V128, V128] -> [V128];
+    I32x4Ne = "i32x4.ne", 0xFD 56, [V128, V128] -> [V128];
+    I32x4LtS = "i32x4.lt_s", 0xFD 57, [V128, V128] -> [V128];
+    I32x4LtU = "i32x4.lt_u", 0xFD 58, [V128, V128] -> [V128];
+    I32x4GtS = "i32x4.gt_s", 0xFD 59, [V128, V128] -> [V128];
+    I32x4GtU = "i32x4.gt_u", 0xFD 60, [V128, V128] -> [V128];
+    I32x4LeS = "i32x4.le_s", 0xFD 61, [V128, V128] -> [V128];
+    I32x4LeU = "i32x4.le_u", 0xFD 62, [V128, V128] -> [V128];
+    I32x4GeS = "i32x4.ge_s", 0xFD 63, [V128, V128] -> [V128];
+    I32x4GeU = "i32x4.ge_u", 0xFD 64, [V128, V128] -> [V128];
+    F32x4Eq = "f32x4.eq", 0xFD 65, [V128, V128] -> [V128];
+    F32x4Ne = "f32x4.ne", 0xFD 66, [V128, V128] -> [V128];
+    F32x4Lt = "f32x4.lt", 0xFD 67, [V128, V128] -> [V128];
+    F32x4Gt = "f32x4.gt", 0xFD 68, [V128, V128] -> [V128];
+    F32x4Le = "f32x4.le", 0xFD 69, [V128, V128] -> [V128];
+    F32x4Ge = "f32x4.ge", 0xFD 70, [V128, V128] -> [V128];
+    F64x2Eq = "f64x2.eq", 0xFD 71, [V128, V128] -> [V128];
+    F64x2Ne = "f64x2.ne", 0xFD 72, [V128, V128] -> [V128];
+    F64x2Lt = "f64x2.lt", 0xFD 73, [V128, V128] -> [V128];
+    F64x2Gt = "f64x2.gt", 0xFD 74, [V128, V128] -> [V128];
+    F64x2Le = "f64x2.le", 0xFD 75, [V128, V128] -> [V128];
+    F64x2Ge = "f64x2.ge", 0xFD 76, [V128, V128] -> [V128];
+    V128Not = "v128.not", 0xFD 77, [V128] -> [V128];
+    V128And = "v128.and", 0xFD 78, [V128, V128] -> [V128];
+    V128Andnot = "v128.andnot", 0xFD 79, [V128, V128] -> [V128];
+    V128Or = "v128.or", 0xFD 80, [V128, V128] -> [V128];
+    V128Xor = "v128.xor", 0xFD 81, [V128, V128] -> [V128];
+    V128Bitselect = "v128.bitselect", 0xFD 82, [V128, V128, V128] -> [V128];
+    V128AnyTrue = "v128.any_true", 0xFD 83, [V128] -> [I32];
+    V128Load8Lane = "v128.load8_lane", 0xFD 84, [I32, V128] -> [V128];
+    V128Load16Lane = "v128.load16_lane", 0xFD 85, [I32, V128] -> [V128];
+    V128Load32Lane = "v128.load32_lane", 0xFD 86, [I32, V128] -> [V128];
+    V128Load64Lane = "v128.load64_lane", 0xFD 87, [I32, V128] -> [V128];
+    V128Store8Lane = "v128.store8_lane", 0xFD 88, [I32, V128] -> [];
+    V128Store16Lane = "v128.store16_lane", 0xFD 89, [I32, V128] -> [];
+    V128Store32Lane = "v128.store32_lane", 0xFD 90, [I32, V128] -> [];
+    V128Store64Lane = "v128.store64_lane", 0xFD 91, [I32, V128] -> [];
+    V128Load32Zero = "v128.load32_zero", 0xFD 92, [I32] -> [V128];
+    V128Load64Zero = "v128.load64_zero", 0xFD 93, [I32] -> [V128];
+    F32x4DemoteF64x2Zero = "f32x4.demote_f64x2_zero", 0xFD 94, [V128] -> [V128];
+    F64x2PromoteLowF32x4 = "f64x2.promote_low_f32x4", 0xFD 95, [V128] -> [V128];
+    I8x16Abs = "i8x16.abs", 0xFD 96, [V128] -> [V128];
+    I8x16Neg = "i8x16.neg", 0xFD 97, [V128] -> [V128];
+    I8x16Popcnt = "i8x16.popcnt", 0xFD 98, [V128] -> [V128];
+    I8x16AllTrue = "i8x16.all_true", 0xFD 99, [V128] -> [I32];
+    I8x16Bitmask = "i8x16.bitmask", 0xFD 100, [V128] -> [I32];
+    I8x16NarrowI16x8S = "i8x16.narrow_i16x8_s", 0xFD 101, [V128, V128] -> [V128];
+    I8x16NarrowI16x8U = "i8x16.narrow_i16x8_u", 0xFD 102, [V128, V128] -> [V128];
+    F32x4Ceil = "f32x4.ceil", 0xFD 103, [V128] -> [V128];
+    F32x4Floor = "f32x4.floor", 0xFD 104, [V128] -> [V128];
+    F32x4Trunc = "f32x4.trunc", 0xFD 105, [V128] -> [V128];
+    F32x4Nearest = "f32x4.nearest", 0xFD 106, [V128] -> [V128];
+    I8x16Shl = "i8x16.shl", 0xFD 107, [V128, I32] -> [V128];
+    I8x16ShrS = "i8x16.shr_s", 0xFD 108, [V128, I32] -> [V128];
+    I8x16ShrU = "i8x16.shr_u", 0xFD 109, [V128, I32] -> [V128];
+    I8x16Add = "i8x16.add", 0xFD 110, [V128, V128] -> [V128];
+    I8x16AddSatS = "i8x16.add_sat_s", 0xFD 111, [V128, V128] -> [V128];
+    I8x16AddSatU = "i8x16.add_sat_u", 0xFD 112, [V128, V128] -> [V128];
+    I8x16Sub = "i8x16.sub", 0xFD 113, [V128, V128] -> [V128];
+    I8x16SubSatS = "i8x16.sub_sat_s", 0xFD 114, [V128, V128] -> [V128];
+    I8x16SubSatU = "i8x16.sub_sat_u", 0xFD 115, [V128, V128] -> [V128];
+    F64x2Ceil = "f64x2.ceil", 0xFD 116, [V128] -> [V128];
+    F64x2Floor = "f64x2.floor", 0xFD 117, [V128] -> [V128];
+    I8x16MinS = "i8x16.min_s", 0xFD 118, [V128, V128] -> [V128];
+    I8x16MinU = "i8x16.min_u", 0xFD 119, [V128, V128] -> [V128];
+    I8x16MaxS = "i8x16.max_s", 0xFD 120, [V128, V128] -> [V128];
+    I8x16MaxU = "i8x16.max_u", 0xFD 121, [V128, V128] -> [V128];
+    F64x2Trunc = "f64x2.trunc", 0xFD 122, [V128] -> [V128];
+    I8x16AvgrU = "i8x16.avgr_u", 0xFD 123, [V128, V128] -> [V128];
+    I16x8ExtaddPairwiseI8x16S = "i16x8.extadd_pairwise_i8x16_s", 0xFD 124, [V128] -> [V128];
+    I16x8ExtaddPairwiseI8x16U = "i16x8.extadd_pairwise_i8x16_u", 0xFD 125, [V128] -> [V128];
+    I32x4ExtaddPairwiseI16x8S = "i32x4.extadd_pairwise_i16x8_s", 0xFD 126, [V128] -> [V128];
+    I32x4ExtaddPairwiseI16x8U = "i32x4.extadd_pairwise_i16x8_u", 0xFD 127, [V128] -> [V128];
+    I16x8Abs = "i16x8.abs", 0xFD 128, [V128] -> [V128];
+    I16x8Neg = "i16x8.neg", 0xFD 129, [V128] -> [V128];
+    I16x8Q15mulrSatS = "i16x8.q15mulr_sat_s", 0xFD 130, [V128, V128] -> [V128];
+    I16x8AllTrue = "i16x8.all_true", 0xFD 131, [V128] -> [I32];
+    I16x8Bitmask = "i16x8.bitmask", 0xFD 132, [V128] -> [I32];
+    I16x8NarrowI32x4S = "i16x8.narrow_i32x4_s", 0xFD 133, [V128, V128] -> [V128];
+    I16x8NarrowI32x4U = "i16x8.narrow_i32x4_u", 0xFD 134, [V128, V128] -> [V128];
+    I16x8ExtendLowI8x16S = "i16x8.extend_low_i8x16_s", 0xFD 135, [V128] -> [V128];
+    I16x8ExtendHighI8x16S = "i16x8.extend_high_i8x16_s", 0xFD 136, [V128] -> [V128];
+    I16x8ExtendLowI8x16U = "i16x8.extend_low_i8x16_u", 0xFD 137, [V128] -> [V128];
+    I16x8ExtendHighI8x16U = "i16x8.extend_high_i8x16_u", 0xFD 138, [V128] -> [V128];
+    I16x8Shl = "i16x8.shl", 0xFD 139, [V128, I32] -> [V128];
+    I16x8ShrS = "i16x8.shr_s", 0xFD 140, [V128, I32] -> [V128];
+    I16x8ShrU = "i16x8.shr_u", 0xFD 141, [V128, I32] -> [V128];
+    I16x8Add = "i16x8.add", 0xFD 142, [V128, V128] -> [V128];
+    I16x8AddSatS = "i16x8.add_sat_s", 0xFD 143, [V128, V128] -> [V128];
+    I16x8AddSatU = "i16x8.add_sat_u", 0xFD 144, [V128, V128] -> [V128];
+    I16x8Sub = "i16x8.sub", 0xFD 145, [V128, V128] -> [V128];
+    I16x8SubSatS = "i16x8.sub_sat_s", 0xFD 146, [V128, V128] -> [V128];
+    I16x8SubSatU = "i16x8.sub_sat_u", 0xFD 147, [V128, V128] -> [V128];
+    F64x2Nearest = "f64x2.nearest", 0xFD 148, [V128] -> [V128];
+    I16x8Mul = "i16x8.mul", 0xFD 149, [V128, V128] -> [V128];
+    I16x8MinS = "i16x8.min_s", 0xFD 150, [V128, V128] -> [V128];
+    I16x8MinU = "i16x8.min_u", 0xFD 151, [V128, V128] -> [V128];
+    I16x8MaxS = "i16x8.max_s", 0xFD 152, [V128, V128] -> [V128];
+    I16x8MaxU = "i16x8.max_u", 0xFD 153, [V128, V128] -> [V128];
+    I16x8AvgrU = "i16x8.avgr_u", 0xFD 155, [V128, V128] -> [V128];
+    I16x8ExtmulLowI8x16S = "i16x8.extmul_low_i8x16_s", 0xFD 156, [V128, V128] -> [V128];
+    I16x8ExtmulHighI8x16S = "i16x8.extmul_high_i8x16_s", 0xFD 157, [V128, V128] -> [V128];
+    I16x8ExtmulLowI8x16U = "i16x8.extmul_low_i8x16_u", 0xFD 158, [V128, V128] -> [V128];
+    I16x8ExtmulHighI8x16U = "i16x8.extmul_high_i8x16_u", 0xFD 159, [V128, V128] -> [V128];
+    I32x4Abs = "i32x4.abs", 0xFD 160, [V128] -> [V128];
+    I32x4Neg = "i32x4.neg", 0xFD 161, [V128] -> [V128];
+    I32x4AllTrue = "i32x4.all_true", 0xFD 163, [V128] -> [I32];
+    I32x4Bitmask = "i32x4.bitmask", 0xFD 164, [V128] -> [I32];
+    I32x4ExtendLowI16x8S = "i32x4.extend_low_i16x8_s", 0xFD 167, [V128] -> [V128];
+    I32x4ExtendHighI16x8S = "i32x4.extend_high_i16x8_s", 0xFD 168, [V128] -> [V128];
+    I32x4ExtendLowI16x8U = "i32x4.extend_low_i16x8_u", 0xFD 169, [V128] -> [V128];
+    I32x4ExtendHighI16x8U = "i32x4.extend_high_i16x8_u", 0xFD 170, [V128] -> [V128];
+    I32x4Shl = "i32x4.shl", 0xFD 171, [V128, I32] -> [V128];
+    I32x4ShrS = "i32x4.shr_s", 0xFD 172, [V128, I32] -> [V128];
+    I32x4ShrU = "i32x4.shr_u", 0xFD 173, [V128, I32] -> [V128];
+    I32x4Add = "i32x4.add", 0xFD 174, [V128, V128] -> [V128];
+    I32x4Sub = "i32x4.sub", 0xFD 177, [V128, V128] -> [V128];
+    I32x4Mul = "i32x4.mul", 0xFD 181, [V128, V128] -> [V128];
+    I32x4MinS = "i32x4.min_s", 0xFD 182, [V128, V128] -> [V128];
+    I32x4MinU = "i32x4.min_u", 0xFD 183, [V128, V128] -> [V128];
+    I32x4MaxS = "i32x4.max_s", 0xFD 184, [V128, V128] -> [V128];
+    I32x4MaxU = "i32x4.max_u", 0xFD 185, [V128, V128] -> [V128];
+    I32x4DotI16x8S = "i32x4.dot_i16x8_s", 0xFD 186, [V128, V128] -> [V128];
+    I32x4ExtmulLowI16x8S = "i32x4.extmul_low_i16x8_s", 0xFD 188, [V128, V128] -> [V128];
+    I32x4ExtmulHighI16x8S = "i32x4.extmul_high_i16x8_s", 0xFD 189, [V128, V128] -> [V128];
+    I32x4ExtmulLowI16x8U = "i32x4.extmul_low_i16x8_u", 0xFD 190, [V128, V128] -> [V128];
+    I32x4ExtmulHighI16x8U = "i32x4.extmul_high_i16x8_u", 0xFD 191, [V128, V128] -> [V128];
+    I64x2Abs = "i64x2.abs", 0xFD 192, [V128] -> [V128];
+    I64x2Neg = "i64x2.neg", 0xFD 193, [V128] -> [V128];
+    I64x2AllTrue = "i64x2.all_true", 0xFD 195, [V128] -> [I32];
+    I64x2Bitmask = "i64x2.bitmask", 0xFD 196, [V128] -> [I32];
+    I64x2ExtendLowI32x4S = "i64x2.extend_low_i32x4_s", 0xFD 199, [V128] -> [V128];
+    I64x2ExtendHighI32x4S = "i64x2.extend_high_i32x4_s", 0xFD 200, [V128] -> [V128];
+    I64x2ExtendLowI32x4U = "i64x2.extend_low_i32x4_u", 0xFD 201, [V128] -> [V128];
+    I64x2ExtendHighI32x4U = "i64x2.extend_high_i32x4_u", 0xFD 202, [V128] -> [V128];
+    I64x2Shl = "i64x2.shl", 0xFD 203, [V128, I32] -> [V128];
+    I64x2ShrS = "i64x2.shr_s", 0xFD 204, [V128, I32] -> [V128];
+    I64x2ShrU = "i64x2.shr_u", 0xFD 205, [V128, I32] -> [V128];
+    I64x2Add = "i64x2.add", 0xFD 206, [V128, V128] -> [V128];
+    I64x2Sub = "i64x2.sub", 0xFD 209, [V128, V128] -> [V128];
+    I64x2Mul = "i64x2.mul", 0xFD 213, [V128, V128] -> [V128];
+    I64x2Eq = "i64x2.eq", 0xFD 214, [V128, V128] -> [V128];
+    I64x2Ne = "i64x2.ne", 0xFD 215, [V128, V128] -> [V128];
+    I64x2LtS = "i64x2.lt_s", 0xFD 216, [V128, V128] -> [V128];
+    I64x2GtS = "i64x2.gt_s", 0xFD 217, [V128, V128] -> [V128];
+    I64x2LeS = "i64x2.le_s", 0xFD 218, [V128, V128] -> [V128];
+    I64x2GeS = "i64x2.ge_s", 0xFD 219, [V128, V128] -> [V128];
+    I64x2ExtmulLowI32x4S = "i64x2.extmul_low_i32x4_s", 0xFD 220, [V128, V128] -> [V128];
+    I64x2ExtmulHighI32x4S = "i64x2.extmul_high_i32x4_s", 0xFD 221, [V128, V128] -> [V128];
+    I64x2ExtmulLowI32x4U = "i64x2.extmul_low_i32x4_u", 0xFD 222, [V128, V128] -> [V128];
+    I64x2ExtmulHighI32x4U = "i64x2.extmul_high_i32x4_u", 0xFD 223, [V128, V128] -> [V128];
+    F32x4Abs = "f32x4.abs", 0xFD 224, [V128] -> [V128];
+    F32x4Neg = "f32x4.neg", 0xFD 225, [V128] -> [V128];
+    F32x4Sqrt = "f32x4.sqrt", 0xFD 227, [V128] -> [V128];
+    F32x4Add = "f32x4.add", 0xFD 228, [V128, V128] -> [V128];
+    F32x4Sub = "f32x4.sub", 0xFD 229, [V128, V128] -> [V128];
+    F32x4Mul = "f32x4.mul", 0xFD 230, [V128, V128] -> [V128];
+    F32x4Div = "f32x4.div", 0xFD 231, [V128, V128] -> [V128];
+    F32x4Min = "f32x4.min", 0xFD 232, [V128, V128] -> [V128];
+    F32x4Max = "f32x4.max", 0xFD 233, [V128, V128] -> [V128];
+    F32x4Pmin = "f32x4.pmin", 0xFD 234, [V128, V128] -> [V128];
+    F32x4Pmax = "f32x4.pmax", 0xFD 235, [V128, V128] -> [V128];
+    F64x2Abs = "f64x2.abs", 0xFD 236, [V128] -> [V128];
+    F64x2Neg = "f64x2.neg", 0xFD 237, [V128] -> [V128];
+    F64x2Sqrt = "f64x2.sqrt", 0xFD 239, [V128] -> [V128];
+    F64x2Add = "f64x2.add", 0xFD 240, [V128, V128] -> [V128];
+    F64x2Sub = "f64x2.sub", 0xFD 241, [V128, V128] -> [V128];
+    F64x2Mul = "f64x2.mul", 0xFD 242, [V128, V128] -> [V128];
+    F64x2Div = "f64x2.div", 0xFD 243, [V128, V128] -> [V128];
+    F64x2Min = "f64x2.min", 0xFD 244, [V128, V128] -> [V128];
+    F64x2Max = "f64x2.max", 0xFD 245, [V128, V128] -> [V128];
+    F64x2Pmin = "f64x2.pmin", 0xFD 246, [V128, V128] -> [V128];
+    F64x2Pmax = "f64x2.pmax", 0xFD 247, [V128, V128] -> [V128];
+    I32x4TruncSatF32x4S = "i32x4.trunc_sat_f32x4_s", 0xFD 248, [V128] -> [V128];
+    I32x4TruncSatF32x4U = "i32x4.trunc_sat_f32x4_u", 0xFD 249, [V128] -> [V128];
+    F32x4ConvertI32x4S = "f32x4.convert_i32x4_s", 0xFD 250, [V128] -> [V128];
+    F32x4ConvertI32x4U = "f32x4.convert_i32x4_u", 0xFD 251, [V128] -> [V128];
+    I32x4TruncSatF64x2SZero = "i32x4.trunc_sat_f64x2_s_zero", 0xFD 252, [V128] -> [V128];
+    I32x4TruncSatF64x2UZero = "i32x4.trunc_sat_f64x2_u_zero", 0xFD 253, [V128] -> [V128];
+    F64x2ConvertLowI32x4S = "f64x2.convert_low_i32x4_s", 0xFD 254, [V128] -> [V128];
+    F64x2ConvertLowI32x4U = "f64x2.convert_low_i32x4_u", 0xFD 255, [V128] -> [V128];
+];
+
+impl Mnemonic {
+    /// Width in bytes of the value a memory instruction accesses, which is the largest alignment it allows
+    pub fn natural_alignment(&self) -> Option<u64> {
+        match self {
+            Mnemonic::I32Load8S
+            | Mnemonic::I32Load8U
+            | Mnemonic::I64Load8S
+            | Mnemonic::I64Load8U
+            | Mnemonic::I32Store8
+            | Mnemonic::I64Store8
+            | Mnemonic::V128Load8Splat
+            | Mnemonic::V128Load8Lane
+            | Mnemonic::V128Store8Lane
+            | Mnemonic::I32AtomicLoad8U
+            | Mnemonic::I64AtomicLoad8U
+            | Mnemonic::I32AtomicStore8
+            | Mnemonic::I64AtomicStore8
+            | Mnemonic::I32AtomicRmw8AddU
+            | Mnemonic::I64AtomicRmw8AddU
+            | Mnemonic::I32AtomicRmw8SubU
+            | Mnemonic::I64AtomicRmw8SubU
+            | Mnemonic::I32AtomicRmw8AndU
+            | Mnemonic::I64AtomicRmw8AndU
+            | Mnemonic::I32AtomicRmw8OrU
+            | Mnemonic::I64AtomicRmw8OrU
+            | Mnemonic::I32AtomicRmw8XorU
+            | Mnemonic::I64AtomicRmw8XorU
+            | Mnemonic::I32AtomicRmw8XchgU
+            | Mnemonic::I64AtomicRmw8XchgU
+            | Mnemonic::I32AtomicRmw8CmpxchgU
+            | Mnemonic::I64AtomicRmw8CmpxchgU => Some(1),
+
+            Mnemonic::I32Load16S
+            | Mnemonic::I32Load16U
+            | Mnemonic::I64Load16S
+            | Mnemonic::I64Load16U
+            | Mnemonic::I32Store16
+            | Mnemonic::I64Store16
+            | Mnemonic::V128Load16Splat
+            | Mnemonic::V128Load16Lane
+            | Mnemonic::V128Store16Lane
+            | Mnemonic::I32AtomicLoad16U
+            | Mnemonic::I64AtomicLoad16U
+            | Mnemonic::I32AtomicStore16
+            | Mnemonic::I64AtomicStore16
+            | Mnemonic::I32AtomicRmw16AddU
+            | Mnemonic::I64AtomicRmw16AddU
+            | Mnemonic::I32AtomicRmw16SubU
+            | Mnemonic::I64AtomicRmw16SubU
+            | Mnemonic::I32AtomicRmw16AndU
+            | Mnemonic::I64AtomicRmw16AndU
+            | Mnemonic::I32AtomicRmw16OrU
+            | Mnemonic::I64AtomicRmw16OrU
+            | Mnemonic::I32AtomicRmw16XorU
+            | Mnemonic::I64AtomicRmw16XorU
+            | Mnemonic::I32AtomicRmw16XchgU
+            | Mnemonic::I64AtomicRmw16XchgU
+            | Mnemonic::I32AtomicRmw16CmpxchgU
+            | Mnemonic::I64AtomicRmw16CmpxchgU => Some(2),
+
+            Mnemonic::I32Load
+            | Mnemonic::F32Load
+            | Mnemonic::I64Load32S
+            | Mnemonic::I64Load32U
+            | Mnemonic::I32Store
+            | Mnemonic::F32Store
+            | Mnemonic::I64Store32
+            | Mnemonic::V128Load32Splat
+            | Mnemonic::V128Load32Zero
+            | Mnemonic::V128Load32Lane
+            | Mnemonic::V128Store32Lane
+            | Mnemonic::MemoryAtomicNotify
+            | Mnemonic::MemoryAtomicWait32
+            | Mnemonic::I32AtomicLoad
+            | Mnemonic::I64AtomicLoad32U
+            | Mnemonic::I32AtomicStore
+            | Mnemonic::I64AtomicStore32
+            | Mnemonic::I32AtomicRmwAdd
+            | Mnemonic::I64AtomicRmw32AddU
+            | Mnemonic::I32AtomicRmwSub
+            | Mnemonic::I64AtomicRmw32SubU
+            | Mnemonic::I32AtomicRmwAnd
+            | Mnemonic::I64AtomicRmw32AndU
+            | Mnemonic::I32AtomicRmwOr
+            | Mnemonic::I64AtomicRmw32OrU
+            | Mnemonic::I32AtomicRmwXor
+            | Mnemonic::I64AtomicRmw32XorU
+            | Mnemonic::I32AtomicRmwXchg
+            | Mnemonic::I64AtomicRmw32XchgU
+            | Mnemonic::I32AtomicRmwCmpxchg
+            | Mnemonic::I64AtomicRmw32CmpxchgU => Some(4),
+
+            Mnemonic::I64Load
+            | Mnemonic::F64Load
+            | Mnemonic::I64Store
+            | Mnemonic::F64Store
+            | Mnemonic::V128Load8x8S
+            | Mnemonic::V128Load8x8U
+            | Mnemonic::V128Load16x4S
+            | Mnemonic::V128Load16x4U
+            | Mnemonic::V128Load32x2S
+            | Mnemonic::V128Load32x2U
+            | Mnemonic::V128Load64Splat
+            | Mnemonic::V128Load64Zero
+            | Mnemonic::V128Load64Lane
+            | Mnemonic::V128Store64Lane
+            | Mnemonic::MemoryAtomicWait64
+            | Mnemonic::I64AtomicLoad
+            | Mnemonic::I64AtomicStore
+            | Mnemonic::I64AtomicRmwAdd
+            | Mnemonic::I64AtomicRmwSub
+            | Mnemonic::I64AtomicRmwAnd
+            | Mnemonic::I64AtomicRmwOr
+            | Mnemonic::I64AtomicRmwXor
+            | Mnemonic::I64AtomicRmwXchg
+            | Mnemonic::I64AtomicRmwCmpxchg => Some(8),
+
+            Mnemonic::V128Load | Mnemonic::V128Store => Some(16),
+
+            _ => None,
+        }
+    }
+}
+
+impl Mnemonic {
+    /// Number of lanes of the vector an instruction with a lane index immediate selects from
+    pub fn lanes(&self) -> Option<u8> {
+        match self {
+            Mnemonic::I8x16ExtractLaneS
+            | Mnemonic::I8x16ExtractLaneU
+            | Mnemonic::I8x16ReplaceLane
+            | Mnemonic::V128Load8Lane
+            | Mnemonic::V128Store8Lane => Some(16),
+
+            Mnemonic::I16x8ExtractLaneS
+            | Mnemonic::I16x8ExtractLaneU
+            | Mnemonic::I16x8ReplaceLane
+            | Mnemonic::V128Load16Lane
+            | Mnemonic::V128Store16Lane => Some(8),
+
+            Mnemonic::I32x4ExtractLane
+            | Mnemonic::I32x4ReplaceLane
+            | Mnemonic::F32x4ExtractLane
+            | Mnemonic::F32x4ReplaceLane
+            | Mnemonic::V128Load32Lane
+            | Mnemonic::V128Store32Lane => Some(4),
+
+            Mnemonic::I64x2ExtractLane
+            | Mnemonic::I64x2ReplaceLane
+            | Mnemonic::F64x2ExtractLane
+            | Mnemonic::F64x2ReplaceLane
+            | Mnemonic::V128Load64Lane
+            | Mnemonic::V128Store64Lane => Some(2),
+
+            _ => None,
+        }
+    }
+
+    /// Atomic memory accesses, which have to be naturally aligned
+    pub fn is_atomic(&self) -> bool {
+        matches!(self.opcode(), Opcode::Prefixed(0xFE, _))
+    }
+
+    /// Whether the instruction takes a memory argument followed by a lane index
+    pub fn is_memory_lane(&self) -> bool {
+        self.lanes().is_some() && self.natural_alignment().is_some()
+    }
+}
+
+impl Mnemonic {
+    /// `select` with explicit result types shares its keyword, but not its opcode
+    pub const TYPED_SELECT: Opcode = Opcode::Single(0x1C);
+}
+
+impl Display for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.keyword())
+    }
+}
+
+/// Interpretation of the lanes of a `v128.const`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorShape {
+    I8x16,
+    I16x8,
+    I32x4,
+    I64x2,
+    F32x4,
+    F64x2,
+}
+
+impl VectorShape {
+    pub fn lanes(&self) -> usize {
+        match self {
+            VectorShape::I8x16 => 16,
+            VectorShape::I16x8 => 8,
+            VectorShape::I32x4 | VectorShape::F32x4 => 4,
+            VectorShape::I64x2 | VectorShape::F64x2 => 2,
+        }
+    }
+}
+
+/// Clause of a `try_table`, the discriminant being its encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchKind {
+    Catch = 0x00,
+    CatchRef = 0x01,
+    CatchAll = 0x02,
+    CatchAllRef = 0x03,
+}
+
+/// Branch taken when an exception reaches a `try_table`, `tag` is only left out by `catch_all`
+#[derive(Debug, Clone)]
+pub struct CatchClause {
+    pub kind: CatchKind,
+    pub tag: Option<Index>,
+    pub label: Index,
+}
+
+#[derive(Debug, Clone)]
+pub enum BlockType {
+    Empty,
+    Value(BuiltinType),
+    /// Any other signature, which is encoded as a type index
+    TypeUse(TypeUse),
+}
+
+/// Immediates of memory instructions, `align` defaults to the natural alignment when left out
+#[derive(Debug, Clone, Default)]
+pub struct MemArg {
+    pub offset: u64,
+    pub align: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    pub kind: InstructionKind,
+    pub cursor: PageCursor,
+}
+
+#[derive(Debug, Clone)]
+pub enum InstructionKind {
+    Block(Option<Identifier>, BlockType),
+    Loop(Option<Identifier>, BlockType),
+    If(Option<Identifier>, BlockType),
+    Else(Option<Identifier>),
+    End(Option<Identifier>),
+    TryTable(Option<Identifier>, BlockType, Vec<CatchClause>),
+
+    /// Instruction without any immediates
+    Plain(Mnemonic),
+
+    /// `select` followed by its result types
+    TypedSelect(Vec<BuiltinType>),
+
+    Br(Index),
+    BrIf(Index),
+    BrTable(Vec<Index>, Index),
+    Call(Index),
+    CallIndirect(Index, TypeUse),
+    /// Calls that replace the current frame, so they return straight to its caller
+    ReturnCall(Index),
+    ReturnCallIndirect(Index, TypeUse),
+    /// Calls through a typed function reference
+    CallRef(Index),
+    ReturnCallRef(Index),
+    Throw(Index),
+    BrOnNull(Index),
+    BrOnNonNull(Index),
+    /// `br_on_cast` or `br_on_cast_fail` with its label, the type of
+    /// its operand and the type it is cast to
+    BrOnCast(Mnemonic, Index, BuiltinType, BuiltinType),
+
+    LocalGet(Index),
+    LocalSet(Index),
+    LocalTee(Index),
+    GlobalGet(Index),
+    GlobalSet(Index),
+
+    /// Load or store through the given memory
+    Memory(Mnemonic, Index, MemArg),
+    /// Vector load or store of a single lane
+    MemoryLane(Mnemonic, Index, MemArg, u8),
+    MemorySize(Index),
+    MemoryGrow(Index),
+    /// Data segment followed by the memory it is copied into
+    MemoryInit(Index, Index),
+    DataDrop(Index),
+    /// Destination memory followed by the source memory
+    MemoryCopy(Index, Index),
+    MemoryFill(Index),
+
+    /// Table instructions whose only immediate is the table they operate on
+    Table(Mnemonic, Index),
+    /// Element segment followed by the table it is copied into
+    TableInit(Index, Index),
+    ElemDrop(Index),
+    /// Destination table followed by the source table
+    TableCopy(Index, Index),
+
+    /// Null reference of the given heap type
+    RefNull(HeapType),
+    RefFunc(Index),
+    /// `ref.test` or `ref.cast` to the given reference type
+    RefCast(Mnemonic, BuiltinType),
+
+    /// Struct or array instruction whose only immediate is its type
+    Aggregate(Mnemonic, Index),
+    /// Struct type followed by the field that is read or written
+    StructField(Mnemonic, Index, Index),
+    /// Array type followed by the number of elements taken from the stack
+    ArrayNewFixed(Index, u32),
+    /// Array type followed by the data or element segment it is filled from
+    ArraySegment(Mnemonic, Index, Index),
+    /// Destination array type followed by the source array type
+    ArrayCopy(Index, Index),
+
+    I32Const(i32),
+    I64Const(i64),
+    /// Bit pattern of the constant, so NaN payloads survive
+    F32Const(u32),
+    F64Const(u64),
+    /// Little endian bytes of the vector, whatever shape it was written in
+    V128Const([u8; 16]),
+    /// Lanes of the two operands that make up the result
+    Shuffle([u8; 16]),
+    /// Lane extraction or replacement
+    Lane(Mnemonic, u8),
 }
 
-enum SignedAware {
-    Signed,
-    Unsigned,
+impl InstructionKind {
+    pub fn mnemonic(&self) -> Mnemonic {
+        match self {
+            InstructionKind::Block(..) => Mnemonic::Block,
+            InstructionKind::Loop(..) => Mnemonic::Loop,
+            InstructionKind::If(..) => Mnemonic::If,
+            InstructionKind::Else(_) => Mnemonic::Else,
+            InstructionKind::End(_) => Mnemonic::End,
+            InstructionKind::TryTable(..) => Mnemonic::TryTable,
+            InstructionKind::Plain(mnemonic) => *mnemonic,
+            InstructionKind::TypedSelect(_) => Mnemonic::Select,
+            InstructionKind::Br(_) => Mnemonic::Br,
+            InstructionKind::BrIf(_) => Mnemonic::BrIf,
+            InstructionKind::BrTable(..) => Mnemonic::BrTable,
+            InstructionKind::Call(_) => Mnemonic::Call,
+            InstructionKind::CallIndirect(..) => Mnemonic::CallIndirect,
+            InstructionKind::ReturnCall(_) => Mnemonic::ReturnCall,
+            InstructionKind::ReturnCallIndirect(..) => Mnemonic::ReturnCallIndirect,
+            InstructionKind::CallRef(_) => Mnemonic::CallRef,
+            InstructionKind::ReturnCallRef(_) => Mnemonic::ReturnCallRef,
+            InstructionKind::Throw(_) => Mnemonic::Throw,
+            InstructionKind::BrOnNull(_) => Mnemonic::BrOnNull,
+            InstructionKind::BrOnNonNull(_) => Mnemonic::BrOnNonNull,
+            InstructionKind::BrOnCast(mnemonic, ..) => *mnemonic,
+            InstructionKind::LocalGet(_) => Mnemonic::LocalGet,
+            InstructionKind::LocalSet(_) => Mnemonic::LocalSet,
+            InstructionKind::LocalTee(_) => Mnemonic::LocalTee,
+            InstructionKind::GlobalGet(_) => Mnemonic::GlobalGet,
+            InstructionKind::GlobalSet(_) => Mnemonic::GlobalSet,
+            InstructionKind::Memory(mnemonic, ..) => *mnemonic,
+            InstructionKind::MemoryLane(mnemonic, ..) => *mnemonic,
+            InstructionKind::MemorySize(_) => Mnemonic::MemorySize,
+            InstructionKind::MemoryGrow(_) => Mnemonic::MemoryGrow,
+            InstructionKind::MemoryInit(..) => Mnemonic::MemoryInit,
+            InstructionKind::DataDrop(_) => Mnemonic::DataDrop,
+            InstructionKind::MemoryCopy(..) => Mnemonic::MemoryCopy,
+            InstructionKind::MemoryFill(_) => Mnemonic::MemoryFill,
+            InstructionKind::TableInit(..) => Mnemonic::TableInit,
+            InstructionKind::ElemDrop(_) => Mnemonic::ElemDrop,
+            InstructionKind::TableCopy(..) => Mnemonic::TableCopy,
+            InstructionKind::Table(mnemonic, _) => *mnemonic,
+            InstructionKind::RefNull(_) => Mnemonic::RefNull,
+            InstructionKind::RefFunc(_) => Mnemonic::RefFunc,
+            InstructionKind::RefCast(mnemonic, _) => *mnemonic,
+            InstructionKind::Aggregate(mnemonic, _) => *mnemonic,
+            InstructionKind::StructField(mnemonic, ..) => *mnemonic,
+            InstructionKind::ArrayNewFixed(..) => Mnemonic::ArrayNewFixed,
+            InstructionKind::ArraySegment(mnemonic, ..) => *mnemonic,
+            InstructionKind::ArrayCopy(..) => Mnemonic::ArrayCopy,
+            InstructionKind::I32Const(_) => Mnemonic::I32Const,
+            InstructionKind::I64Const(_) => Mnemonic::I64Const,
+            InstructionKind::F32Const(_) => Mnemonic::F32Const,
+            InstructionKind::F64Const(_) => Mnemonic::F64Const,
+            InstructionKind::V128Const(_) => Mnemonic::V128Const,
+            InstructionKind::Shuffle(_) => Mnemonic::I8x16Shuffle,
+            InstructionKind::Lane(mnemonic, _) => *mnemonic,
+        }
+    }
 }
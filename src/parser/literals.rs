@@ -0,0 +1,230 @@
+/// Lanes of a vector constant follow the same rule as `i32.const`, so an `i8x16`
+/// lane accepts anything from `-128` up to `255`
+pub fn integer_to_i8(value: i128) -> Option<i8> {
+    if (i8::MIN as i128..=u8::MAX as i128).contains(&value) {
+        Some(value as u8 as i8)
+    } else {
+        None
+    }
+}
+
+pub fn integer_to_i16(value: i128) -> Option<i16> {
+    if (i16::MIN as i128..=u16::MAX as i128).contains(&value) {
+        Some(value as u16 as i16)
+    } else {
+        None
+    }
+}
+
+/// Integer constants may be written signed or unsigned, so `i32.const` accepts
+/// anything from `-2^31` up to `2^32 - 1`
+pub fn integer_to_i32(value: i128) -> Option<i32> {
+    if (i32::MIN as i128..=u32::MAX as i128).contains(&value) {
+        Some(value as u32 as i32)
+    } else {
+        None
+    }
+}
+
+pub fn integer_to_i64(value: i128) -> Option<i64> {
+    if (i64::MIN as i128..=u64::MAX as i128).contains(&value) {
+        Some(value as u64 as i64)
+    } else {
+        None
+    }
+}
+
+pub fn float_to_f32_bits(literal: &str) -> Option<u32> {
+    let bits = match special_float_bits(literal, 23, 8) {
+        Some(bits) => bits?,
+        None => {
+            let value: f32 = literal.replace('_', "").parse().ok()?;
+
+            if value.is_infinite() {
+                return None;
+            }
+
+            value.to_bits() as u64
+        }
+    };
+
+    Some(bits as u32)
+}
+
+pub fn float_to_f64_bits(literal: &str) -> Option<u64> {
+    match special_float_bits(literal, 52, 11) {
+        Some(bits) => bits,
+        None => {
+            let value: f64 = literal.replace('_', "").parse().ok()?;
+
+            if value.is_infinite() {
+                return None;
+            }
+
+            Some(value.to_bits())
+        }
+    }
+}
+
+/// Handles the float notations Rust can't parse itself, being `inf`, `nan`,
+/// `nan:0x...` and hexadecimal floats.
+///
+/// Returns [None] for plain decimal floats and `Some(None)` for malformed ones.
+fn special_float_bits(
+    literal: &str,
+    mantissa_bits: u32,
+    exponent_bits: u32,
+) -> Option<Option<u64>> {
+    let (negative, magnitude) = match literal.strip_prefix('-') {
+        Some(magnitude) => (true, magnitude),
+        None => (false, literal.strip_prefix('+').unwrap_or(literal)),
+    };
+
+    let sign = (negative as u64) << (mantissa_bits + exponent_bits);
+    let infinity = ((1u64 << exponent_bits) - 1) << mantissa_bits;
+    let mantissa_mask = (1u64 << mantissa_bits) - 1;
+
+    if magnitude == "inf" {
+        return Some(Some(sign | infinity));
+    }
+
+    if magnitude == "nan" {
+        return Some(Some(sign | infinity | 1 << (mantissa_bits - 1)));
+    }
+
+    if let Some(payload) = magnitude.strip_prefix("nan:0x") {
+        let payload = u64::from_str_radix(&payload.replace('_', ""), 16).ok();
+
+        return Some(
+            payload
+                .filter(|payload| (1..=mantissa_mask).contains(payload))
+                .map(|payload| sign | infinity | payload),
+        );
+    }
+
+    let hex = magnitude.strip_prefix("0x")?;
+
+    Some(hex_float_bits(hex, mantissa_bits, exponent_bits).map(|bits| sign | bits))
+}
+
+/// Correctly rounded (to nearest, ties to even) conversion of an unsigned hexadecimal float
+fn hex_float_bits(hex: &str, mantissa_bits: u32, exponent_bits: u32) -> Option<u64> {
+    let hex = hex.replace('_', "");
+
+    let (digits, exponent) = match hex.split_once(['p', 'P']) {
+        Some((digits, exponent)) => (digits, exponent.parse::<i64>().ok()?),
+        None => (hex.as_str(), 0),
+    };
+
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+    // Digits that no longer fit are only relevant to break rounding ties
+    let mut mantissa: u128 = 0;
+    let mut exponent = exponent;
+    let mut sticky = false;
+
+    for digit in whole.chars() {
+        let digit = digit.to_digit(16)? as u128;
+
+        if mantissa >> 120 == 0 {
+            mantissa = mantissa * 16 + digit;
+        } else {
+            exponent = exponent.saturating_add(4);
+            sticky |= digit != 0;
+        }
+    }
+
+    for digit in fraction.chars() {
+        let digit = digit.to_digit(16)? as u128;
+
+        if mantissa >> 120 == 0 {
+            mantissa = mantissa * 16 + digit;
+            exponent = exponent.saturating_sub(4);
+        } else {
+            sticky |= digit != 0;
+        }
+    }
+
+    if mantissa == 0 {
+        return Some(0);
+    }
+
+    let bias = (1i64 << (exponent_bits - 1)) - 1;
+    let highest_bit = 127 - mantissa.leading_zeros() as i64;
+    let value_exponent = highest_bit.saturating_add(exponent);
+
+    // Subnormals have fewer significant bits, so they're shifted further
+    let shift = if value_exponent >= 1 - bias {
+        highest_bit - mantissa_bits as i64
+    } else {
+        highest_bit - mantissa_bits as i64 + (1 - bias - value_exponent)
+    };
+
+    let mut significand = if shift <= 0 {
+        mantissa << -shift
+    } else if shift >= 128 {
+        0
+    } else {
+        let remainder = mantissa & ((1 << shift) - 1);
+        let half = 1 << (shift - 1);
+        let truncated = mantissa >> shift;
+
+        let round_up = remainder > half || (remainder == half && (sticky || truncated & 1 == 1));
+
+        truncated + round_up as u128
+    };
+
+    let mut value_exponent = value_exponent;
+
+    if value_exponent < 1 - bias {
+        // Rounding a subnormal up to the smallest normal carries into the exponent field
+        return Some(significand as u64);
+    }
+
+    if significand >> (mantissa_bits + 1) != 0 {
+        significand >>= 1;
+        value_exponent += 1;
+    }
+
+    if value_exponent > bias {
+        return None;
+    }
+
+    let biased_exponent = (value_exponent + bias) as u64;
+
+    Some(biased_exponent << mantissa_bits | (significand as u64 & ((1 << mantissa_bits) - 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{float_to_f32_bits, float_to_f64_bits, integer_to_i32};
+
+    #[test]
+    fn test_unsigned_i32_wraps() {
+        assert_eq!(integer_to_i32(0xFFFF_FFFF), Some(-1));
+        assert_eq!(integer_to_i32(0x1_0000_0000), None);
+    }
+
+    #[test]
+    fn test_decimal_float() {
+        assert_eq!(float_to_f32_bits("1.5"), Some(1.5f32.to_bits()));
+        assert_eq!(
+            float_to_f64_bits("-1_000.25e-1"),
+            Some((-100.025f64).to_bits())
+        );
+    }
+
+    #[test]
+    fn test_hex_float() {
+        assert_eq!(float_to_f32_bits("0x1.8p1"), Some(3.0f32.to_bits()));
+        assert_eq!(float_to_f64_bits("-0x10"), Some((-16.0f64).to_bits()));
+        assert_eq!(float_to_f64_bits("0x1p-1074"), Some(1));
+        assert_eq!(float_to_f32_bits("0x1.ffffffp127"), None);
+    }
+
+    #[test]
+    fn test_nan_payload() {
+        assert_eq!(float_to_f32_bits("nan:0x200000"), Some(0x7FA0_0000));
+        assert_eq!(float_to_f32_bits("-inf"), Some(0xFF80_0000));
+    }
+}
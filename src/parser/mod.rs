@@ -1,140 +1,1720 @@
 pub mod errors;
 pub mod expression;
-mod instructions;
+pub mod instructions;
+mod literals;
 mod rules;
-use std::vec;
 
-use errors::ExpectedMethodError;
-use expression::{Export, Expr, FunctionDefinition, IntOp, Module, Param};
-use instructions::BuiltinType;
+use errors::{
+    ConstantOutOfRangeError, ExpectedMethodError, ExpectedNumberError, ExpectedTypeError,
+    UnexpectedEndError, UnexpectedTokenError, UnknownTypeError,
+};
+use expression::{
+    CompositeDefinition, CustomDefinition, CustomPlacement, DataDefinition, DataMode,
+    ElementDefinition, ElementItems, ElementMode, Export, Expr, ExternalKind, FieldDefinition,
+    FunctionDefinition, FunctionSignature, GlobalDefinition, GlobalType, Import, ImportDescription,
+    Limits, Local, MemoryDefinition, Module, Param, RecursionGroup, StartDefinition, StorageType,
+    TableDefinition, TableType, TagDefinition, TypeDefinition, TypeUse,
+};
+use instructions::{
+    BlockType, BuiltinType, CatchClause, CatchKind, HeapType, Instruction, InstructionKind, MemArg,
+    Mnemonic, VectorShape,
+};
+use literals::{
+    float_to_f32_bits, float_to_f64_bits, integer_to_i16, integer_to_i32, integer_to_i64,
+    integer_to_i8,
+};
+
+use std::collections::HashMap;
 
 use crate::{
+    bytecode::SectionId,
+    diagnostic::Error,
+    features::{errors::DisabledFeatureError, WasmFeatures},
+    shared::{Identifier, Index},
     tokenizer::{
         token_store::{TokenIter, TokenStore},
-        TokenType,
+        Token, TokenType,
     },
-    traits::error_display::ErrorDisplay,
+    traits::page_position::PageCursor,
 };
 
 type Tokens<'a> = &'a mut TokenIter;
 
-pub fn parse_tokens(tokens: TokenStore) -> Result<Expr, Box<dyn ErrorDisplay>> {
-    parse_expression(&mut tokens.into_iter())
+/// Size of a page of linear memory in bytes
+const PAGE_SIZE: u64 = 65536;
+
+/// Module field along with the names of its inline exports
+struct Field {
+    expr: Expr,
+    exports: Vec<String>,
 }
 
-fn parse_expression(tokens: Tokens) -> Result<Expr, Box<dyn ErrorDisplay>> {
-    loop {
-        let token = tokens.next().unwrap();
+pub fn parse_tokens(tokens: TokenStore, features: &WasmFeatures) -> Result<Module, Error> {
+    let tokens = &mut tokens.into_iter();
+
+    tokens.set_features(*features);
+    tokens.set_type_names(scan_type_names(tokens));
 
-        return match token.token_type {
-            TokenType::LineComment(_) => continue,
+    let module = if tokens.peek_clause(TokenType::Module) {
+        tokens.consume(TokenType::LeftParen)?;
+
+        let token = tokens.consume(TokenType::Module)?;
+        let module = Module {
+            id: tokens.consume_optional_identifier(),
+            exprs: parse_module_fields(tokens)?,
+            cursor: token.cursor,
+        };
+
+        tokens.consume(TokenType::RightParen)?;
+
+        module
+    } else {
+        // The fields of a module may also be written without the enclosing `(module ...)`
+        Module {
+            id: None,
+            cursor: tokens.peek_cursor(),
+            exprs: parse_module_fields(tokens)?,
+        }
+    };
+
+    match tokens.peek() {
+        Some(token) => Err(Error::from(UnexpectedTokenError {
+            token_type: token.token_type,
+            cursor: token.cursor,
+        })),
+        None => Ok(module),
+    }
+}
 
-            // TODO: Better figure out scoping
-            TokenType::LeftParen => continue,
-            TokenType::Module => Ok(Expr::Module(Module {
-                exprs: parse_multiple_expressions(tokens)?,
-            })),
+/// Numbers the types of the module up front, since reference types may name
+/// types that are defined further down, like the next type of a recursion group
+fn scan_type_names(tokens: &TokenIter) -> HashMap<Identifier, u32> {
+    let mut type_names = HashMap::new();
+    let mut count = 0;
+    // Keyword of every clause the scan is inside of, types are only defined in modules and groups
+    let mut clauses = Vec::new();
+    let mut position = 0;
 
-            TokenType::Func => {
-                let (id, _) = tokens.consume_identifier()?;
+    while let Some(token) = tokens.peek_nth(position) {
+        match token.token_type {
+            TokenType::LeftParen => {
+                let keyword = tokens.peek_nth(position + 1).map(|token| token.token_type);
 
-                if tokens.consume(TokenType::RightParen).is_ok() {
-                    return Ok(Expr::FuncReference(id));
+                // Types outside of any clause belong to a module written without `(module ...)`
+                if keyword == Some(TokenType::Type)
+                    && matches!(
+                        clauses.last(),
+                        None | Some(Some(TokenType::Module | TokenType::Rec))
+                    )
+                {
+                    if let Some(TokenType::Identifier(id)) =
+                        tokens.peek_nth(position + 2).map(|token| token.token_type)
+                    {
+                        type_names.entry(id).or_insert(count);
+                    }
+
+                    count += 1;
                 }
 
-                let body = parse_multiple_expressions(tokens)?;
+                clauses.push(keyword);
+            }
+            TokenType::RightParen => {
+                clauses.pop();
+            }
+            _ => {}
+        }
+
+        position += 1;
+    }
+
+    type_names
+}
+
+fn parse_expression(tokens: Tokens) -> Result<Field, Error> {
+    tokens.consume(TokenType::LeftParen)?;
+
+    let token = next_token(tokens)?;
+    let mut exports = Vec::new();
+
+    let expr = match token.token_type {
+        TokenType::Module => Expr::Module(Module {
+            id: tokens.consume_optional_identifier(),
+            exprs: parse_module_fields(tokens)?,
+            cursor: token.cursor,
+        }),
+
+        TokenType::Type => Expr::Type(parse_type_definition(tokens, token.cursor)?),
+
+        TokenType::Rec => {
+            let mut types = Vec::new();
+
+            while tokens.peek_clause(TokenType::Type) {
+                tokens.consume(TokenType::LeftParen)?;
 
-                Ok(Expr::Func(FunctionDefinition { id: Some(id), body }))
+                let type_token = tokens.consume(TokenType::Type)?;
+
+                types.push(parse_type_definition(tokens, type_token.cursor)?);
+
+                tokens.consume(TokenType::RightParen)?;
             }
 
-            TokenType::Export => {
-                let (name, _) = tokens.consume_string()?;
+            Expr::Rec(RecursionGroup {
+                types,
+                cursor: token.cursor,
+            })
+        }
+
+        TokenType::Func => {
+            let id = tokens.consume_optional_identifier();
 
-                Ok(Expr::Export(Box::new(Export {
-                    name,
-                    expr: parse_expression(tokens)?,
-                })))
+            exports = parse_inline_exports(tokens)?;
+
+            if tokens.peek_clause(TokenType::Import) {
+                parse_inline_import(tokens, id, token.cursor, |tokens| {
+                    Ok(ImportDescription::Func(parse_type_use(tokens)?))
+                })?
+            } else {
+                let type_use = parse_type_use(tokens)?;
+                let locals = parse_locals(tokens)?;
+                let body = parse_instructions(tokens)?;
+
+                Expr::Func(FunctionDefinition {
+                    id,
+                    type_use,
+                    locals,
+                    body,
+                    cursor: token.cursor,
+                })
             }
+        }
 
-            TokenType::Param => {
-                let (id, _) = tokens.consume_identifier()?;
+        TokenType::Table => {
+            let id = tokens.consume_optional_identifier();
 
-                let parameter_type = match tokens.consume_type()?.token_type {
-                        TokenType::I32 => BuiltinType::I32,
-                        TokenType::F32 => BuiltinType::F32,
-                        TokenType::I64 => BuiltinType::I64,
-                        TokenType::F64 => BuiltinType::F64,
+            exports = parse_inline_exports(tokens)?;
 
-                        _ => unreachable!("Every token that consume_type returns should be convertible to a BuiltinType"),
-                };
+            if tokens.peek_clause(TokenType::Import) {
+                parse_inline_import(tokens, id, token.cursor, |tokens| {
+                    Ok(ImportDescription::Table(parse_table_type(tokens)?))
+                })?
+            } else {
+                let (table_type, inline_elements, init) = if peek_reference_type(tokens) {
+                    let element_type = parse_value_type(tokens)?;
 
-                tokens.consume(TokenType::RightParen)?;
+                    tokens.consume(TokenType::LeftParen)?;
+                    tokens.consume(TokenType::Elem)?;
+
+                    let items = if tokens.peek_type() == Some(TokenType::LeftParen) {
+                        ElementItems::Expressions(element_type, parse_element_expressions(tokens)?)
+                    } else if element_type == BuiltinType::FuncRef {
+                        ElementItems::Functions(parse_indices(tokens))
+                    } else {
+                        // Only funcref segments can list bare functions, others take
+                        // a `ref.func` of the table's type for each of them
+                        let mut expressions = Vec::new();
+
+                        loop {
+                            let cursor = tokens.peek_cursor();
+
+                            let Some(function) = tokens.consume_optional_index() else {
+                                break;
+                            };
+
+                            expressions.push(vec![Instruction {
+                                kind: InstructionKind::RefFunc(function),
+                                cursor,
+                            }]);
+                        }
+
+                        ElementItems::Expressions(element_type, expressions)
+                    };
+
+                    tokens.consume(TokenType::RightParen)?;
 
-                Ok(Expr::Param(Box::new(Param { id, parameter_type })))
+                    // The table is sized to exactly fit its inline elements
+                    let size = match &items {
+                        ElementItems::Functions(functions) => functions.len(),
+                        ElementItems::Expressions(_, expressions) => expressions.len(),
+                    } as u64;
+
+                    let limits = Limits {
+                        min: size,
+                        max: Some(size),
+                        shared: false,
+                        index_type: BuiltinType::I32,
+                    };
+
+                    (
+                        TableType {
+                            limits,
+                            element_type,
+                        },
+                        Some(items),
+                        None,
+                    )
+                } else {
+                    let table_type = parse_table_type(tokens)?;
+                    let init = parse_instructions(tokens)?;
+
+                    (table_type, None, (!init.is_empty()).then_some(init))
+                };
+
+                Expr::Table(TableDefinition {
+                    id,
+                    table_type,
+                    inline_elements,
+                    init,
+                    cursor: token.cursor,
+                })
             }
+        }
+
+        TokenType::Memory => {
+            let id = tokens.consume_optional_identifier();
+
+            exports = parse_inline_exports(tokens)?;
 
-            TokenType::Result => {
-                let parameter_type = match tokens.consume_type()?.token_type {
-                    TokenType::I32 => BuiltinType::I32,
-                    TokenType::F32 => BuiltinType::F32,
-                    TokenType::I64 => BuiltinType::I64,
-                    TokenType::F64 => BuiltinType::F64,
+            if tokens.peek_clause(TokenType::Import) {
+                parse_inline_import(tokens, id, token.cursor, |tokens| {
+                    let index_type = parse_index_type(tokens);
 
-                    _ => unreachable!("Every token that consume_type returns should be convertible to a BuiltinType"),
+                    Ok(ImportDescription::Memory(parse_memory_type(
+                        tokens, index_type,
+                    )?))
+                })?
+            } else {
+                let index_type = parse_index_type(tokens);
+
+                let (limits, inline_data) = if tokens.peek_clause(TokenType::Data) {
+                    tokens.consume(TokenType::LeftParen)?;
+                    tokens.consume(TokenType::Data)?;
+
+                    let data = parse_data_strings(tokens);
+
+                    tokens.consume(TokenType::RightParen)?;
+
+                    // The memory is sized to exactly fit its inline data
+                    let pages = (data.len() as u64).div_ceil(PAGE_SIZE);
+
+                    (
+                        Limits {
+                            min: pages,
+                            max: Some(pages),
+                            shared: false,
+                            index_type,
+                        },
+                        Some(data),
+                    )
+                } else {
+                    (parse_memory_type(tokens, index_type)?, None)
                 };
 
-                tokens.consume(TokenType::RightParen)?;
+                Expr::Memory(MemoryDefinition {
+                    id,
+                    limits,
+                    inline_data,
+                    cursor: token.cursor,
+                })
+            }
+        }
+
+        TokenType::Global => {
+            let id = tokens.consume_optional_identifier();
 
-                Ok(Expr::Result(parameter_type))
+            exports = parse_inline_exports(tokens)?;
+
+            if tokens.peek_clause(TokenType::Import) {
+                parse_inline_import(tokens, id, token.cursor, |tokens| {
+                    Ok(ImportDescription::Global(parse_global_type(tokens)?))
+                })?
+            } else {
+                Expr::Global(GlobalDefinition {
+                    id,
+                    global_type: parse_global_type(tokens)?,
+                    init: parse_instructions(tokens)?,
+                    cursor: token.cursor,
+                })
             }
+        }
 
-            TokenType::Local => {
-                tokens.consume(TokenType::Dot)?;
+        TokenType::Tag => {
+            let id = tokens.consume_optional_identifier();
 
-                let method = tokens.next().unwrap();
+            exports = parse_inline_exports(tokens)?;
 
-                let (id, _) = tokens.consume_identifier()?;
+            if tokens.peek_clause(TokenType::Import) {
+                parse_inline_import(tokens, id, token.cursor, |tokens| {
+                    Ok(ImportDescription::Tag(parse_type_use(tokens)?))
+                })?
+            } else {
+                Expr::Tag(TagDefinition {
+                    id,
+                    type_use: parse_type_use(tokens)?,
+                    cursor: token.cursor,
+                })
+            }
+        }
 
-                tokens.consume(TokenType::RightParen)?;
+        TokenType::Elem => {
+            let id = tokens.consume_optional_identifier();
+
+            let mode = if tokens.peek_type() == Some(TokenType::Declare) {
+                tokens.next();
+
+                ElementMode::Declarative
+            } else if tokens.peek_type() == Some(TokenType::LeftParen)
+                && !peek_reference_type(tokens)
+            {
+                // Active segments name their table and offset before the elements, while
+                // passive ones may start with a typed reference like `(ref $t)`
+                let table = if tokens.peek_clause(TokenType::Table) {
+                    tokens.consume(TokenType::LeftParen)?;
+                    tokens.consume(TokenType::Table)?;
 
-                match method.token_type {
-                    TokenType::Get => Ok(Expr::LocalGet(id)),
+                    let table = tokens.consume_index()?;
 
-                    _ => Err(Box::new(ExpectedMethodError {
-                        cursor: method.cursor,
-                        methods: vec!["get"],
-                    })),
+                    tokens.consume(TokenType::RightParen)?;
+
+                    table
+                } else {
+                    Index::Numeric(0)
+                };
+
+                ElementMode::Active {
+                    table,
+                    offset: parse_offset(tokens)?,
                 }
-            }
+            } else {
+                ElementMode::Passive
+            };
+
+            Expr::Elem(ElementDefinition {
+                id,
+                mode,
+                items: parse_element_list(tokens)?,
+                cursor: token.cursor,
+            })
+        }
+
+        TokenType::Data => {
+            let id = tokens.consume_optional_identifier();
+
+            // Active segments name their memory and offset before the strings
+            let mode = if tokens.peek_type() == Some(TokenType::LeftParen) {
+                let memory = if tokens.peek_clause(TokenType::Memory) {
+                    tokens.consume(TokenType::LeftParen)?;
+                    tokens.consume(TokenType::Memory)?;
 
-            TokenType::I32 => {
-                tokens.consume(TokenType::Dot)?;
+                    let memory = tokens.consume_index()?;
 
-                let method = tokens.next().unwrap();
+                    tokens.consume(TokenType::RightParen)?;
 
-                match method.token_type {
-                    TokenType::Add => Ok(Expr::IntOp(BuiltinType::I32, IntOp::Add)),
-                    _ => Err(Box::new(ExpectedMethodError {
-                        cursor: method.cursor,
-                        methods: vec!["add"],
-                    })),
+                    memory
+                } else {
+                    Index::Numeric(0)
+                };
+
+                DataMode::Active {
+                    memory,
+                    offset: parse_offset(tokens)?,
                 }
-            }
+            } else {
+                DataMode::Passive
+            };
 
-            _ => todo!("parsing of {:?}", token.token_type),
-        };
+            Expr::Data(DataDefinition {
+                id,
+                mode,
+                bytes: parse_data_strings(tokens),
+                cursor: token.cursor,
+            })
+        }
+
+        TokenType::Import => {
+            let (module, _) = tokens.consume_string()?;
+            let (name, _) = tokens.consume_string()?;
+
+            tokens.consume(TokenType::LeftParen)?;
+
+            let kind_token = next_token(tokens)?;
+            let id = tokens.consume_optional_identifier();
+
+            let description = match kind_token.token_type {
+                TokenType::Func => ImportDescription::Func(parse_type_use(tokens)?),
+                TokenType::Table => ImportDescription::Table(parse_table_type(tokens)?),
+                TokenType::Memory => {
+                    let index_type = parse_index_type(tokens);
+
+                    ImportDescription::Memory(parse_memory_type(tokens, index_type)?)
+                }
+                TokenType::Global => ImportDescription::Global(parse_global_type(tokens)?),
+                TokenType::Tag => ImportDescription::Tag(parse_type_use(tokens)?),
+
+                _ => {
+                    return Err(Error::from(ExpectedMethodError {
+                        methods: vec!["func", "table", "memory", "global", "tag"],
+                        cursor: kind_token.cursor,
+                    }))
+                }
+            };
+
+            tokens.consume(TokenType::RightParen)?;
+
+            Expr::Import(Box::new(Import {
+                module,
+                name,
+                id,
+                description,
+                cursor: token.cursor,
+            }))
+        }
+
+        TokenType::Export => {
+            let (name, _) = tokens.consume_string()?;
+
+            tokens.consume(TokenType::LeftParen)?;
+
+            let kind_token = next_token(tokens)?;
+
+            let kind = match kind_token.token_type {
+                TokenType::Func => ExternalKind::Func,
+                TokenType::Table => ExternalKind::Table,
+                TokenType::Memory => ExternalKind::Memory,
+                TokenType::Global => ExternalKind::Global,
+                TokenType::Tag => ExternalKind::Tag,
+
+                _ => {
+                    return Err(Error::from(ExpectedMethodError {
+                        methods: vec!["func", "table", "memory", "global", "tag"],
+                        cursor: kind_token.cursor,
+                    }))
+                }
+            };
+
+            let index = tokens.consume_index()?;
+
+            tokens.consume(TokenType::RightParen)?;
+
+            Expr::Export(Box::new(Export {
+                name,
+                kind,
+                index,
+                cursor: token.cursor,
+            }))
+        }
+
+        TokenType::Annotation(name) if name == "custom" => {
+            let (name, _) = tokens.consume_string()?;
+            let placement = parse_custom_placement(tokens)?;
+
+            Expr::Custom(CustomDefinition {
+                name,
+                placement,
+                bytes: parse_data_strings(tokens),
+                cursor: token.cursor,
+            })
+        }
+
+        TokenType::Start => Expr::Start(StartDefinition {
+            function: tokens.consume_index()?,
+            cursor: token.cursor,
+        }),
+
+        token_type => {
+            return Err(Error::from(UnexpectedTokenError {
+                token_type,
+                cursor: token.cursor,
+            }))
+        }
+    };
+
+    tokens.consume(TokenType::RightParen)?;
+
+    Ok(Field { expr, exports })
+}
+
+/// Parses where a custom section goes, `(before first)`, `(after code)` and so on,
+/// custom sections without one are placed after all other sections
+fn parse_custom_placement(tokens: Tokens) -> Result<CustomPlacement, Error> {
+    if tokens.peek_type() != Some(TokenType::LeftParen) {
+        return Ok(CustomPlacement::Last);
     }
+
+    tokens.consume(TokenType::LeftParen)?;
+
+    let side_token = next_token(tokens)?;
+
+    let side = match side_token.token_type {
+        TokenType::Reserved(keyword) if keyword == "before" || keyword == "after" => keyword,
+
+        _ => {
+            return Err(Error::from(ExpectedMethodError {
+                methods: vec!["before", "after"],
+                cursor: side_token.cursor,
+            }))
+        }
+    };
+
+    let section_token = next_token(tokens)?;
+
+    let placement = match (side.as_str(), &section_token.token_type) {
+        ("before", TokenType::Reserved(keyword)) if keyword == "first" => CustomPlacement::First,
+        ("after", TokenType::Reserved(keyword)) if keyword == "last" => CustomPlacement::Last,
+
+        (side, token_type) => {
+            let section = match token_type {
+                TokenType::Type => SectionId::Type,
+                TokenType::Import => SectionId::Import,
+                TokenType::Func => SectionId::Function,
+                TokenType::Table => SectionId::Table,
+                TokenType::Memory => SectionId::Memory,
+                TokenType::Tag => SectionId::Tag,
+                TokenType::Global => SectionId::Global,
+                TokenType::Export => SectionId::Export,
+                TokenType::Start => SectionId::Start,
+                TokenType::Elem => SectionId::Element,
+                TokenType::Data => SectionId::Data,
+                TokenType::Reserved(keyword) if keyword == "datacount" => SectionId::DataCount,
+                TokenType::Reserved(keyword) if keyword == "code" => SectionId::Code,
+
+                _ => {
+                    return Err(Error::from(ExpectedMethodError {
+                        methods: vec![
+                            if side == "before" { "first" } else { "last" },
+                            "type",
+                            "import",
+                            "func",
+                            "table",
+                            "memory",
+                            "tag",
+                            "global",
+                            "export",
+                            "start",
+                            "elem",
+                            "datacount",
+                            "code",
+                            "data",
+                        ],
+                        cursor: section_token.cursor,
+                    }))
+                }
+            };
+
+            if side == "before" {
+                CustomPlacement::Before(section)
+            } else {
+                CustomPlacement::After(section)
+            }
+        }
+    };
+
+    tokens.consume(TokenType::RightParen)?;
+
+    Ok(placement)
 }
 
-fn parse_multiple_expressions(tokens: Tokens) -> Result<Vec<Expr>, Box<dyn ErrorDisplay>> {
+/// Parses the fields of a module, desugaring inline exports into export fields
+fn parse_module_fields(tokens: Tokens) -> Result<Vec<Expr>, Error> {
     let mut exprs = Vec::new();
+    // Imports have to precede definitions, so counting definitions in order
+    // yields the index of anonymous ones
+    let mut counts: HashMap<ExternalKind, u32> = HashMap::new();
 
     while tokens
         .peek()
         .is_some_and(|token| token.token_type != TokenType::RightParen)
     {
-        exprs.push(parse_expression(tokens)?);
+        let Field { expr, exports } = parse_expression(tokens)?;
+
+        let Some((kind, id, cursor)) = expr.definition() else {
+            exprs.push(expr);
+            continue;
+        };
+
+        let count = counts.entry(kind).or_default();
+        let index = match id {
+            Some(id) => Index::Identifier(id.clone()),
+            None => Index::Numeric(*count),
+        };
+
+        *count += 1;
+
+        exprs.push(expr);
+        exprs.extend(exports.into_iter().map(|name| {
+            Expr::Export(Box::new(Export {
+                name,
+                kind,
+                index: index.clone(),
+                cursor,
+            }))
+        }));
     }
 
     Ok(exprs)
 }
+
+fn next_token(tokens: Tokens) -> Result<Token, Error> {
+    let cursor = tokens.peek_cursor();
+
+    tokens
+        .next()
+        .ok_or_else(|| Error::from(UnexpectedEndError { cursor }) as Error)
+}
+
+/// Parses `(export "name")*` inside a definition
+fn parse_inline_exports(tokens: Tokens) -> Result<Vec<String>, Error> {
+    let mut exports = Vec::new();
+
+    while tokens.peek_clause(TokenType::Export) {
+        tokens.consume(TokenType::LeftParen)?;
+        tokens.consume(TokenType::Export)?;
+
+        exports.push(tokens.consume_string()?.0);
+
+        tokens.consume(TokenType::RightParen)?;
+    }
+
+    Ok(exports)
+}
+
+/// Parses the rest of a definition abbreviated to `(import "module" "name") description`
+fn parse_inline_import(
+    tokens: Tokens,
+    id: Option<Identifier>,
+    cursor: PageCursor,
+    parse_description: fn(Tokens) -> Result<ImportDescription, Error>,
+) -> Result<Expr, Error> {
+    tokens.consume(TokenType::LeftParen)?;
+    tokens.consume(TokenType::Import)?;
+
+    let (module, _) = tokens.consume_string()?;
+    let (name, _) = tokens.consume_string()?;
+
+    tokens.consume(TokenType::RightParen)?;
+
+    Ok(Expr::Import(Box::new(Import {
+        module,
+        name,
+        id,
+        description: parse_description(tokens)?,
+        cursor,
+    })))
+}
+
+/// Parses either `valtype` or `(mut valtype)`
+fn parse_global_type(tokens: Tokens) -> Result<GlobalType, Error> {
+    if !tokens.peek_clause(TokenType::Mut) {
+        return Ok(GlobalType {
+            value_type: parse_value_type(tokens)?,
+            mutable: false,
+        });
+    }
+
+    tokens.consume(TokenType::LeftParen)?;
+    tokens.consume(TokenType::Mut)?;
+
+    let value_type = parse_value_type(tokens)?;
+
+    tokens.consume(TokenType::RightParen)?;
+
+    Ok(GlobalType {
+        value_type,
+        mutable: true,
+    })
+}
+
+/// Parses `(type $t)? (param ...)* (result ...)*`
+fn parse_type_use(tokens: Tokens) -> Result<TypeUse, Error> {
+    let index = if tokens.peek_clause(TokenType::Type) {
+        tokens.consume(TokenType::LeftParen)?;
+        tokens.consume(TokenType::Type)?;
+
+        let index = tokens.consume_index()?;
+
+        tokens.consume(TokenType::RightParen)?;
+
+        Some(index)
+    } else {
+        None
+    };
+
+    Ok(TypeUse {
+        index,
+        signature: parse_signature(tokens)?,
+    })
+}
+
+fn parse_signature(tokens: Tokens) -> Result<FunctionSignature, Error> {
+    let mut signature = FunctionSignature::default();
+
+    while tokens.peek_clause(TokenType::Param) {
+        tokens.consume(TokenType::LeftParen)?;
+        tokens.consume(TokenType::Param)?;
+
+        // Named params declare one type, anonymous ones can list any number of them
+        if let Some(id) = tokens.consume_optional_identifier() {
+            signature.params.push(Param {
+                id: Some(id),
+                parameter_type: parse_value_type(tokens)?,
+            });
+        } else {
+            for parameter_type in parse_types(tokens)? {
+                signature.params.push(Param {
+                    id: None,
+                    parameter_type,
+                });
+            }
+        }
+
+        tokens.consume(TokenType::RightParen)?;
+    }
+
+    while tokens.peek_clause(TokenType::Result) {
+        tokens.consume(TokenType::LeftParen)?;
+        tokens.consume(TokenType::Result)?;
+
+        signature.results.extend(parse_types(tokens)?);
+
+        tokens.consume(TokenType::RightParen)?;
+    }
+
+    Ok(signature)
+}
+
+fn parse_locals(tokens: Tokens) -> Result<Vec<Local>, Error> {
+    let mut locals = Vec::new();
+
+    while tokens.peek_clause(TokenType::Local) {
+        tokens.consume(TokenType::LeftParen)?;
+        tokens.consume(TokenType::Local)?;
+
+        if let Some(id) = tokens.consume_optional_identifier() {
+            locals.push(Local {
+                id: Some(id),
+                local_type: parse_value_type(tokens)?,
+            });
+        } else {
+            for local_type in parse_types(tokens)? {
+                locals.push(Local {
+                    id: None,
+                    local_type,
+                });
+            }
+        }
+
+        tokens.consume(TokenType::RightParen)?;
+    }
+
+    Ok(locals)
+}
+
+/// Parses the rest of `(type $t? subtype)`, where the subtype is either
+/// `(sub final? $supertype* comptype)` or just the composite type
+fn parse_type_definition(tokens: Tokens, cursor: PageCursor) -> Result<TypeDefinition, Error> {
+    let id = tokens.consume_optional_identifier();
+
+    if !tokens.peek_clause(TokenType::Sub) {
+        return Ok(TypeDefinition {
+            id,
+            composite: parse_composite(tokens)?,
+            is_final: true,
+            supertypes: Vec::new(),
+            cursor,
+        });
+    }
+
+    tokens.consume(TokenType::LeftParen)?;
+    tokens.consume(TokenType::Sub)?;
+
+    let is_final = tokens.peek_type() == Some(TokenType::Final);
+
+    if is_final {
+        tokens.next();
+    }
+
+    let supertypes = parse_indices(tokens);
+    let composite = parse_composite(tokens)?;
+
+    tokens.consume(TokenType::RightParen)?;
+
+    Ok(TypeDefinition {
+        id,
+        composite,
+        is_final,
+        supertypes,
+        cursor,
+    })
+}
+
+/// Parses `(func ...)`, `(struct (field ...)*)` or `(array fieldtype)`
+fn parse_composite(tokens: Tokens) -> Result<CompositeDefinition, Error> {
+    tokens.consume(TokenType::LeftParen)?;
+
+    let cursor = tokens.peek_cursor();
+
+    let composite = match next_token(tokens)?.token_type {
+        TokenType::Func => CompositeDefinition::Func(parse_signature(tokens)?),
+        TokenType::Struct => CompositeDefinition::Struct(parse_fields(tokens)?),
+        TokenType::Array => CompositeDefinition::Array(parse_field(tokens, None)?),
+        _ => {
+            return Err(Error::from(ExpectedMethodError {
+                methods: vec!["func", "struct", "array"],
+                cursor,
+            }))
+        }
+    };
+
+    tokens.consume(TokenType::RightParen)?;
+
+    Ok(composite)
+}
+
+fn parse_fields(tokens: Tokens) -> Result<Vec<FieldDefinition>, Error> {
+    let mut fields = Vec::new();
+
+    while tokens.peek_clause(TokenType::Field) {
+        tokens.consume(TokenType::LeftParen)?;
+        tokens.consume(TokenType::Field)?;
+
+        // Like params, named fields declare one type and anonymous ones any number of them
+        if let Some(id) = tokens.consume_optional_identifier() {
+            fields.push(parse_field(tokens, Some(id))?);
+        } else {
+            while tokens.peek_type() != Some(TokenType::RightParen) {
+                fields.push(parse_field(tokens, None)?);
+            }
+        }
+
+        tokens.consume(TokenType::RightParen)?;
+    }
+
+    Ok(fields)
+}
+
+/// Parses either `storagetype` or `(mut storagetype)`, where the storage type
+/// is a value type or one of the packed types `i8` and `i16`
+fn parse_field(tokens: Tokens, id: Option<Identifier>) -> Result<FieldDefinition, Error> {
+    let mutable = tokens.peek_clause(TokenType::Mut);
+
+    if mutable {
+        tokens.consume(TokenType::LeftParen)?;
+        tokens.consume(TokenType::Mut)?;
+    }
+
+    let storage_type = match tokens.peek_type() {
+        Some(TokenType::I8) => {
+            tokens.next();
+
+            StorageType::I8
+        }
+        Some(TokenType::I16) => {
+            tokens.next();
+
+            StorageType::I16
+        }
+        _ => StorageType::Value(parse_value_type(tokens)?),
+    };
+
+    if mutable {
+        tokens.consume(TokenType::RightParen)?;
+    }
+
+    Ok(FieldDefinition {
+        id,
+        storage_type,
+        mutable,
+    })
+}
+
+/// Parses a value type, including reference types written as `(ref null? heaptype)`
+fn parse_value_type(tokens: Tokens) -> Result<BuiltinType, Error> {
+    if !tokens.peek_clause(TokenType::Ref) {
+        return Ok(tokens.consume_type()?);
+    }
+
+    tokens.consume(TokenType::LeftParen)?;
+    tokens.consume(TokenType::Ref)?;
+
+    let nullable = tokens.peek_type() == Some(TokenType::Null);
+
+    if nullable {
+        tokens.next();
+    }
+
+    let heap_type = parse_heap_type(tokens)?;
+
+    tokens.consume(TokenType::RightParen)?;
+
+    Ok(BuiltinType::reference(nullable, heap_type))
+}
+
+/// Whether a reference type comes next, either as a shorthand or as `(ref ...)`
+fn peek_reference_type(tokens: Tokens) -> bool {
+    tokens.peek_clause(TokenType::Ref)
+        || matches!(
+            tokens.peek_type(),
+            Some(
+                TokenType::FuncRef
+                    | TokenType::ExternRef
+                    | TokenType::ExnRef
+                    | TokenType::AbstractRef(_)
+            )
+        )
+}
+
+/// Parses an abstract heap type like `func` or `any`, or the index of a type of the module
+fn parse_heap_type(tokens: Tokens) -> Result<HeapType, Error> {
+    let cursor = tokens.peek_cursor();
+
+    let heap_type = match next_token(tokens)?.token_type {
+        TokenType::Func => HeapType::Func,
+        TokenType::Extern => HeapType::Extern,
+        TokenType::Exn => HeapType::Exn,
+        TokenType::Struct => HeapType::Struct,
+        TokenType::Array => HeapType::Array,
+        TokenType::HeapType(heap_type) => heap_type,
+        TokenType::IntegerLiteral(index) if (0..=u32::MAX as i128).contains(&index) => {
+            HeapType::Concrete(index as u32)
+        }
+        TokenType::Identifier(id) => match tokens.type_index(&id) {
+            Some(index) => HeapType::Concrete(index),
+            None => return Err(Error::from(UnknownTypeError { id, cursor })),
+        },
+        _ => return Err(Error::from(ExpectedTypeError { cursor })),
+    };
+
+    Ok(heap_type)
+}
+
+/// Parses the types of a clause up to its closing paren
+fn parse_types(tokens: Tokens) -> Result<Vec<BuiltinType>, Error> {
+    let mut types = Vec::new();
+
+    while tokens.peek_type() != Some(TokenType::RightParen) {
+        types.push(parse_value_type(tokens)?);
+    }
+
+    Ok(types)
+}
+
+fn parse_limits(tokens: Tokens) -> Result<Limits, Error> {
+    let (min, cursor) = tokens.consume_integer()?;
+
+    let max = match tokens.peek_type() {
+        Some(TokenType::IntegerLiteral(_)) => Some(tokens.consume_integer()?),
+        _ => None,
+    };
+
+    let to_u64 =
+        |(value, cursor)| u64::try_from(value).map_err(|_| ConstantOutOfRangeError { cursor });
+
+    Ok(Limits {
+        min: to_u64((min, cursor))?,
+        max: max.map(to_u64).transpose()?,
+        shared: false,
+        index_type: BuiltinType::I32,
+    })
+}
+
+/// Memories are addressed with `i32` unless they are declared with `i64`
+fn parse_index_type(tokens: Tokens) -> BuiltinType {
+    match tokens.peek_type() {
+        Some(TokenType::I64) => {
+            tokens.next();
+
+            BuiltinType::I64
+        }
+        Some(TokenType::I32) => {
+            tokens.next();
+
+            BuiltinType::I32
+        }
+        _ => BuiltinType::I32,
+    }
+}
+
+/// Parses the limits of a memory, which may be followed by `shared`
+fn parse_memory_type(tokens: Tokens, index_type: BuiltinType) -> Result<Limits, Error> {
+    let mut limits = parse_limits(tokens)?;
+
+    limits.index_type = index_type;
+
+    if tokens.peek_type() == Some(TokenType::Shared) {
+        tokens.next();
+
+        limits.shared = true;
+    }
+
+    Ok(limits)
+}
+
+fn parse_table_type(tokens: Tokens) -> Result<TableType, Error> {
+    let limits = parse_limits(tokens)?;
+    let cursor = tokens.peek_cursor();
+    let element_type = parse_value_type(tokens)?;
+
+    if !element_type.is_reference() {
+        return Err(Error::from(ExpectedTypeError { cursor }));
+    }
+
+    Ok(TableType {
+        limits,
+        element_type,
+    })
+}
+
+fn parse_indices(tokens: Tokens) -> Vec<Index> {
+    let mut indices = Vec::new();
+
+    while let Some(index) = tokens.consume_optional_index() {
+        indices.push(index);
+    }
+
+    indices
+}
+
+/// Parses either `func index*`, `reftype expr*` or just `index*`
+fn parse_element_list(tokens: Tokens) -> Result<ElementItems, Error> {
+    if peek_reference_type(tokens) {
+        let element_type = parse_value_type(tokens)?;
+
+        return Ok(ElementItems::Expressions(
+            element_type,
+            parse_element_expressions(tokens)?,
+        ));
+    }
+
+    match tokens.peek_type() {
+        Some(TokenType::Func) => {
+            tokens.next();
+
+            Ok(ElementItems::Functions(parse_indices(tokens)))
+        }
+        _ => Ok(ElementItems::Functions(parse_indices(tokens))),
+    }
+}
+
+/// Parses element expressions, each either `(item instr*)` or a single folded instruction
+fn parse_element_expressions(tokens: Tokens) -> Result<Vec<Vec<Instruction>>, Error> {
+    let mut expressions = Vec::new();
+
+    while tokens.peek_type() == Some(TokenType::LeftParen) {
+        let mut expression = Vec::new();
+
+        if tokens.peek_clause(TokenType::Item) {
+            tokens.consume(TokenType::LeftParen)?;
+            tokens.consume(TokenType::Item)?;
+
+            expression = parse_instructions(tokens)?;
+
+            tokens.consume(TokenType::RightParen)?;
+        } else {
+            parse_folded_instruction(tokens, &mut expression)?;
+        }
+
+        expressions.push(expression);
+    }
+
+    Ok(expressions)
+}
+
+/// Concatenates the bytes of consecutive string literals
+fn parse_data_strings(tokens: Tokens) -> Vec<u8> {
+    let mut data = Vec::new();
+
+    while let Ok((bytes, _)) = tokens.consume_bytes() {
+        data.extend(bytes);
+    }
+
+    data
+}
+
+/// Parses `(offset instr*)`, or the abbreviation of it as a single folded instruction
+fn parse_offset(tokens: Tokens) -> Result<Vec<Instruction>, Error> {
+    let mut offset = Vec::new();
+
+    if tokens.peek_clause(TokenType::Offset) {
+        tokens.consume(TokenType::LeftParen)?;
+        tokens.consume(TokenType::Offset)?;
+
+        offset = parse_instructions(tokens)?;
+
+        tokens.consume(TokenType::RightParen)?;
+    } else {
+        parse_folded_instruction(tokens, &mut offset)?;
+    }
+
+    Ok(offset)
+}
+
+fn parse_memarg(tokens: Tokens) -> MemArg {
+    let mut memarg = MemArg::default();
+
+    if let Some(TokenType::MemArgOffset(offset)) = tokens.peek_type() {
+        tokens.next();
+        memarg.offset = offset;
+    }
+
+    if let Some(TokenType::MemArgAlign(align)) = tokens.peek_type() {
+        tokens.next();
+        memarg.align = Some(align);
+    }
+
+    memarg
+}
+
+/// Parses the destination and source of a copy, which are either both given or both left out
+fn parse_index_pair(
+    tokens: Tokens,
+    mnemonic: Mnemonic,
+    feature: &'static str,
+) -> Result<(Index, Index), Error> {
+    let cursor = tokens.peek_cursor();
+
+    match tokens.consume_optional_index() {
+        Some(destination) => {
+            require_explicit_index(tokens, mnemonic, feature, cursor)?;
+
+            Ok((destination, tokens.consume_index()?))
+        }
+        None => Ok((Index::Numeric(0), Index::Numeric(0))),
+    }
+}
+
+/// The memory or table an instruction works on, which is left out for the first one
+/// and can only be written out with `feature` enabled
+fn parse_default_index(
+    tokens: Tokens,
+    mnemonic: Mnemonic,
+    feature: &'static str,
+) -> Result<Index, Error> {
+    let cursor = tokens.peek_cursor();
+
+    match tokens.consume_optional_index() {
+        Some(index) => {
+            require_explicit_index(tokens, mnemonic, feature, cursor)?;
+
+            Ok(index)
+        }
+        None => Ok(Index::Numeric(0)),
+    }
+}
+
+fn require_explicit_index(
+    tokens: Tokens,
+    mnemonic: Mnemonic,
+    feature: &'static str,
+    cursor: PageCursor,
+) -> Result<(), DisabledFeatureError> {
+    let construct = format!("An explicit index on `{}`", mnemonic);
+
+    tokens.require_feature(feature, &construct, cursor)
+}
+
+/// Block types are type uses, abbreviated to at most one result type where possible
+fn parse_block_type(tokens: Tokens) -> Result<BlockType, Error> {
+    let type_use = parse_type_use(tokens)?;
+
+    if type_use.index.is_some() || !type_use.signature.params.is_empty() {
+        return Ok(BlockType::TypeUse(type_use));
+    }
+
+    match type_use.signature.results.as_slice() {
+        [] => Ok(BlockType::Empty),
+        [result] => Ok(BlockType::Value(*result)),
+        _ => Ok(BlockType::TypeUse(type_use)),
+    }
+}
+
+/// Parses the `(catch $tag $label)`, `(catch_ref ...)`, `(catch_all $label)`
+/// and `(catch_all_ref ...)` clauses of a `try_table`
+fn parse_catch_clauses(tokens: Tokens) -> Result<Vec<CatchClause>, Error> {
+    let mut clauses = Vec::new();
+
+    while tokens.peek_type() == Some(TokenType::LeftParen) {
+        let Some(TokenType::Catch(kind)) = tokens.peek_nth(1).map(|token| token.token_type) else {
+            break;
+        };
+
+        tokens.consume(TokenType::LeftParen)?;
+        tokens.consume(TokenType::Catch(kind))?;
+
+        let tag = match kind {
+            CatchKind::Catch | CatchKind::CatchRef => Some(tokens.consume_index()?),
+            CatchKind::CatchAll | CatchKind::CatchAllRef => None,
+        };
+
+        clauses.push(CatchClause {
+            kind,
+            tag,
+            label: tokens.consume_index()?,
+        });
+
+        tokens.consume(TokenType::RightParen)?;
+    }
+
+    Ok(clauses)
+}
+
+/// Parses instructions until the closing paren of the enclosing expression,
+/// flattening folded instructions into their plain form
+fn parse_instructions(tokens: Tokens) -> Result<Vec<Instruction>, Error> {
+    let mut instructions = Vec::new();
+
+    while tokens
+        .peek()
+        .is_some_and(|token| token.token_type != TokenType::RightParen)
+    {
+        parse_instruction(tokens, &mut instructions)?;
+    }
+
+    Ok(instructions)
+}
+
+fn parse_instruction(tokens: Tokens, instructions: &mut Vec<Instruction>) -> Result<(), Error> {
+    if tokens.peek_type() == Some(TokenType::LeftParen) {
+        return parse_folded_instruction(tokens, instructions);
+    }
+
+    let token = next_token(tokens)?;
+
+    let kind = match token.token_type {
+        TokenType::Block => InstructionKind::Block(
+            tokens.consume_optional_identifier(),
+            parse_block_type(tokens)?,
+        ),
+        TokenType::Loop => InstructionKind::Loop(
+            tokens.consume_optional_identifier(),
+            parse_block_type(tokens)?,
+        ),
+        TokenType::If => InstructionKind::If(
+            tokens.consume_optional_identifier(),
+            parse_block_type(tokens)?,
+        ),
+        TokenType::Else => InstructionKind::Else(tokens.consume_optional_identifier()),
+        TokenType::End => InstructionKind::End(tokens.consume_optional_identifier()),
+        TokenType::TryTable => InstructionKind::TryTable(
+            tokens.consume_optional_identifier(),
+            parse_block_type(tokens)?,
+            parse_catch_clauses(tokens)?,
+        ),
+
+        TokenType::Instruction(mnemonic) => parse_immediates(tokens, mnemonic)?,
+
+        token_type => {
+            return Err(Error::from(UnexpectedTokenError {
+                token_type,
+                cursor: token.cursor,
+            }))
+        }
+    };
+
+    instructions.push(Instruction {
+        kind,
+        cursor: token.cursor,
+    });
+
+    Ok(())
+}
+
+/// Folded instructions are written as `(op operand*)` and evaluate their operands first
+fn parse_folded_instruction(
+    tokens: Tokens,
+    instructions: &mut Vec<Instruction>,
+) -> Result<(), Error> {
+    tokens.consume(TokenType::LeftParen)?;
+
+    let token = next_token(tokens)?;
+    let cursor = token.cursor;
+
+    let end = |instructions: &mut Vec<Instruction>| {
+        instructions.push(Instruction {
+            kind: InstructionKind::End(None),
+            cursor,
+        })
+    };
+
+    match token.token_type {
+        TokenType::Block | TokenType::Loop => {
+            let label = tokens.consume_optional_identifier();
+            let block_type = parse_block_type(tokens)?;
+
+            let kind = if token.token_type == TokenType::Block {
+                InstructionKind::Block(label, block_type)
+            } else {
+                InstructionKind::Loop(label, block_type)
+            };
+
+            instructions.push(Instruction { kind, cursor });
+            instructions.extend(parse_instructions(tokens)?);
+            end(instructions);
+        }
+
+        TokenType::TryTable => {
+            let kind = InstructionKind::TryTable(
+                tokens.consume_optional_identifier(),
+                parse_block_type(tokens)?,
+                parse_catch_clauses(tokens)?,
+            );
+
+            instructions.push(Instruction { kind, cursor });
+            instructions.extend(parse_instructions(tokens)?);
+            end(instructions);
+        }
+
+        TokenType::If => {
+            let label = tokens.consume_optional_identifier();
+            let block_type = parse_block_type(tokens)?;
+
+            while tokens.peek_type() == Some(TokenType::LeftParen)
+                && !tokens.peek_clause(TokenType::Then)
+            {
+                parse_folded_instruction(tokens, instructions)?;
+            }
+
+            instructions.push(Instruction {
+                kind: InstructionKind::If(label, block_type),
+                cursor,
+            });
+
+            tokens.consume(TokenType::LeftParen)?;
+            tokens.consume(TokenType::Then)?;
+            instructions.extend(parse_instructions(tokens)?);
+            tokens.consume(TokenType::RightParen)?;
+
+            if tokens.peek_clause(TokenType::Else) {
+                tokens.consume(TokenType::LeftParen)?;
+                let else_token = tokens.consume(TokenType::Else)?;
+
+                instructions.push(Instruction {
+                    kind: InstructionKind::Else(None),
+                    cursor: else_token.cursor,
+                });
+                instructions.extend(parse_instructions(tokens)?);
+
+                tokens.consume(TokenType::RightParen)?;
+            }
+
+            end(instructions);
+        }
+
+        TokenType::Instruction(mnemonic) => {
+            let kind = parse_immediates(tokens, mnemonic)?;
+
+            instructions.extend(parse_instructions(tokens)?);
+            instructions.push(Instruction { kind, cursor });
+        }
+
+        token_type => return Err(Error::from(UnexpectedTokenError { token_type, cursor })),
+    }
+
+    tokens.consume(TokenType::RightParen)?;
+
+    Ok(())
+}
+
+fn parse_immediates(tokens: Tokens, mnemonic: Mnemonic) -> Result<InstructionKind, Error> {
+    let kind = match mnemonic {
+        Mnemonic::Br => InstructionKind::Br(tokens.consume_index()?),
+        Mnemonic::BrIf => InstructionKind::BrIf(tokens.consume_index()?),
+        Mnemonic::BrTable => {
+            let mut labels = vec![tokens.consume_index()?];
+
+            while let Some(label) = tokens.consume_optional_index() {
+                labels.push(label);
+            }
+
+            let default = labels
+                .pop()
+                .expect("br_table should have at least one label");
+
+            InstructionKind::BrTable(labels, default)
+        }
+
+        Mnemonic::Select if tokens.peek_clause(TokenType::Result) => {
+            let mut result_types = Vec::new();
+
+            while tokens.peek_clause(TokenType::Result) {
+                tokens.consume(TokenType::LeftParen)?;
+                tokens.consume(TokenType::Result)?;
+
+                result_types.extend(parse_types(tokens)?);
+
+                tokens.consume(TokenType::RightParen)?;
+            }
+
+            InstructionKind::TypedSelect(result_types)
+        }
+
+        Mnemonic::Throw => InstructionKind::Throw(tokens.consume_index()?),
+        Mnemonic::BrOnNull => InstructionKind::BrOnNull(tokens.consume_index()?),
+        Mnemonic::BrOnNonNull => InstructionKind::BrOnNonNull(tokens.consume_index()?),
+        Mnemonic::BrOnCast | Mnemonic::BrOnCastFail => InstructionKind::BrOnCast(
+            mnemonic,
+            tokens.consume_index()?,
+            parse_value_type(tokens)?,
+            parse_value_type(tokens)?,
+        ),
+
+        Mnemonic::Call => InstructionKind::Call(tokens.consume_index()?),
+        Mnemonic::CallIndirect => {
+            let table = parse_default_index(tokens, mnemonic, "reference-types")?;
+
+            InstructionKind::CallIndirect(table, parse_type_use(tokens)?)
+        }
+        Mnemonic::ReturnCall => InstructionKind::ReturnCall(tokens.consume_index()?),
+        Mnemonic::ReturnCallIndirect => {
+            let table = parse_default_index(tokens, mnemonic, "reference-types")?;
+
+            InstructionKind::ReturnCallIndirect(table, parse_type_use(tokens)?)
+        }
+        Mnemonic::CallRef => InstructionKind::CallRef(tokens.consume_index()?),
+        Mnemonic::ReturnCallRef => InstructionKind::ReturnCallRef(tokens.consume_index()?),
+
+        Mnemonic::LocalGet => InstructionKind::LocalGet(tokens.consume_index()?),
+        Mnemonic::LocalSet => InstructionKind::LocalSet(tokens.consume_index()?),
+        Mnemonic::LocalTee => InstructionKind::LocalTee(tokens.consume_index()?),
+        Mnemonic::GlobalGet => InstructionKind::GlobalGet(tokens.consume_index()?),
+        Mnemonic::GlobalSet => InstructionKind::GlobalSet(tokens.consume_index()?),
+
+        Mnemonic::MemorySize => {
+            InstructionKind::MemorySize(parse_default_index(tokens, mnemonic, "multi-memory")?)
+        }
+        Mnemonic::MemoryGrow => {
+            InstructionKind::MemoryGrow(parse_default_index(tokens, mnemonic, "multi-memory")?)
+        }
+        Mnemonic::RefNull => InstructionKind::RefNull(parse_heap_type(tokens)?),
+        Mnemonic::RefFunc => InstructionKind::RefFunc(tokens.consume_index()?),
+        Mnemonic::RefTest | Mnemonic::RefCast => {
+            InstructionKind::RefCast(mnemonic, parse_value_type(tokens)?)
+        }
+
+        Mnemonic::StructNew
+        | Mnemonic::StructNewDefault
+        | Mnemonic::ArrayNew
+        | Mnemonic::ArrayNewDefault
+        | Mnemonic::ArrayGet
+        | Mnemonic::ArrayGetS
+        | Mnemonic::ArrayGetU
+        | Mnemonic::ArraySet
+        | Mnemonic::ArrayFill => InstructionKind::Aggregate(mnemonic, tokens.consume_index()?),
+        Mnemonic::StructGet | Mnemonic::StructGetS | Mnemonic::StructGetU | Mnemonic::StructSet => {
+            InstructionKind::StructField(mnemonic, tokens.consume_index()?, tokens.consume_index()?)
+        }
+        Mnemonic::ArrayNewFixed => {
+            let array = tokens.consume_index()?;
+            let (length, cursor) = tokens.consume_integer()?;
+
+            InstructionKind::ArrayNewFixed(
+                array,
+                u32::try_from(length).map_err(|_| ConstantOutOfRangeError { cursor })?,
+            )
+        }
+        Mnemonic::ArrayNewData
+        | Mnemonic::ArrayNewElem
+        | Mnemonic::ArrayInitData
+        | Mnemonic::ArrayInitElem => InstructionKind::ArraySegment(
+            mnemonic,
+            tokens.consume_index()?,
+            tokens.consume_index()?,
+        ),
+        Mnemonic::ArrayCopy => {
+            InstructionKind::ArrayCopy(tokens.consume_index()?, tokens.consume_index()?)
+        }
+
+        Mnemonic::MemoryInit => {
+            let first = tokens.consume_index()?;
+            let cursor = tokens.peek_cursor();
+
+            // The memory is only written out when it isn't the default one
+            match tokens.consume_optional_index() {
+                Some(data) => {
+                    require_explicit_index(tokens, mnemonic, "multi-memory", cursor)?;
+
+                    InstructionKind::MemoryInit(data, first)
+                }
+                None => InstructionKind::MemoryInit(first, Index::Numeric(0)),
+            }
+        }
+        Mnemonic::DataDrop => InstructionKind::DataDrop(tokens.consume_index()?),
+        Mnemonic::MemoryCopy => {
+            let (destination, source) = parse_index_pair(tokens, mnemonic, "multi-memory")?;
+
+            InstructionKind::MemoryCopy(destination, source)
+        }
+        Mnemonic::MemoryFill => {
+            InstructionKind::MemoryFill(parse_default_index(tokens, mnemonic, "multi-memory")?)
+        }
+
+        Mnemonic::TableInit => {
+            let first = tokens.consume_index()?;
+            let cursor = tokens.peek_cursor();
+
+            // Like `memory.init`, the table is only written out when it isn't the default one
+            match tokens.consume_optional_index() {
+                Some(element) => {
+                    require_explicit_index(tokens, mnemonic, "reference-types", cursor)?;
+
+                    InstructionKind::TableInit(element, first)
+                }
+                None => InstructionKind::TableInit(first, Index::Numeric(0)),
+            }
+        }
+        Mnemonic::ElemDrop => InstructionKind::ElemDrop(tokens.consume_index()?),
+        Mnemonic::TableCopy => {
+            let (destination, source) = parse_index_pair(tokens, mnemonic, "reference-types")?;
+
+            InstructionKind::TableCopy(destination, source)
+        }
+
+        Mnemonic::TableGet
+        | Mnemonic::TableSet
+        | Mnemonic::TableGrow
+        | Mnemonic::TableSize
+        | Mnemonic::TableFill => InstructionKind::Table(
+            mnemonic,
+            tokens.consume_optional_index().unwrap_or(Index::Numeric(0)),
+        ),
+
+        Mnemonic::I32Const => {
+            let (value, cursor) = tokens.consume_integer()?;
+
+            InstructionKind::I32Const(
+                integer_to_i32(value).ok_or(ConstantOutOfRangeError { cursor })?,
+            )
+        }
+        Mnemonic::I64Const => {
+            let (value, cursor) = tokens.consume_integer()?;
+
+            InstructionKind::I64Const(
+                integer_to_i64(value).ok_or(ConstantOutOfRangeError { cursor })?,
+            )
+        }
+        Mnemonic::F32Const => InstructionKind::F32Const(parse_f32(tokens)?),
+        Mnemonic::F64Const => InstructionKind::F64Const(parse_f64(tokens)?),
+
+        Mnemonic::V128Const => InstructionKind::V128Const(parse_v128(tokens)?),
+        Mnemonic::I8x16Shuffle => {
+            let mut lanes = [0; 16];
+
+            for lane in &mut lanes {
+                *lane = parse_lane_index(tokens)?;
+            }
+
+            InstructionKind::Shuffle(lanes)
+        }
+        lane if lane.is_memory_lane() => {
+            // A lone integer is the lane, the memory is only given in front of a memarg or lane
+            let cursor = tokens.peek_cursor();
+            let memory = match (
+                tokens.peek_type(),
+                tokens.peek_nth(1).map(|token| token.token_type),
+            ) {
+                (Some(TokenType::Identifier(_)), _)
+                | (
+                    Some(TokenType::IntegerLiteral(_)),
+                    Some(
+                        TokenType::IntegerLiteral(_)
+                        | TokenType::MemArgOffset(_)
+                        | TokenType::MemArgAlign(_),
+                    ),
+                ) => {
+                    require_explicit_index(tokens, mnemonic, "multi-memory", cursor)?;
+
+                    tokens.consume_index()?
+                }
+                _ => Index::Numeric(0),
+            };
+            let memarg = parse_memarg(tokens);
+
+            InstructionKind::MemoryLane(lane, memory, memarg, parse_lane_index(tokens)?)
+        }
+        lane if lane.lanes().is_some() => InstructionKind::Lane(lane, parse_lane_index(tokens)?),
+        memory_instruction if memory_instruction.natural_alignment().is_some() => {
+            let memory = parse_default_index(tokens, mnemonic, "multi-memory")?;
+
+            InstructionKind::Memory(memory_instruction, memory, parse_memarg(tokens))
+        }
+
+        _ => InstructionKind::Plain(mnemonic),
+    };
+
+    Ok(kind)
+}
+
+fn parse_f32(tokens: Tokens) -> Result<u32, Error> {
+    let cursor = tokens.peek_cursor();
+
+    let bits = match next_token(tokens)?.token_type {
+        TokenType::FloatLiteral(literal) => float_to_f32_bits(&literal),
+        TokenType::IntegerLiteral(value) => Some((value as f32).to_bits()),
+        _ => return Err(Error::from(ExpectedNumberError { cursor })),
+    };
+
+    Ok(bits.ok_or(ConstantOutOfRangeError { cursor })?)
+}
+
+fn parse_f64(tokens: Tokens) -> Result<u64, Error> {
+    let cursor = tokens.peek_cursor();
+
+    let bits = match next_token(tokens)?.token_type {
+        TokenType::FloatLiteral(literal) => float_to_f64_bits(&literal),
+        TokenType::IntegerLiteral(value) => Some((value as f64).to_bits()),
+        _ => return Err(Error::from(ExpectedNumberError { cursor })),
+    };
+
+    Ok(bits.ok_or(ConstantOutOfRangeError { cursor })?)
+}
+
+/// Reads the shape of a `v128.const` followed by one literal per lane
+fn parse_v128(tokens: Tokens) -> Result<[u8; 16], Error> {
+    let cursor = tokens.peek_cursor();
+
+    let shape = match next_token(tokens)?.token_type {
+        TokenType::VectorShape(shape) => shape,
+        _ => return Err(Error::from(ExpectedTypeError { cursor })),
+    };
+
+    let mut bytes = Vec::with_capacity(16);
+
+    for _ in 0..shape.lanes() {
+        match shape {
+            VectorShape::I8x16 => {
+                let (value, cursor) = tokens.consume_integer()?;
+
+                bytes.push(integer_to_i8(value).ok_or(ConstantOutOfRangeError { cursor })? as u8);
+            }
+            VectorShape::I16x8 => {
+                let (value, cursor) = tokens.consume_integer()?;
+                let lane = integer_to_i16(value).ok_or(ConstantOutOfRangeError { cursor })?;
+
+                bytes.extend(lane.to_le_bytes());
+            }
+            VectorShape::I32x4 => {
+                let (value, cursor) = tokens.consume_integer()?;
+                let lane = integer_to_i32(value).ok_or(ConstantOutOfRangeError { cursor })?;
+
+                bytes.extend(lane.to_le_bytes());
+            }
+            VectorShape::I64x2 => {
+                let (value, cursor) = tokens.consume_integer()?;
+                let lane = integer_to_i64(value).ok_or(ConstantOutOfRangeError { cursor })?;
+
+                bytes.extend(lane.to_le_bytes());
+            }
+            VectorShape::F32x4 => bytes.extend(parse_f32(tokens)?.to_le_bytes()),
+            VectorShape::F64x2 => bytes.extend(parse_f64(tokens)?.to_le_bytes()),
+        }
+    }
+
+    Ok(bytes
+        .try_into()
+        .expect("every vector shape should add up to 16 bytes"))
+}
+
+/// Lane indices are range checked by the compiler, which knows how many lanes there are
+fn parse_lane_index(tokens: Tokens) -> Result<u8, Error> {
+    let (value, cursor) = tokens.consume_integer()?;
+
+    Ok(u8::try_from(value).map_err(|_| ConstantOutOfRangeError { cursor })?)
+}
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Identifier(pub String);
 
@@ -6,3 +8,26 @@ impl From<String> for Identifier {
         Self(value)
     }
 }
+
+impl Display for Identifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
+
+/// Reference to an item in one of the index spaces, either by
+/// its position or by its symbolic identifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Index {
+    Numeric(u32),
+    Identifier(Identifier),
+}
+
+impl Display for Index {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Index::Numeric(index) => write!(f, "{}", index),
+            Index::Identifier(id) => write!(f, "{}", id),
+        }
+    }
+}
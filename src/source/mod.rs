@@ -96,14 +96,6 @@ impl SourceIter {
         res
     }
 
-    pub fn expect(&mut self, ch: char) -> Result<SourceIterItem, Option<SourceIterItem>> {
-        match self.next() {
-            Some(res) if res.0 == ch => Ok(res),
-            Some(res) => Err(Some(res)),
-            None => Err(None),
-        }
-    }
-
     pub fn next_if_char(&mut self, ch: char) -> Option<SourceIterItem> {
         self.next_if(|(next_character, _)| next_character == ch)
     }
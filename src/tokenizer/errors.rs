@@ -43,6 +43,75 @@ impl ErrorDisplay for UnrecognizedKeywordError {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidNumberError {
+    pub literal: String,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for InvalidNumberError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("tokenizer error should point to valid position");
+
+        format!(
+            "Invalid number \"{}\" found in line:\n{}",
+            self.literal.escape_debug().collect::<String>(),
+            position
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnterminatedStringError {
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for UnterminatedStringError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("tokenizer error should point to valid position");
+
+        format!("String is missing its closing quote in line:\n{}", position)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidEscapeError {
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for InvalidEscapeError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("tokenizer error should point to valid position");
+
+        format!("Invalid escape sequence in line:\n{}", position)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnclosedAnnotationError {
+    pub name: String,
+    pub cursor: PageCursor,
+}
+
+impl ErrorDisplay for UnclosedAnnotationError {
+    fn display(&self, source: Source) -> String {
+        let position = source
+            .display_position(self.cursor)
+            .expect("tokenizer error should point to valid position");
+
+        format!(
+            "The `@{}` annotation is missing its closing paren in line:\n{}",
+            self.name, position
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::traits::{error_display::ErrorDisplay, page_position::PageCursor};
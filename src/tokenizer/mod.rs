@@ -1,18 +1,21 @@
-use errors::{UnrecognizedKeywordError, UnrecognizedTokenError};
+use errors::{
+    InvalidEscapeError, InvalidNumberError, UnclosedAnnotationError, UnrecognizedKeywordError,
+    UnrecognizedTokenError, UnterminatedStringError,
+};
 use token_store::TokenStore;
-use util::{char_to_digit, keyword_to_token_type};
+use util::{keyword_to_token_type, memarg_to_token_type, number_to_token_type};
 
 use crate::{
+    diagnostic::{DiagnosticKind, Error},
+    features::{errors::DisabledFeatureError, WasmFeatures},
+    parser::instructions::{CatchKind, HeapType, Mnemonic, VectorShape},
     shared::Identifier,
     source::SourceIter,
-    traits::{
-        error_display::ErrorDisplay,
-        page_position::{PageCursor, PagePosition},
-    },
+    traits::page_position::{PageCursor, PagePosition},
     Source,
 };
 
-mod errors;
+pub mod errors;
 mod tests;
 mod util;
 #[macro_use]
@@ -36,36 +39,76 @@ pub enum TokenType {
     RightParen,
     SemiColon, // TODO: Do i need this?
     LineComment(String),
-    String(String),
-    IntegerLiteral(i32),
+    /// Raw bytes of a string literal after escapes are resolved, which need not be valid UTF-8
+    String(Vec<u8>),
+    IntegerLiteral(i128),
+    /// Kept as written, since its value depends on the precision it is read with
+    FloatLiteral(String),
     Identifier(Identifier),
+    /// Name of an annotation such as `(@custom ...)`, without the `@`
+    Annotation(String),
+    /// Keyword without a meaning of its own, which is only allowed inside annotations
+    Reserved(String),
     I32,
     I64,
     F32,
     F64,
     V128,
+    /// Lane shape of a `v128.const`, such as `i32x4`
+    VectorShape(VectorShape),
     FuncRef,
     ExternRef,
+    ExnRef,
+    /// Shorthand for a nullable reference to one of the other abstract heap types, like `anyref`
+    AbstractRef(HeapType),
+    /// Abstract heap types without a token of their own, like `any`
+    HeapType(HeapType),
     Func,
     Extern,
+    Exn,
+    Ref,
+    Null,
+    Struct,
+    Array,
+    Field,
+    /// Packed storage types of fields
+    I8,
+    I16,
+    Rec,
+    Sub,
+    Final,
     Module,
+    Type,
     Result,
     Param,
     Mut,
     Local,
-    Dot,
-    Set,
-    Get,
     Export,
-    Const,
-    Nan,
-    Inf,
+    Import,
+    Global,
+    Memory,
+    Data,
+    Table,
+    Elem,
+    Declare,
+    Shared,
+    Item,
+    Start,
+    Tag,
+    Offset,
+    /// `offset=` field of a memory instruction
+    MemArgOffset(u64),
+    /// `align=` field of a memory instruction
+    MemArgAlign(u64),
     Block,
     Loop,
     If,
-    Unreachable,
-    Return,
-    Add,
+    Then,
+    Else,
+    End,
+    TryTable,
+    Catch(CatchKind),
+    Instruction(Mnemonic),
 }
 
 fn is_identifier_character(ch: char) -> bool {
@@ -80,26 +123,59 @@ fn is_identifier_character(ch: char) -> bool {
 fn is_separator_character(ch: char) -> bool {
     match ch {
         whitespace if whitespace.is_ascii_whitespace() => true,
-        '.' | ')' | ';' => true,
+        '(' | ')' | ';' | '"' => true,
         _ => false,
     }
 }
 
-pub fn generate_tokens(input: Source) -> Result<TokenStore, Vec<Box<dyn ErrorDisplay>>> {
+pub fn generate_tokens(input: Source, features: &WasmFeatures) -> Result<TokenStore, Vec<Error>> {
     let mut store = TokenStore::default();
-    let mut errors: Vec<Box<dyn ErrorDisplay>> = Vec::new();
+    let mut errors: Vec<Error> = Vec::new();
 
     let input_iter = &mut input.into_iter();
 
+    // Parens left open inside the current annotation, whose contents may be arbitrary keywords
+    let mut annotation_depth = 0;
+    let mut annotation = None;
+
     while let Some((character, cursor)) = input_iter.next() {
         if character.is_ascii_whitespace() {
             continue;
         }
 
-        match tokenize_token(input_iter, character, cursor) {
-            Ok(token_type) => store.tokens.push(Token { token_type, cursor }),
-            Err(err) => errors.push(err),
+        let token_type = match tokenize_token(input_iter, character, cursor, annotation_depth > 0) {
+            Ok(token_type) => token_type,
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        };
+
+        match &token_type {
+            TokenType::Annotation(name) if annotation_depth == 0 => {
+                if !features.annotations {
+                    errors.push(Error::from(DisabledFeatureError {
+                        construct: format!("The `@{}` annotation", name),
+                        feature: "annotations",
+                        kind: DiagnosticKind::Lex,
+                        cursor,
+                    }));
+                }
+
+                annotation_depth = 1;
+                annotation = Some((name.clone(), cursor));
+            }
+            TokenType::LeftParen if annotation_depth > 0 => annotation_depth += 1,
+            TokenType::RightParen if annotation_depth > 0 => annotation_depth -= 1,
+            _ => {}
         }
+
+        store.tokens.push(Token { token_type, cursor });
+    }
+
+    // Otherwise the annotation would silently swallow the rest of the file
+    if let Some((name, cursor)) = annotation.filter(|_| annotation_depth > 0) {
+        errors.push(Error::from(UnclosedAnnotationError { name, cursor }));
     }
 
     if !errors.is_empty() {
@@ -113,7 +189,8 @@ fn tokenize_token(
     source_iter: &mut SourceIter,
     character: char,
     cursor: PageCursor,
-) -> Result<TokenType, Box<dyn ErrorDisplay>> {
+    in_annotation: bool,
+) -> Result<TokenType, Error> {
     match character {
         '(' => Ok(TokenType::LeftParen),
         ')' => Ok(TokenType::RightParen),
@@ -142,66 +219,122 @@ fn tokenize_token(
             Ok(TokenType::Identifier(Identifier(identifier_name)))
         }
 
-        number_start if number_start.is_ascii_digit() => {
-            let number = source_iter
-                .consume_to_string_while(|(char, _)| char.is_ascii_digit() || char == '_')
-                .chars()
-                .fold(char_to_digit(number_start), |prev, digit| {
-                    if digit == '_' {
-                        return prev;
-                    };
-
-                    prev * 10 + char_to_digit(digit)
-                });
+        '@' => {
+            let annotation_name =
+                source_iter.consume_to_string_while(|(ch, _)| is_identifier_character(ch));
 
-            Ok(TokenType::IntegerLiteral(number))
-        }
-        sign_char if sign_char == '+' || sign_char == '-' => {
-            let number = source_iter
-                .consume_to_string_while(|(char, _)| char.is_ascii_digit() || char == '_')
-                .chars()
-                .fold(0, |prev, digit| {
-                    if digit == '_' {
-                        return prev;
-                    };
-
-                    prev * 10 + char_to_digit(digit)
-                });
-
-            if sign_char == '+' {
-                Ok(TokenType::IntegerLiteral(number))
-            } else {
-                Ok(TokenType::IntegerLiteral(-number))
+            if annotation_name.is_empty() {
+                return Err(Error::from(UnrecognizedTokenError {
+                    unrecognized_character: character,
+                    cursor,
+                }));
             }
-        }
 
-        '"' => {
-            // TODO: Support hex digits and escape characters in strings
-            let string_contents = source_iter.consume_to_string_while(|(char, _)| char != '"');
+            Ok(TokenType::Annotation(annotation_name))
+        }
 
-            source_iter.expect('"').expect("\" should follow string");
+        number_start
+            if number_start.is_ascii_digit() || number_start == '+' || number_start == '-' =>
+        {
+            let literal = number_start.to_string()
+                + &source_iter.consume_to_string_while(|(char, _)| !is_separator_character(char));
 
-            Ok(TokenType::String(string_contents))
+            match number_to_token_type(&literal) {
+                Some(number_token_type) => Ok(number_token_type),
+                None => Err(Error::from(InvalidNumberError { literal, cursor })),
+            }
         }
 
-        '.' => Ok(TokenType::Dot),
+        '"' => tokenize_string(source_iter, cursor).map(TokenType::String),
 
         keyword_start if keyword_start.is_ascii_alphabetic() => {
             let keyword = keyword_start.to_string()
                 + &source_iter.consume_to_string_while(|(char, _)| !is_separator_character(char));
 
-            match keyword_to_token_type(&keyword) {
+            match keyword_to_token_type(&keyword)
+                .or_else(|| number_to_token_type(&keyword))
+                .or_else(|| memarg_to_token_type(&keyword))
+            {
                 Some(keyword_token_type) => Ok(keyword_token_type),
-                None => Err(Box::new(UnrecognizedKeywordError {
+                None if in_annotation => Ok(TokenType::Reserved(keyword)),
+                None => Err(Error::from(UnrecognizedKeywordError {
                     unrecognized_keyword: keyword,
                     cursor,
                 })),
             }
         }
 
-        _ => Err(Box::new(UnrecognizedTokenError {
+        _ => Err(Error::from(UnrecognizedTokenError {
             unrecognized_character: character,
             cursor,
         })),
     }
 }
+
+/// Reads the rest of a string literal, resolving escapes into the bytes they stand for
+fn tokenize_string(source_iter: &mut SourceIter, cursor: PageCursor) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    let mut invalid_escape = None;
+
+    loop {
+        match source_iter.next() {
+            Some(('"', _)) => break,
+            Some(('\\', escape_cursor)) => match tokenize_escape(source_iter) {
+                Some(escaped) => bytes.extend(escaped),
+                // Keep reading so the rest of the string isn't mistaken for tokens
+                None => {
+                    invalid_escape.get_or_insert(InvalidEscapeError {
+                        cursor: escape_cursor,
+                    });
+                }
+            },
+            Some(('\n', _)) | None => return Err(Error::from(UnterminatedStringError { cursor })),
+            Some((character, _)) => {
+                bytes.extend(character.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+        }
+    }
+
+    match invalid_escape {
+        Some(error) => Err(Error::from(error)),
+        None => Ok(bytes),
+    }
+}
+
+fn tokenize_escape(source_iter: &mut SourceIter) -> Option<Vec<u8>> {
+    let (character, _) = source_iter.next_if(|(character, _)| character != '\n')?;
+
+    let byte = match character {
+        't' => b'\t',
+        'n' => b'\n',
+        'r' => b'\r',
+        '"' => b'"',
+        '\'' => b'\'',
+        '\\' => b'\\',
+
+        'u' => {
+            source_iter.next_if_char('{')?;
+
+            let digits = source_iter.consume_to_string_while(|(character, _)| {
+                character.is_ascii_hexdigit() || character == '_'
+            });
+
+            source_iter.next_if_char('}')?;
+
+            let code_point = u32::from_str_radix(&digits.replace('_', ""), 16).ok()?;
+            let character = char::from_u32(code_point)?;
+
+            return Some(character.encode_utf8(&mut [0; 4]).as_bytes().to_vec());
+        }
+
+        high if high.is_ascii_hexdigit() => {
+            let (low, _) = source_iter.next_if(|(low, _)| low.is_ascii_hexdigit())?;
+
+            (high.to_digit(16)? * 16 + low.to_digit(16)?) as u8
+        }
+
+        _ => return None,
+    };
+
+    Some(vec![byte])
+}
@@ -1,15 +1,17 @@
 #![cfg(test)]
-use crate::{source::Source, tokenizer::generate_tokens};
 use crate::{
-    tokenizer::{token_store::TokenStore, Token, TokenType},
+    diagnostic::Error,
+    features::WasmFeatures,
+    tokenizer::{errors::UnclosedAnnotationError, token_store::TokenStore, Token, TokenType},
     traits::page_position::PageCursor,
 };
+use crate::{parser::instructions::Mnemonic, source::Source, tokenizer::generate_tokens};
 
 #[test]
 fn test_tokenize_line_comment() {
     let source: Source = ";; comment contents\n()".into();
 
-    let res = generate_tokens(dbg!(source));
+    let res = generate_tokens(dbg!(source), &WasmFeatures::default());
 
     assert_eq!(
         res.unwrap(),
@@ -36,14 +38,14 @@ fn test_tokenize_line_comment() {
 fn test_tokenize_string() {
     let source: Source = "\"string contents\"()".into();
 
-    let res = generate_tokens(dbg!(source));
+    let res = generate_tokens(dbg!(source), &WasmFeatures::default());
 
     assert_eq!(
         res.unwrap(),
         (TokenStore {
             tokens: vec![
                 Token {
-                    token_type: TokenType::String("string contents".to_string()),
+                    token_type: TokenType::String(b"string contents".to_vec()),
                     cursor: PageCursor { line: 1, column: 0 }
                 },
                 Token {
@@ -69,7 +71,7 @@ fn test_tokenize_string() {
 fn test_parse_number() {
     let source: Source = "1234".into();
 
-    let res = generate_tokens(source);
+    let res = generate_tokens(source, &WasmFeatures::default());
 
     assert_eq!(
         res.unwrap(),
@@ -86,7 +88,7 @@ fn test_parse_number() {
 fn test_parse_number_with_underscore() {
     let source: Source = "1_234".into();
 
-    let res = generate_tokens(source);
+    let res = generate_tokens(source, &WasmFeatures::default());
 
     assert_eq!(
         res.unwrap(),
@@ -103,7 +105,7 @@ fn test_parse_number_with_underscore() {
 fn test_parse_number_with_negative() {
     let source: Source = "-1_234".into();
 
-    let res = generate_tokens(source);
+    let res = generate_tokens(source, &WasmFeatures::default());
 
     assert_eq!(
         res.unwrap(),
@@ -120,7 +122,7 @@ fn test_parse_number_with_negative() {
 fn test_parse_identifier_name() {
     let source: Source = "$epic-identifier>=<&@!%^&)".into();
 
-    let res = generate_tokens(source);
+    let res = generate_tokens(source, &WasmFeatures::default());
 
     assert_eq!(
         res.unwrap(),
@@ -143,3 +145,158 @@ fn test_parse_identifier_name() {
         })
     )
 }
+
+#[test]
+fn test_parse_hex_number() {
+    let source: Source = "0xFF_FF".into();
+
+    let res = generate_tokens(source, &WasmFeatures::default());
+
+    assert_eq!(
+        res.unwrap(),
+        (TokenStore {
+            tokens: vec![Token {
+                token_type: TokenType::IntegerLiteral(0xFFFF),
+                cursor: PageCursor::start()
+            }]
+        })
+    )
+}
+
+#[test]
+fn test_parse_float() {
+    let source: Source = "-1.5e10".into();
+
+    let res = generate_tokens(source, &WasmFeatures::default());
+
+    assert_eq!(
+        res.unwrap(),
+        (TokenStore {
+            tokens: vec![Token {
+                token_type: TokenType::FloatLiteral("-1.5e10".to_string()),
+                cursor: PageCursor::start()
+            }]
+        })
+    )
+}
+
+#[test]
+fn test_tokenize_instruction() {
+    let source: Source = "(i32.add)".into();
+
+    let res = generate_tokens(source, &WasmFeatures::default());
+
+    assert_eq!(
+        res.unwrap(),
+        (TokenStore {
+            tokens: vec![
+                Token {
+                    token_type: TokenType::LeftParen,
+                    cursor: PageCursor::start()
+                },
+                Token {
+                    token_type: TokenType::Instruction(Mnemonic::I32Add),
+                    cursor: PageCursor { line: 1, column: 1 }
+                },
+                Token {
+                    token_type: TokenType::RightParen,
+                    cursor: PageCursor { line: 1, column: 8 }
+                }
+            ]
+        })
+    )
+}
+
+#[test]
+fn test_tokenize_memarg() {
+    let source: Source = "offset=0x10 align=4".into();
+
+    let res = generate_tokens(source, &WasmFeatures::default());
+
+    assert_eq!(
+        res.unwrap(),
+        (TokenStore {
+            tokens: vec![
+                Token {
+                    token_type: TokenType::MemArgOffset(16),
+                    cursor: PageCursor::start()
+                },
+                Token {
+                    token_type: TokenType::MemArgAlign(4),
+                    cursor: PageCursor {
+                        line: 1,
+                        column: 12
+                    }
+                }
+            ]
+        })
+    )
+}
+
+#[test]
+fn test_tokenize_string_escapes() {
+    let source: Source = r#""\t\"\\\ff\u{263A}""#.into();
+
+    let res = generate_tokens(source, &WasmFeatures::default());
+
+    assert_eq!(
+        res.unwrap(),
+        (TokenStore {
+            tokens: vec![Token {
+                token_type: TokenType::String(vec![0x09, b'"', b'\\', 0xFF, 0xE2, 0x98, 0xBA]),
+                cursor: PageCursor::start()
+            }]
+        })
+    )
+}
+
+#[test]
+fn test_unterminated_string() {
+    let source: Source = "\"abc".into();
+
+    assert!(generate_tokens(source, &WasmFeatures::default()).is_err());
+}
+
+#[test]
+fn test_tokenize_annotation() {
+    let source: Source = "(@custom code) code".into();
+
+    // Unknown keywords are only allowed inside the annotation
+    assert!(generate_tokens(source, &WasmFeatures::default()).is_err());
+
+    let source: Source = "(@custom code)".into();
+
+    assert_eq!(
+        generate_tokens(source, &WasmFeatures::default())
+            .unwrap()
+            .tokens[1..3],
+        [
+            Token {
+                token_type: TokenType::Annotation("custom".to_owned()),
+                cursor: PageCursor { line: 1, column: 1 }
+            },
+            Token {
+                token_type: TokenType::Reserved("code".to_owned()),
+                cursor: PageCursor { line: 1, column: 9 }
+            }
+        ]
+    )
+}
+
+#[test]
+fn test_unclosed_annotation() {
+    let source: Source = "(module (func) (@x \"x\"".into();
+
+    // Points at the annotation rather than the module it swallowed
+    let errors = generate_tokens(source, &WasmFeatures::default()).unwrap_err();
+
+    assert!(matches!(
+        &errors[..],
+        [Error::UnclosedAnnotation(UnclosedAnnotationError { name, cursor })]
+            if name == "x" && *cursor == PageCursor { line: 1, column: 16 }
+    ));
+
+    let source: Source = "(module (func)) (@x".into();
+
+    assert!(generate_tokens(source, &WasmFeatures::default()).is_err());
+}
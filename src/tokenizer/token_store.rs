@@ -1,13 +1,24 @@
+use std::collections::HashMap;
+
 use crate::{
-    parser::errors::{
-        ExpectedIdentifierError, ExpectedStringError, ExpectedTokenError, ExpectedTypeError,
+    diagnostic::{DiagnosticKind, Error},
+    features::{errors::DisabledFeatureError, WasmFeatures},
+    parser::{
+        errors::{
+            ExpectedIdentifierError, ExpectedIndexError, ExpectedIntegerError, ExpectedStringError,
+            ExpectedTokenError, ExpectedTypeError, MalformedNameError,
+        },
+        instructions::BuiltinType,
     },
-    shared::Identifier,
-    traits::{error_display::ErrorDisplay, page_position::PageCursor},
+    shared::{Identifier, Index},
+    traits::page_position::PageCursor,
 };
 
 use super::{Token, TokenType};
 
+/// Annotations that are handed to the parser, all others are skipped
+const PARSED_ANNOTATIONS: [&str; 1] = ["custom"];
+
 #[derive(Debug, PartialEq, Default)]
 pub struct TokenStore {
     pub tokens: Vec<Token>,
@@ -17,29 +28,127 @@ impl IntoIterator for TokenStore {
     type Item = Token;
     type IntoIter = TokenIter;
 
+    /// Comments and annotations the parser doesn't know carry no meaning for it,
+    /// so they are dropped here
     fn into_iter(self) -> Self::IntoIter {
+        let mut tokens = self
+            .tokens
+            .into_iter()
+            .filter(|token| !matches!(token.token_type, TokenType::LineComment(_)))
+            .peekable();
+
+        let mut token_iter = Vec::new();
+        let mut ignored_depth = 0;
+
+        while let Some(token) = tokens.next() {
+            if ignored_depth > 0 {
+                match token.token_type {
+                    TokenType::LeftParen => ignored_depth += 1,
+                    TokenType::RightParen => ignored_depth -= 1,
+                    _ => {}
+                }
+            } else if token.token_type == TokenType::LeftParen
+                && tokens.peek().is_some_and(is_ignored_annotation)
+            {
+                ignored_depth = 1;
+            } else {
+                token_iter.push(token);
+            }
+        }
+
         TokenIter {
-            token_iter: self.tokens,
+            token_iter,
             cursor: 0,
+            type_names: HashMap::new(),
+            features: WasmFeatures::default(),
         }
     }
 }
 
+fn is_ignored_annotation(token: &Token) -> bool {
+    match &token.token_type {
+        TokenType::Annotation(name) => !PARSED_ANNOTATIONS.contains(&name.as_str()),
+        _ => false,
+    }
+}
+
 pub struct TokenIter {
     token_iter: Vec<Token>,
     cursor: usize,
+    /// Indices of the named types of the module, which reference types can
+    /// point to before they are defined
+    type_names: HashMap<Identifier, u32>,
+    /// Proposals the parser accepts the syntax of
+    features: WasmFeatures,
 }
 
 impl TokenIter {
+    pub fn set_type_names(&mut self, type_names: HashMap<Identifier, u32>) {
+        self.type_names = type_names;
+    }
+
+    pub fn type_index(&self, id: &Identifier) -> Option<u32> {
+        self.type_names.get(id).copied()
+    }
+
+    pub fn set_features(&mut self, features: WasmFeatures) {
+        self.features = features;
+    }
+
+    /// Rejects syntax that only exists with `feature` enabled
+    pub fn require_feature(
+        &self,
+        feature: &'static str,
+        construct: &str,
+        cursor: PageCursor,
+    ) -> Result<(), DisabledFeatureError> {
+        if self.features.is_enabled(feature) {
+            return Ok(());
+        }
+
+        Err(DisabledFeatureError {
+            construct: construct.to_owned(),
+            feature,
+            kind: DiagnosticKind::Parse,
+            cursor,
+        })
+    }
+
     pub fn peek(&self) -> Option<Token> {
         self.token_iter.get(self.cursor).cloned()
     }
 
+    /// Look `n` tokens past the next one without advancing
+    pub fn peek_nth(&self, n: usize) -> Option<Token> {
+        self.token_iter.get(self.cursor + n).cloned()
+    }
+
+    pub fn peek_type(&self) -> Option<TokenType> {
+        self.peek().map(|token| token.token_type)
+    }
+
+    /// Whether the next tokens are a left paren followed by `token_type`
+    pub fn peek_clause(&self, token_type: TokenType) -> bool {
+        self.peek_type() == Some(TokenType::LeftParen)
+            && self
+                .peek_nth(1)
+                .is_some_and(|token| token.token_type == token_type)
+    }
+
     pub fn guess_cursor(&self) -> Option<PageCursor> {
         // TODO: Make this smarter... detect when cursor should advance
         self.token_iter
-            .get(self.cursor - 1)
+            .get(self.cursor.saturating_sub(1))
+            .or(self.token_iter.last())
+            .map(|token| token.cursor)
+    }
+
+    /// Cursor of the next token, falling back to the last one at the end of the input
+    pub fn peek_cursor(&self) -> PageCursor {
+        self.peek()
             .map(|token| token.cursor)
+            .or(self.guess_cursor())
+            .unwrap_or(PageCursor::start())
     }
 
     pub fn consume_identifier(
@@ -56,27 +165,83 @@ impl TokenIter {
                     cursor: token.cursor,
                 }),
             },
-            None => todo!(),
+            None => Err(ExpectedIdentifierError {
+                cursor: self.peek_cursor(),
+            }),
         }
     }
 
-    pub fn consume_type(&mut self) -> Result<Token, ExpectedTypeError> {
+    /// Consumes the next token only if it is an identifier
+    pub fn consume_optional_identifier(&mut self) -> Option<Identifier> {
+        self.consume_identifier().ok().map(|(id, _)| id)
+    }
+
+    pub fn consume_index(&mut self) -> Result<Index, ExpectedIndexError> {
+        let cursor = self.peek_cursor();
+
+        match self.peek_type() {
+            Some(TokenType::Identifier(id)) => {
+                self.next();
+
+                Ok(Index::Identifier(id))
+            }
+            Some(TokenType::IntegerLiteral(value)) if (0..=u32::MAX as i128).contains(&value) => {
+                self.next();
+
+                Ok(Index::Numeric(value as u32))
+            }
+            _ => Err(ExpectedIndexError { cursor }),
+        }
+    }
+
+    /// Consumes the next token only if it is an index
+    pub fn consume_optional_index(&mut self) -> Option<Index> {
+        self.consume_index().ok()
+    }
+
+    pub fn consume_integer(&mut self) -> Result<(i128, PageCursor), ExpectedIntegerError> {
         match self.peek() {
-            Some(token) => match &token.token_type {
-                TokenType::I32 | TokenType::I64 | TokenType::F32 | TokenType::F64 => {
+            Some(token) => match token.token_type {
+                TokenType::IntegerLiteral(value) => {
                     self.next();
 
-                    Ok(token)
+                    Ok((value, token.cursor))
                 }
-                _ => Err(ExpectedTypeError {
+                _ => Err(ExpectedIntegerError {
                     cursor: token.cursor,
                 }),
             },
-            None => todo!(),
+            None => Err(ExpectedIntegerError {
+                cursor: self.peek_cursor(),
+            }),
         }
     }
 
-    pub fn consume_string(&mut self) -> Result<(String, PageCursor), ExpectedStringError> {
+    pub fn consume_type(&mut self) -> Result<BuiltinType, ExpectedTypeError> {
+        let builtin_type = match self.peek_type() {
+            Some(TokenType::I32) => BuiltinType::I32,
+            Some(TokenType::I64) => BuiltinType::I64,
+            Some(TokenType::F32) => BuiltinType::F32,
+            Some(TokenType::F64) => BuiltinType::F64,
+            Some(TokenType::V128) => BuiltinType::V128,
+            Some(TokenType::FuncRef) => BuiltinType::FuncRef,
+            Some(TokenType::ExternRef) => BuiltinType::ExternRef,
+            Some(TokenType::ExnRef) => BuiltinType::ExnRef,
+            Some(TokenType::AbstractRef(heap_type)) => BuiltinType::reference(true, heap_type),
+
+            _ => {
+                return Err(ExpectedTypeError {
+                    cursor: self.peek_cursor(),
+                })
+            }
+        };
+
+        self.next();
+
+        Ok(builtin_type)
+    }
+
+    pub fn consume_bytes(&mut self) -> Result<(Vec<u8>, PageCursor), ExpectedStringError> {
         match self.peek() {
             Some(token) => match token.token_type {
                 TokenType::String(literal) => {
@@ -88,26 +253,33 @@ impl TokenIter {
                     cursor: token.cursor,
                 }),
             },
-            None => todo!(),
+            None => Err(ExpectedStringError {
+                cursor: self.peek_cursor(),
+            }),
+        }
+    }
+
+    /// Consumes a string that is used as a name, which has to be valid UTF-8
+    pub fn consume_string(&mut self) -> Result<(String, PageCursor), Error> {
+        let (bytes, cursor) = self.consume_bytes()?;
+
+        match String::from_utf8(bytes) {
+            Ok(name) => Ok((name, cursor)),
+            Err(_) => Err(Error::from(MalformedNameError { cursor })),
         }
     }
 
     /// Only advances if the next token matches the `expected_token_type`
-    pub fn consume(
-        &mut self,
-        expected_token_type: TokenType,
-    ) -> Result<Token, Box<dyn ErrorDisplay>> {
+    pub fn consume(&mut self, expected_token_type: TokenType) -> Result<Token, Error> {
         if self
             .peek()
             .is_some_and(|token| token.token_type == expected_token_type)
         {
             Ok(self.next().unwrap())
         } else {
-            Err(Box::new(ExpectedTokenError {
+            Err(Error::from(ExpectedTokenError {
                 expected_token: expected_token_type,
-                cursor: self
-                    .guess_cursor()
-                    .expect("should be able to estimate Cursor to display ExpectedTokenError"),
+                cursor: self.peek_cursor(),
             }))
         }
     }
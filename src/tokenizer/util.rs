@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use crate::parser::instructions::{CatchKind, HeapType, Mnemonic, VectorShape};
+
 use super::TokenType;
 
 pub fn char_to_digit(ch: char) -> i32 {
@@ -14,22 +16,40 @@ pub fn keyword_to_token_type(name: &str) -> Option<TokenType> {
     let mut lookup = HashMap::new();
 
     lookup.insert("module", TokenType::Module);
+    lookup.insert("type", TokenType::Type);
     lookup.insert("param", TokenType::Param);
     lookup.insert("result", TokenType::Result);
     lookup.insert("export", TokenType::Export);
-    lookup.insert("const", TokenType::Const);
-
-    lookup.insert("get", TokenType::Get);
-    lookup.insert("set", TokenType::Set);
+    lookup.insert("import", TokenType::Import);
+    lookup.insert("global", TokenType::Global);
+    lookup.insert("memory", TokenType::Memory);
+    lookup.insert("data", TokenType::Data);
+    lookup.insert("offset", TokenType::Offset);
+    lookup.insert("table", TokenType::Table);
+    lookup.insert("elem", TokenType::Elem);
+    lookup.insert("declare", TokenType::Declare);
+    lookup.insert("shared", TokenType::Shared);
+    lookup.insert("item", TokenType::Item);
+    lookup.insert("start", TokenType::Start);
+    lookup.insert("tag", TokenType::Tag);
+    lookup.insert("rec", TokenType::Rec);
+    lookup.insert("sub", TokenType::Sub);
+    lookup.insert("final", TokenType::Final);
+    lookup.insert("struct", TokenType::Struct);
+    lookup.insert("array", TokenType::Array);
+    lookup.insert("field", TokenType::Field);
 
     lookup.insert("block", TokenType::Block);
     lookup.insert("loop", TokenType::Loop);
     lookup.insert("if", TokenType::If);
-
-    lookup.insert("unreachable", TokenType::Unreachable);
-    lookup.insert("return", TokenType::Return);
-
-    lookup.insert("add", TokenType::Add);
+    lookup.insert("then", TokenType::Then);
+    lookup.insert("else", TokenType::Else);
+    lookup.insert("end", TokenType::End);
+    lookup.insert("try_table", TokenType::TryTable);
+    lookup.insert("catch", TokenType::Catch(CatchKind::Catch));
+    lookup.insert("catch_ref", TokenType::Catch(CatchKind::CatchRef));
+    lookup.insert("catch_all", TokenType::Catch(CatchKind::CatchAll));
+    lookup.insert("catch_all_ref", TokenType::Catch(CatchKind::CatchAllRef));
 
     // Types
     lookup.insert("i32", TokenType::I32);
@@ -39,16 +59,151 @@ pub fn keyword_to_token_type(name: &str) -> Option<TokenType> {
     lookup.insert("v128", TokenType::V128);
     lookup.insert("funcref", TokenType::FuncRef);
     lookup.insert("externref", TokenType::ExternRef);
+    lookup.insert("exnref", TokenType::ExnRef);
+    lookup.insert("anyref", TokenType::AbstractRef(HeapType::Any));
+    lookup.insert("eqref", TokenType::AbstractRef(HeapType::Eq));
+    lookup.insert("i31ref", TokenType::AbstractRef(HeapType::I31));
+    lookup.insert("structref", TokenType::AbstractRef(HeapType::Struct));
+    lookup.insert("arrayref", TokenType::AbstractRef(HeapType::Array));
+    lookup.insert("nullref", TokenType::AbstractRef(HeapType::None));
+    lookup.insert("nullfuncref", TokenType::AbstractRef(HeapType::NoFunc));
+    lookup.insert("nullexternref", TokenType::AbstractRef(HeapType::NoExtern));
+    lookup.insert("nullexnref", TokenType::AbstractRef(HeapType::NoExn));
+    lookup.insert("i8", TokenType::I8);
+    lookup.insert("i16", TokenType::I16);
+
+    // Vector shapes
+    lookup.insert("i8x16", TokenType::VectorShape(VectorShape::I8x16));
+    lookup.insert("i16x8", TokenType::VectorShape(VectorShape::I16x8));
+    lookup.insert("i32x4", TokenType::VectorShape(VectorShape::I32x4));
+    lookup.insert("i64x2", TokenType::VectorShape(VectorShape::I64x2));
+    lookup.insert("f32x4", TokenType::VectorShape(VectorShape::F32x4));
+    lookup.insert("f64x2", TokenType::VectorShape(VectorShape::F64x2));
+
     lookup.insert("func", TokenType::Func);
     lookup.insert("extern", TokenType::Extern);
+    lookup.insert("exn", TokenType::Exn);
+    lookup.insert("any", TokenType::HeapType(HeapType::Any));
+    lookup.insert("eq", TokenType::HeapType(HeapType::Eq));
+    lookup.insert("i31", TokenType::HeapType(HeapType::I31));
+    lookup.insert("none", TokenType::HeapType(HeapType::None));
+    lookup.insert("nofunc", TokenType::HeapType(HeapType::NoFunc));
+    lookup.insert("noextern", TokenType::HeapType(HeapType::NoExtern));
+    lookup.insert("noexn", TokenType::HeapType(HeapType::NoExn));
+    lookup.insert("ref", TokenType::Ref);
+    lookup.insert("null", TokenType::Null);
     lookup.insert("mut", TokenType::Mut);
 
-    lookup.insert("inf", TokenType::Inf);
-    lookup.insert("nan", TokenType::Nan);
-
     lookup.insert("local", TokenType::Local);
 
-    lookup.get(name).cloned()
+    lookup
+        .get(name)
+        .cloned()
+        .or_else(|| Mnemonic::from_keyword(name).map(TokenType::Instruction))
+}
+
+/// Classify a literal starting with a digit or sign (or one of the
+/// float keywords `inf` and `nan`) as either an integer or a float
+pub fn number_to_token_type(literal: &str) -> Option<TokenType> {
+    let (negative, magnitude) = match literal.strip_prefix('-') {
+        Some(magnitude) => (true, magnitude),
+        None => (false, literal.strip_prefix('+').unwrap_or(literal)),
+    };
+
+    if magnitude == "inf" || magnitude == "nan" {
+        return Some(TokenType::FloatLiteral(literal.to_owned()));
+    }
+
+    if let Some(payload) = magnitude.strip_prefix("nan:0x") {
+        return is_digit_sequence(payload, 16).then(|| TokenType::FloatLiteral(literal.to_owned()));
+    }
+
+    let (radix, digits) = match magnitude.strip_prefix("0x") {
+        Some(hex_digits) => (16, hex_digits),
+        None => (10, magnitude),
+    };
+
+    if is_digit_sequence(digits, radix) {
+        let value = digits
+            .chars()
+            .filter(|digit| *digit != '_')
+            .try_fold(0i128, |prev, digit| {
+                let digit = if radix == 10 {
+                    char_to_digit(digit) as i128
+                } else {
+                    digit.to_digit(radix)? as i128
+                };
+
+                prev.checked_mul(radix as i128)?.checked_add(digit)
+            })
+            .filter(|value| *value <= u64::MAX as i128)?;
+
+        return Some(TokenType::IntegerLiteral(if negative {
+            -value
+        } else {
+            value
+        }));
+    }
+
+    is_float(digits, radix).then(|| TokenType::FloatLiteral(literal.to_owned()))
+}
+
+/// Memory instructions take their offset and alignment as `offset=N` and `align=N`
+pub fn memarg_to_token_type(keyword: &str) -> Option<TokenType> {
+    let (field, value) = keyword.split_once('=')?;
+
+    if value.starts_with(['+', '-']) {
+        return None;
+    }
+
+    let Some(TokenType::IntegerLiteral(value)) = number_to_token_type(value) else {
+        return None;
+    };
+
+    match field {
+        "offset" => Some(TokenType::MemArgOffset(value as u64)),
+        "align" => Some(TokenType::MemArgAlign(value as u64)),
+        _ => None,
+    }
+}
+
+/// Digits of the given radix, optionally separated by single underscores
+fn is_digit_sequence(digits: &str, radix: u32) -> bool {
+    !digits.is_empty()
+        && digits
+            .split('_')
+            .all(|group| !group.is_empty() && group.chars().all(|ch| ch.is_digit(radix)))
+}
+
+/// Matches `digits ('.' digits?)? (exponent sign? decimal_digits)?`, where the exponent marker
+/// is `e` for decimal floats and `p` for hexadecimal floats
+fn is_float(literal: &str, radix: u32) -> bool {
+    let exponent_markers: &[char] = if radix == 16 {
+        &['p', 'P']
+    } else {
+        &['e', 'E']
+    };
+
+    let (mantissa, exponent) = match literal.split_once(exponent_markers) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (literal, None),
+    };
+
+    let mantissa_valid = match mantissa.split_once('.') {
+        Some((whole, fraction)) => {
+            is_digit_sequence(whole, radix)
+                && (fraction.is_empty() || is_digit_sequence(fraction, radix))
+        }
+        None => is_digit_sequence(mantissa, radix),
+    };
+
+    let exponent_valid = exponent.is_none_or(|exponent| {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+
+        is_digit_sequence(exponent, 10)
+    });
+
+    mantissa_valid && exponent_valid
 }
 
 #[cfg(test)]
@@ -1,3 +1,2 @@
-pub mod boxable;
 pub mod error_display;
 pub mod page_position;